@@ -0,0 +1,64 @@
+use std::fmt;
+
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+
+use crate::persistence::error::PersistenceError;
+
+/// Why a request failed; decides the HTTP status. Sent to the client as
+/// `{"error": "..."}`.
+#[derive(Debug)]
+pub enum ApiError {
+    /// Invalid body or a write the database rejected (400)
+    BadRequest(String),
+    /// The addressed item or endpoint doesn't exist (404)
+    NotFound(String),
+    /// A well-formed change that can't be made, such as a parent cycle (422)
+    Unprocessable(String),
+    /// Anything else, e.g. an unreadable database (500)
+    Internal(String),
+}
+
+impl ApiError {
+    pub fn status(&self) -> StatusCode {
+        match self {
+            ApiError::BadRequest(_) => StatusCode::BAD_REQUEST,
+            ApiError::NotFound(_) => StatusCode::NOT_FOUND,
+            ApiError::Unprocessable(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            ApiError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ApiError::BadRequest(msg) | ApiError::NotFound(msg) | ApiError::Unprocessable(msg) | ApiError::Internal(msg) => {
+                write!(f, "{}", msg)
+            }
+        }
+    }
+}
+
+impl From<PersistenceError> for ApiError {
+    fn from(e: PersistenceError) -> Self {
+        if let PersistenceError::InvalidParent { .. } | PersistenceError::DateOutOfRange = e {
+            return ApiError::Unprocessable(e.to_string());
+        }
+        // e.g. a project_id that doesn't exist
+        if let PersistenceError::Sqlite(rusqlite::Error::SqliteFailure(failure, _)) = &e
+            && failure.code == rusqlite::ErrorCode::ConstraintViolation
+        {
+            return ApiError::BadRequest(e.to_string());
+        }
+        ApiError::Internal(e.to_string())
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let body = serde_json::json!({ "error": self.to_string() });
+        (self.status(), Json(body)).into_response()
+    }
+}
@@ -0,0 +1,4 @@
+pub mod error;
+pub mod resource;
+pub mod routes;
+pub mod server;
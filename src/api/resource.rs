@@ -0,0 +1,151 @@
+use std::collections::HashMap;
+
+use axum::Router;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::api::error::ApiError;
+use crate::api::routes::routes;
+use crate::persistence::filter::parser;
+use crate::persistence::persistence::{Persistable, Persistence, Task};
+use crate::persistence::project::Project;
+use crate::persistence::query::TaskQuery;
+use crate::persistence::tag::Tag;
+
+/// Every resource the API serves; `server::router` mounts CRUD routes for
+/// each. A new entity type implements `ApiResource` and is added here.
+pub const RESOURCES: [fn() -> Router; 3] = [routes::<Task>, routes::<Project>, routes::<Tag>];
+
+/// A `Persistable` served by the HTTP API under `PATH`, once listed in
+/// `RESOURCES`.
+pub trait ApiResource: Persistable + Serialize + DeserializeOwned + Send + 'static {
+    /// Collection path, e.g. `/tasks`; items live at `/tasks/{id}`
+    const PATH: &'static str;
+
+    /// Items for `GET PATH`. `params` is the query string; overridden by
+    /// resources that can be filtered.
+    fn list(persistence: &Persistence, _params: &HashMap<String, String>) -> Result<Vec<Self>, ApiError> {
+        Ok(persistence.get_all::<Self>()?)
+    }
+
+    /// Checks and normalises an item before it is inserted or updated.
+    fn prepare(&mut self, _persistence: &Persistence) -> Result<(), ApiError> {
+        Ok(())
+    }
+
+    /// Inserts a new item, returning its id. Overridden by resources whose
+    /// writes are logged for undo.
+    fn write_insert(&self, persistence: &Persistence) -> Result<Option<i64>, ApiError> {
+        Ok(persistence.save(self)?)
+    }
+
+    /// Writes an existing item back. Overridden where an update has side
+    /// effects beyond the row itself.
+    fn write_update(&self, persistence: &Persistence) -> Result<(), ApiError> {
+        persistence.update(self)?;
+        Ok(())
+    }
+
+    /// Deletes the item. Overridden where deleting it changes tasks, so the
+    /// change is logged for undo.
+    fn write_delete(&self, persistence: &Persistence) -> Result<(), ApiError> {
+        if let Some(id) = self.id() {
+            persistence.delete::<Self>(id)?;
+        }
+        Ok(())
+    }
+
+    /// Refuses deleting items that must stay, such as the Inbox.
+    fn check_delete(&self) -> Result<(), ApiError> {
+        Ok(())
+    }
+}
+
+fn required(name: &mut String, what: &str) -> Result<(), ApiError> {
+    *name = name.trim().to_string();
+    if name.is_empty() {
+        return Err(ApiError::BadRequest(format!("{} cannot be empty", what)));
+    }
+    Ok(())
+}
+
+impl ApiResource for Task {
+    const PATH: &'static str = "/tasks";
+
+    /// `?q=` takes a filter expression, e.g. `?q=tag:work due<7d`.
+    fn list(persistence: &Persistence, params: &HashMap<String, String>) -> Result<Vec<Self>, ApiError> {
+        let filter = match params.get("q") {
+            Some(q) => parser::parse(q).map_err(|e| ApiError::BadRequest(e.to_string()))?,
+            None => None,
+        };
+        match filter {
+            Some(filter) => Ok(persistence.tasks(&TaskQuery { filter: Some(filter), ..TaskQuery::default() })?),
+            None => Ok(persistence.get_all::<Task>()?),
+        }
+    }
+
+    fn prepare(&mut self, persistence: &Persistence) -> Result<(), ApiError> {
+        required(&mut self.title, "Title")?;
+        self.tags = Tag::parse_list(&self.tags.join(","));
+        if let Some(parent_id) = self.parent_id {
+            let parent = persistence
+                .get::<Task>(parent_id)?
+                .ok_or_else(|| ApiError::BadRequest(format!("No task with id {}", parent_id)))?;
+            // neither the task itself nor one of its subtasks
+            persistence.check_parent(self.id, parent_id)?;
+            // subtasks live in their parent's project
+            self.project_id = parent.project_id;
+        }
+        Ok(())
+    }
+
+    fn write_insert(&self, persistence: &Persistence) -> Result<Option<i64>, ApiError> {
+        Ok(persistence.add_logged(self)?)
+    }
+
+    /// Completing a recurring task creates its next occurrence, as in the UI.
+    fn write_update(&self, persistence: &Persistence) -> Result<(), ApiError> {
+        let was_completed = self.id.map(|id| persistence.get::<Task>(id)).transpose()?.flatten().map(|t| t.completed);
+        if self.completed && was_completed == Some(false) {
+            persistence.set_completed_logged(self, true, false)?;
+        } else {
+            persistence.update_logged(self, "edit")?;
+        }
+        Ok(())
+    }
+
+    fn write_delete(&self, persistence: &Persistence) -> Result<(), ApiError> {
+        Ok(persistence.delete_logged(self)?)
+    }
+}
+
+impl ApiResource for Project {
+    const PATH: &'static str = "/projects";
+
+    fn prepare(&mut self, _persistence: &Persistence) -> Result<(), ApiError> {
+        required(&mut self.name, "Name")
+    }
+
+    fn check_delete(&self) -> Result<(), ApiError> {
+        if self.is_inbox() {
+            return Err(ApiError::BadRequest("The Inbox cannot be deleted".to_string()));
+        }
+        Ok(())
+    }
+
+    fn write_delete(&self, persistence: &Persistence) -> Result<(), ApiError> {
+        Ok(persistence.delete_project_logged(self)?)
+    }
+}
+
+impl ApiResource for Tag {
+    const PATH: &'static str = "/tags";
+
+    fn prepare(&mut self, _persistence: &Persistence) -> Result<(), ApiError> {
+        required(&mut self.name, "Name")
+    }
+
+    fn write_delete(&self, persistence: &Persistence) -> Result<(), ApiError> {
+        Ok(persistence.delete_tag_logged(self)?)
+    }
+}
@@ -0,0 +1,118 @@
+use std::collections::HashMap;
+
+use axum::extract::{Path, Query};
+use axum::http::StatusCode;
+use axum::routing::get;
+use axum::{Json, Router};
+use serde_json::Value;
+
+use crate::api::error::ApiError;
+use crate::api::resource::ApiResource;
+use crate::persistence::persistence::Persistence;
+
+/// CRUD endpoints for `T`:
+///
+/// - `GET PATH` lists every item (or those matching the query string, see
+///   `ApiResource::list`), `POST PATH` creates one
+/// - `GET PATH/{id}` reads one, `PUT` replaces it, `PATCH` changes only the
+///   fields in the body, `DELETE` removes it
+pub fn routes<T: ApiResource>() -> Router {
+    Router::new()
+        .route(T::PATH, get(list::<T>).post(create::<T>))
+        .route(
+            &format!("{}/{{id}}", T::PATH),
+            get(read::<T>).put(replace::<T>).patch(patch::<T>).delete(remove::<T>),
+        )
+}
+
+/// Each request gets its own connection, so writes from the UI or CLI are
+/// always seen.
+fn open() -> Result<Persistence, ApiError> {
+    let persistence = Persistence::new()?;
+    persistence.sync_schema()?;
+    Ok(persistence)
+}
+
+/// Runs `work` on a connection of its own on tokio's blocking pool, so
+/// SQLite calls don't hold up the threads serving other requests.
+async fn blocking<R: Send + 'static>(
+    work: impl FnOnce(&Persistence) -> Result<R, ApiError> + Send + 'static,
+) -> Result<R, ApiError> {
+    tokio::task::spawn_blocking(move || work(&open()?))
+        .await
+        .map_err(|e| ApiError::Internal(e.to_string()))?
+}
+
+fn find<T: ApiResource>(persistence: &Persistence, id: i64) -> Result<T, ApiError> {
+    persistence
+        .get::<T>(id)?
+        .ok_or_else(|| ApiError::NotFound(format!("No item with id {} in {}", id, T::PATH)))
+}
+
+/// Builds an item from a JSON object, forcing its id to `id`.
+fn from_json<T: ApiResource>(mut body: Value, id: Option<i64>) -> Result<T, ApiError> {
+    let Some(fields) = body.as_object_mut() else {
+        return Err(ApiError::BadRequest("Expected a JSON object".to_string()));
+    };
+    fields.insert("id".to_string(), id.into());
+    serde_json::from_value(body).map_err(|e| ApiError::BadRequest(e.to_string()))
+}
+
+/// Validates and writes `item`, then answers with it as stored.
+fn store<T: ApiResource>(persistence: &Persistence, mut item: T) -> Result<Json<T>, ApiError> {
+    item.prepare(persistence)?;
+    let id = match item.id() {
+        Some(id) => {
+            item.write_update(persistence)?;
+            id
+        }
+        None => item
+            .write_insert(persistence)?
+            .ok_or_else(|| ApiError::Internal("Insert returned no id".to_string()))?,
+    };
+    Ok(Json(find::<T>(persistence, id)?))
+}
+
+async fn list<T: ApiResource>(Query(params): Query<HashMap<String, String>>) -> Result<Json<Vec<T>>, ApiError> {
+    Ok(Json(blocking(move |persistence| T::list(persistence, &params)).await?))
+}
+
+async fn read<T: ApiResource>(Path(id): Path<i64>) -> Result<Json<T>, ApiError> {
+    Ok(Json(blocking(move |persistence| find::<T>(persistence, id)).await?))
+}
+
+async fn create<T: ApiResource>(Json(body): Json<Value>) -> Result<(StatusCode, Json<T>), ApiError> {
+    let item = from_json::<T>(body, None)?;
+    Ok((StatusCode::CREATED, blocking(move |persistence| store(persistence, item)).await?))
+}
+
+async fn replace<T: ApiResource>(Path(id): Path<i64>, Json(body): Json<Value>) -> Result<Json<T>, ApiError> {
+    blocking(move |persistence| {
+        find::<T>(persistence, id)?;
+        store(persistence, from_json::<T>(body, Some(id))?)
+    })
+    .await
+}
+
+async fn patch<T: ApiResource>(Path(id): Path<i64>, Json(body): Json<Value>) -> Result<Json<T>, ApiError> {
+    blocking(move |persistence| {
+        let mut merged = serde_json::to_value(find::<T>(persistence, id)?)
+            .map_err(|e| ApiError::Internal(e.to_string()))?;
+        let (Some(fields), Value::Object(changes)) = (merged.as_object_mut(), body) else {
+            return Err(ApiError::BadRequest("Expected a JSON object".to_string()));
+        };
+        fields.extend(changes);
+        store(persistence, from_json::<T>(merged, Some(id))?)
+    })
+    .await
+}
+
+async fn remove<T: ApiResource>(Path(id): Path<i64>) -> Result<StatusCode, ApiError> {
+    blocking(move |persistence| {
+        let item = find::<T>(persistence, id)?;
+        item.check_delete()?;
+        item.write_delete(persistence)
+    })
+    .await?;
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,30 @@
+use std::net::{Ipv4Addr, SocketAddr};
+
+use axum::Router;
+
+use crate::api::error::ApiError;
+use crate::api::resource::RESOURCES;
+
+pub const DEFAULT_PORT: u16 = 7410;
+
+pub fn router() -> Router {
+    RESOURCES
+        .iter()
+        .fold(Router::new(), |router, routes| router.merge(routes()))
+        .fallback(|| async { ApiError::NotFound("No such endpoint".to_string()) })
+}
+
+/// Serves the API on `127.0.0.1:port` until Ctrl-C.
+pub fn run(port: u16) -> std::io::Result<()> {
+    let runtime = tokio::runtime::Runtime::new()?;
+    runtime.block_on(async {
+        let bind = SocketAddr::from((Ipv4Addr::LOCALHOST, port));
+        let listener = tokio::net::TcpListener::bind(bind).await?;
+        eprintln!("API listening on http://{} (Ctrl-C to stop)", bind);
+        axum::serve(listener, router())
+            .with_graceful_shutdown(async {
+                let _ = tokio::signal::ctrl_c().await;
+            })
+            .await
+    })
+}
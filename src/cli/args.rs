@@ -0,0 +1,106 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::persistence::error::PersistenceError;
+
+/// Why a subcommand failed; decides the process exit code.
+#[derive(Debug)]
+pub enum CliError {
+    /// Bad arguments or unknown command (exit code 2)
+    Usage(String),
+    /// The referenced task or project doesn't exist (exit code 3)
+    NotFound(String),
+    /// Anything else, e.g. a database failure (exit code 1)
+    Failed(String),
+}
+
+impl CliError {
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            CliError::Failed(_) => 1,
+            CliError::Usage(_) => 2,
+            CliError::NotFound(_) => 3,
+        }
+    }
+}
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CliError::Usage(msg) | CliError::NotFound(msg) | CliError::Failed(msg) => write!(f, "{}", msg),
+        }
+    }
+}
+
+impl From<PersistenceError> for CliError {
+    fn from(e: PersistenceError) -> Self {
+        CliError::Failed(e.to_string())
+    }
+}
+
+/// Arguments of a subcommand: positionals, `--name value` options and
+/// `--name` switches.
+pub struct Args {
+    pub positional: Vec<String>,
+    options: HashMap<String, String>,
+    switches: Vec<String>,
+}
+
+impl Args {
+    /// Splits `raw` into positionals and flags. `options` lists the flags
+    /// that take a value, `switches` those that don't; anything else
+    /// starting with `--` is rejected.
+    pub fn parse(raw: &[String], options: &[&str], switches: &[&str]) -> Result<Args, CliError> {
+        let mut args = Args {
+            positional: Vec::new(),
+            options: HashMap::new(),
+            switches: Vec::new(),
+        };
+        let mut iter = raw.iter();
+        while let Some(arg) = iter.next() {
+            let Some(name) = arg.strip_prefix("--") else {
+                args.positional.push(arg.clone());
+                continue;
+            };
+            // allow --name=value as well as --name value
+            let (name, inline) = match name.split_once('=') {
+                Some((name, value)) => (name, Some(value.to_string())),
+                None => (name, None),
+            };
+            if options.contains(&name) {
+                let value = match inline {
+                    Some(value) => value,
+                    None => iter
+                        .next()
+                        .cloned()
+                        .ok_or_else(|| CliError::Usage(format!("--{} needs a value", name)))?,
+                };
+                args.options.insert(name.to_string(), value);
+            } else if switches.contains(&name) && inline.is_none() {
+                args.switches.push(name.to_string());
+            } else {
+                return Err(CliError::Usage(format!("Unknown option --{}", name)));
+            }
+        }
+        Ok(args)
+    }
+
+    pub fn option(&self, name: &str) -> Option<&str> {
+        self.options.get(name).map(String::as_str)
+    }
+
+    pub fn switch(&self, name: &str) -> bool {
+        self.switches.iter().any(|s| s == name)
+    }
+
+    /// The single positional argument parsed as a task id.
+    pub fn id(&self) -> Result<i64, CliError> {
+        match self.positional.as_slice() {
+            [id] => id
+                .parse()
+                .map_err(|_| CliError::Usage(format!("'{}' is not a task id", id))),
+            [] => Err(CliError::Usage("Missing task id".to_string())),
+            _ => Err(CliError::Usage("Expected a single task id".to_string())),
+        }
+    }
+}
@@ -0,0 +1,473 @@
+use std::io::Read;
+use std::path::Path;
+
+use crate::cli::args::{Args, CliError};
+use crate::exchange::csv::{ColumnMap, Table};
+use crate::exchange::dataset::{Dataset, ImportMode};
+use crate::exchange::error::ExchangeError;
+use crate::exchange::format::Format;
+use crate::persistence::error::PersistenceError;
+use crate::persistence::filter::ast::Expr;
+use crate::persistence::filter::parser::parse;
+use crate::persistence::persistence::{Persistence, Priority, Task};
+use crate::persistence::project::Project;
+use crate::persistence::query::{Sort, Status, TaskQuery};
+use crate::persistence::recurrence::Recurrence;
+use crate::persistence::tag::Tag;
+
+const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+const USAGE: &str = "\
+Usage: todo [COMMAND]
+
+Without a command the interactive UI is started.
+
+Commands:
+  add <title> [options]     Add a task
+  list [FILTER...] [--all] [--project NAME] [--format FORMAT]
+                            List open tasks matching FILTER, e.g.
+                            'tag:work due<7d' (--all, or a FILTER on
+                            status, includes completed ones); FORMAT is
+                            text (default), json or ndjson
+  search <FILTER...> [--all] [--project NAME] [--format FORMAT]
+                            Like list, but the FILTER is required and the
+                            tasks best matching its words come first
+  done <id>                 Mark a task complete
+  reopen <id>               Mark a task open again
+  edit <id> [options]       Change a task (--title plus the options below)
+  rm <id>                   Delete a task and its subtasks
+  export [--format F] [--output FILE]
+                            Write all projects, tags and tasks to FILE
+                            (default: stdout); F is json, todotxt, ics,
+                            csv or markdown, by default picked by FILE's
+                            extension, else json
+  import <FILE> [--format F] [--replace] [--dry-run]
+                            Merge FILE ('-' for stdin) into the database;
+                            --replace makes it an exact copy of FILE and
+                            --dry-run only reports what would change; F
+                            can also be taskwarrior (a 'task export' file)
+         [--columns MAP] [--skip-invalid]
+                            For CSV: MAP picks the column of each field,
+                            e.g. title=Task,due=3,tags=- (header, number or
+                            - for none); --skip-invalid imports the valid
+                            rows when others fail
+  mcp [--port N] [--stdio]  Serve the tasks to MCP clients over SSE on
+                            localhost (default port 7420), or over
+                            stdin/stdout with --stdio
+  serve [--port N]          Serve a REST/JSON API for tasks, projects and
+                            tags on localhost (default port 7410)
+  help                      Show this help
+
+Task options:
+  --desc TEXT               Description
+  --due DATE                YYYY-MM-DD [HH:MM], today or tomorrow
+  --priority LEVEL          none, low, medium, high or urgent
+  --project NAME            Project (default: Inbox)
+  --tags a,b                Comma-separated tags
+  --repeat RULE             daily, weekly:mon,fri, monthly:15 or after:3
+  --parent ID               Make the task a subtask of ID
+  Pass an empty value (e.g. --due \"\") to clear a field when editing.
+
+Exit codes: 0 success, 1 failure, 2 usage error, 3 task or project not found
+";
+
+/// Options shared by `add` and `edit`.
+const TASK_OPTIONS: [&str; 7] = ["desc", "due", "priority", "project", "tags", "repeat", "parent"];
+
+/// Runs a non-interactive subcommand and returns the process exit code.
+pub fn run(raw: &[String]) -> i32 {
+    match dispatch(raw) {
+        Ok(()) => 0,
+        Err(e) => {
+            eprintln!("todo: {}", e);
+            if let CliError::Usage(_) = e {
+                eprintln!("Run 'todo help' for usage.");
+            }
+            e.exit_code()
+        }
+    }
+}
+
+fn dispatch(raw: &[String]) -> Result<(), CliError> {
+    let Some((command, rest)) = raw.split_first() else {
+        return Err(CliError::Usage("Missing command".to_string()));
+    };
+    match command.as_str() {
+        "add" => add(rest),
+        "list" | "ls" => list(rest),
+        "search" => search(rest),
+        "done" => set_completed(rest, true),
+        "reopen" => set_completed(rest, false),
+        "edit" => edit(rest),
+        "rm" => remove(rest),
+        "export" => export(rest),
+        "import" => import(rest),
+        "mcp" => mcp(rest),
+        "serve" => serve(rest),
+        "help" | "--help" | "-h" => {
+            print!("{}", USAGE);
+            Ok(())
+        }
+        "--version" | "-V" => {
+            println!("todo {}", VERSION);
+            Ok(())
+        }
+        other => Err(CliError::Usage(format!("Unknown command '{}'", other))),
+    }
+}
+
+fn open() -> Result<Persistence, CliError> {
+    let persistence = Persistence::new()?;
+    persistence.sync_schema()?;
+    Ok(persistence)
+}
+
+fn find_task(persistence: &Persistence, id: i64) -> Result<Task, CliError> {
+    persistence
+        .get::<Task>(id)?
+        .ok_or_else(|| CliError::NotFound(format!("No task with id {}", id)))
+}
+
+fn find_project(persistence: &Persistence, name: &str) -> Result<Project, CliError> {
+    persistence
+        .get_all::<Project>()?
+        .into_iter()
+        .find(|p| p.name.eq_ignore_ascii_case(name.trim()))
+        .ok_or_else(|| CliError::NotFound(format!("No project named '{}'", name.trim())))
+}
+
+/// Applies the shared task options present in `args` onto `task`.
+fn apply_options(persistence: &Persistence, args: &Args, task: &mut Task) -> Result<(), CliError> {
+    let non_empty = |value: &str| Some(value.trim()).filter(|v| !v.is_empty()).map(str::to_string);
+
+    if let Some(desc) = args.option("desc") {
+        task.description = non_empty(desc);
+    }
+    if let Some(due) = args.option("due") {
+        task.due = non_empty(due)
+            .map(|d| Task::parse_due(&d))
+            .transpose()
+            .map_err(CliError::Usage)?;
+    }
+    if let Some(priority) = args.option("priority") {
+        task.priority = priority.parse::<Priority>().map_err(CliError::Usage)?;
+    }
+    if let Some(project) = args.option("project") {
+        task.project_id = find_project(persistence, project)?.id;
+    }
+    if let Some(tags) = args.option("tags") {
+        task.tags = Tag::parse_list(tags);
+    }
+    if let Some(repeat) = args.option("repeat") {
+        task.recurrence = non_empty(repeat)
+            .map(|r| r.parse::<Recurrence>())
+            .transpose()
+            .map_err(CliError::Usage)?;
+    }
+    if let Some(parent) = args.option("parent") {
+        task.parent_id = match non_empty(parent) {
+            None => None,
+            Some(id) => {
+                let id = id.parse().map_err(|_| CliError::Usage(format!("'{}' is not a task id", id)))?;
+                let parent = find_task(persistence, id)?;
+                // neither the task itself nor one of its subtasks
+                persistence.check_parent(task.id, id).map_err(|e| match e {
+                    PersistenceError::InvalidParent { .. } => CliError::Usage(e.to_string()),
+                    e => e.into(),
+                })?;
+                // subtasks live in their parent's project
+                task.project_id = parent.project_id;
+                parent.id
+            }
+        };
+    }
+    Ok(())
+}
+
+fn add(raw: &[String]) -> Result<(), CliError> {
+    let args = Args::parse(raw, &TASK_OPTIONS, &[])?;
+    let title = args.positional.join(" ").trim().to_string();
+    if title.is_empty() {
+        return Err(CliError::Usage("Missing task title".to_string()));
+    }
+
+    let persistence = open()?;
+    let mut task = Task { title, ..Default::default() };
+    apply_options(&persistence, &args, &mut task)?;
+    let id = persistence.add_logged(&task)?.unwrap_or_default();
+    println!("Added task {}", id);
+    Ok(())
+}
+
+fn list(raw: &[String]) -> Result<(), CliError> {
+    let args = Args::parse(raw, &["project", "format"], &["all"])?;
+    let format = list_format(&args)?;
+    let filter = list_filter(&args)?;
+
+    let persistence = open()?;
+    let query = list_query(&persistence, &args, filter)?;
+    print_tasks(&persistence, &persistence.tasks(&query)?, format)
+}
+
+fn search(raw: &[String]) -> Result<(), CliError> {
+    let args = Args::parse(raw, &["project", "format"], &["all"])?;
+    let format = list_format(&args)?;
+    let filter = list_filter(&args)?;
+    if filter.is_none() {
+        return Err(CliError::Usage("Missing search words".to_string()));
+    }
+
+    let persistence = open()?;
+    let query = TaskQuery { sort: Sort::Relevance, ..list_query(&persistence, &args, filter)? };
+    print_tasks(&persistence, &persistence.tasks(&query)?, format)
+}
+
+/// The filter expression given as the positional arguments of `list` and
+/// `search`, if any.
+fn list_filter(args: &Args) -> Result<Option<Expr>, CliError> {
+    let input = args.positional.join(" ");
+    parse(&input).map_err(|e| {
+        let pointer = e.pointer(&input).lines().map(|l| format!("    {}", l)).collect::<Vec<_>>().join("\n");
+        CliError::Usage(format!("{}\n\n{}\n", e, pointer))
+    })
+}
+
+/// The tasks picked by `filter` and the `--project` and `--all` options of
+/// `list` and `search`. Open tasks only, unless `--all` is given or the
+/// filter is about completion.
+fn list_query(persistence: &Persistence, args: &Args, filter: Option<Expr>) -> Result<TaskQuery, CliError> {
+    let project_id = args.option("project").map(|name| find_project(persistence, name)).transpose()?.and_then(|p| p.id);
+    let status = if args.switch("all") || filter.as_ref().is_some_and(Expr::mentions_status) {
+        Status::All
+    } else {
+        Status::Open
+    };
+    Ok(TaskQuery { project_id, status, filter, ..TaskQuery::default() })
+}
+
+/// The `--format` of `list` and `search`.
+fn list_format(args: &Args) -> Result<&str, CliError> {
+    let format = args.option("format").unwrap_or("text");
+    if !["text", "json", "ndjson"].contains(&format) {
+        return Err(CliError::Usage(format!("Unknown format '{}' (use text, json or ndjson)", format)));
+    }
+    Ok(format)
+}
+
+fn print_tasks(persistence: &Persistence, tasks: &[Task], format: &str) -> Result<(), CliError> {
+    let to_json = |e: serde_json::Error| CliError::Failed(e.to_string());
+    match format {
+        "json" => println!("{}", serde_json::to_string_pretty(&tasks).map_err(to_json)?),
+        "ndjson" => {
+            for task in tasks {
+                println!("{}", serde_json::to_string(task).map_err(to_json)?);
+            }
+        }
+        _ => {
+            let projects = persistence.get_all::<Project>()?;
+            for task in tasks {
+                println!("{}", format_task(task, &projects));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// One line per task: `  12 [ ] Title  !high  due:2026-03-01  +Project  #tag`
+fn format_task(task: &Task, projects: &[Project]) -> String {
+    let mut line = format!(
+        "{:>4} [{}] {}",
+        task.id.unwrap_or_default(),
+        if task.completed { "x" } else { " " },
+        task.title
+    );
+    if task.priority != Priority::None {
+        line.push_str(&format!("  !{}", task.priority.label()));
+    }
+    if let Some(due) = &task.due {
+        line.push_str(&format!("  due:{}", Task::format_due(due).replace(' ', "T")));
+    }
+    if let Some(rule) = &task.recurrence {
+        line.push_str(&format!("  repeat:{}", rule));
+    }
+    if let Some(parent) = task.parent_id {
+        line.push_str(&format!("  parent:{}", parent));
+    }
+    if let Some(project) = projects.iter().find(|p| p.id == task.project_id) {
+        line.push_str(&format!("  +{}", project.name.replace(' ', "_")));
+    }
+    for tag in &task.tags {
+        line.push_str(&format!("  #{}", tag));
+    }
+    line
+}
+
+fn set_completed(raw: &[String], completed: bool) -> Result<(), CliError> {
+    let id = Args::parse(raw, &[], &[])?.id()?;
+    let persistence = open()?;
+    let task = find_task(&persistence, id)?;
+
+    let next = persistence.set_completed_logged(&task, completed, false)?;
+    if completed {
+        println!("Completed task {}", id);
+    } else {
+        println!("Reopened task {}", id);
+    }
+    if let Some(next) = next.map(|next| find_task(&persistence, next)).transpose()? {
+        let due = next.due.as_ref().map(Task::format_due).unwrap_or_default();
+        println!("Next occurrence: task {} due {}", next.id.unwrap_or_default(), due);
+    }
+    Ok(())
+}
+
+fn edit(raw: &[String]) -> Result<(), CliError> {
+    let mut options = TASK_OPTIONS.to_vec();
+    options.push("title");
+    let args = Args::parse(raw, &options, &[])?;
+    let id = args.id()?;
+    if options.iter().all(|o| args.option(o).is_none()) {
+        return Err(CliError::Usage("Nothing to change; pass at least one option".to_string()));
+    }
+
+    let persistence = open()?;
+    let mut task = find_task(&persistence, id)?;
+    if let Some(title) = args.option("title") {
+        if title.trim().is_empty() {
+            return Err(CliError::Usage("Title cannot be empty".to_string()));
+        }
+        task.title = title.trim().to_string();
+    }
+    apply_options(&persistence, &args, &mut task)?;
+    persistence.update_logged(&task, "edit")?;
+    println!("Updated task {}", id);
+    Ok(())
+}
+
+fn remove(raw: &[String]) -> Result<(), CliError> {
+    let id = Args::parse(raw, &[], &[])?.id()?;
+    let persistence = open()?;
+    let task = find_task(&persistence, id)?;
+    persistence.delete_logged(&task)?;
+    println!("Deleted task {}", id);
+    Ok(())
+}
+
+/// The `--format` option, else the format `path`'s extension selects,
+/// defaulting to JSON.
+fn format(args: &Args, path: Option<&str>) -> Result<Format, CliError> {
+    match args.option("format") {
+        Some(format) => format.parse().map_err(CliError::Usage),
+        None => Ok(path.and_then(|p| Format::from_path(Path::new(p))).unwrap_or(Format::Json)),
+    }
+}
+
+fn export(raw: &[String]) -> Result<(), CliError> {
+    let args = Args::parse(raw, &["format", "output"], &[])?;
+    if !args.positional.is_empty() {
+        return Err(CliError::Usage("export takes no positional arguments".to_string()));
+    }
+    let format = format(&args, args.option("output"))?;
+    let persistence = open()?;
+    let text = format.export(&persistence).map_err(|e| CliError::Failed(e.to_string()))?;
+    match args.option("output") {
+        Some(path) => {
+            std::fs::write(path, text).map_err(|e| CliError::Failed(format!("Cannot write {}: {}", path, e)))?;
+            eprintln!("Exported to {}", path);
+        }
+        None => println!("{}", text.trim_end()),
+    }
+    Ok(())
+}
+
+/// The tasks in a CSV file, read with the `--columns` mapping. Invalid
+/// rows are listed on stderr and fail the import unless `--skip-invalid`.
+fn csv_rows(args: &Args, input: &str) -> Result<Dataset, CliError> {
+    let failed = |e: ExchangeError| CliError::Failed(e.to_string());
+    let table = Table::read(input).map_err(failed)?;
+    let mut map = ColumnMap::detect(&table.headers);
+    if let Some(spec) = args.option("columns") {
+        map.apply(spec, &table.headers).map_err(CliError::Usage)?;
+    }
+    let (data, errors) = table.tasks(&map).map_err(failed)?;
+    if errors.is_empty() {
+        return Ok(data);
+    }
+    if !args.switch("skip-invalid") {
+        return Err(failed(ExchangeError::InvalidRows(errors)));
+    }
+    for error in &errors {
+        eprintln!("Skipped {}", error);
+    }
+    Ok(data)
+}
+
+fn import(raw: &[String]) -> Result<(), CliError> {
+    let args = Args::parse(raw, &["format", "columns"], &["replace", "dry-run", "skip-invalid"])?;
+    let [path] = args.positional.as_slice() else {
+        return Err(CliError::Usage("Expected the file to import ('-' for stdin)".to_string()));
+    };
+    let format = format(&args, Some(path))?;
+    let input = if path == "-" {
+        let mut input = String::new();
+        std::io::stdin()
+            .read_to_string(&mut input)
+            .map_err(|e| CliError::Failed(format!("Cannot read stdin: {}", e)))?;
+        input
+    } else {
+        std::fs::read_to_string(path).map_err(|e| CliError::NotFound(format!("Cannot read {}: {}", path, e)))?
+    };
+    let mode = if args.switch("replace") { ImportMode::Replace } else { ImportMode::Merge };
+    let dry_run = args.switch("dry-run");
+
+    let data = match format {
+        Format::Csv => csv_rows(&args, &input)?,
+        _ if args.option("columns").is_some() || args.switch("skip-invalid") => {
+            return Err(CliError::Usage("--columns and --skip-invalid only apply to CSV".to_string()));
+        }
+        _ => format.parse(&input).map_err(|e| CliError::Failed(e.to_string()))?,
+    };
+
+    let persistence = open()?;
+    let report = data
+        .import(&persistence, mode, dry_run)
+        .map_err(|e| CliError::Failed(e.to_string()))?;
+    if dry_run {
+        println!("Dry run, nothing was changed.");
+    }
+    println!("{}", report);
+    Ok(())
+}
+
+/// The `--port` option, or `default` when it's absent.
+fn port(args: &Args, default: u16) -> Result<u16, CliError> {
+    match args.option("port") {
+        Some(port) => port
+            .parse()
+            .map_err(|_| CliError::Usage(format!("'{}' is not a port number", port))),
+        None => Ok(default),
+    }
+}
+
+fn mcp(raw: &[String]) -> Result<(), CliError> {
+    let args = Args::parse(raw, &["port"], &["stdio"])?;
+    if !args.positional.is_empty() {
+        return Err(CliError::Usage("mcp takes no positional arguments".to_string()));
+    }
+    if args.switch("stdio") {
+        if args.option("port").is_some() {
+            return Err(CliError::Usage("--port cannot be used with --stdio".to_string()));
+        }
+        return crate::mcp::server::run_stdio().map_err(|e| CliError::Failed(e.to_string()));
+    }
+    let port = port(&args, crate::mcp::server::DEFAULT_PORT)?;
+    crate::mcp::server::run_sse(port).map_err(|e| CliError::Failed(e.to_string()))
+}
+
+fn serve(raw: &[String]) -> Result<(), CliError> {
+    let args = Args::parse(raw, &["port"], &[])?;
+    if !args.positional.is_empty() {
+        return Err(CliError::Usage("serve takes no positional arguments".to_string()));
+    }
+    let port = port(&args, crate::api::server::DEFAULT_PORT)?;
+    crate::api::server::run(port).map_err(|e| CliError::Failed(e.to_string()))
+}
@@ -0,0 +1,335 @@
+//! Spreadsheets as CSV: one task per row under a header row.
+//!
+//! Exports have a column for every `Field`, named after it. On import a
+//! `ColumnMap` says which column holds which field; by default columns are
+//! matched by header name (including common aliases such as "Due Date" or
+//! "Notes"). Rows that don't make a valid task are reported as `RowError`s.
+
+use crate::exchange::dataset::Dataset;
+use crate::exchange::error::{ExchangeError, RowError};
+use crate::persistence::persistence::{Priority, Task};
+use crate::persistence::recurrence::Recurrence;
+use crate::persistence::tag::Tag;
+
+/// A task field a column can be imported into.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Field {
+    Title,
+    Description,
+    Completed,
+    Due,
+    Priority,
+    Project,
+    Tags,
+    Repeat,
+}
+
+impl Field {
+    pub const ALL: [Field; 8] = [
+        Field::Title,
+        Field::Description,
+        Field::Completed,
+        Field::Due,
+        Field::Priority,
+        Field::Project,
+        Field::Tags,
+        Field::Repeat,
+    ];
+
+    /// Header of the exported column, also accepted in `ColumnMap::apply`.
+    pub fn name(self) -> &'static str {
+        match self {
+            Field::Title => "title",
+            Field::Description => "description",
+            Field::Completed => "completed",
+            Field::Due => "due",
+            Field::Priority => "priority",
+            Field::Project => "project",
+            Field::Tags => "tags",
+            Field::Repeat => "repeat",
+        }
+    }
+
+    /// Other headers recognised for the field, lowercase.
+    fn aliases(self) -> &'static [&'static str] {
+        match self {
+            Field::Title => &["task", "name", "summary", "subject"],
+            Field::Description => &["notes", "note", "details", "comment", "comments"],
+            Field::Completed => &["done", "status", "complete"],
+            Field::Due => &["due date", "deadline", "due_date"],
+            Field::Priority => &["prio", "importance"],
+            Field::Project => &["list", "category"],
+            Field::Tags => &["tag", "labels", "label", "contexts"],
+            Field::Repeat => &["recurrence", "repeats", "recurring"],
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Field> {
+        let name = name.trim().to_lowercase();
+        Field::ALL.into_iter().find(|f| f.name() == name)
+    }
+}
+
+/// Which column, if any, each field is read from.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColumnMap {
+    columns: [Option<usize>; Field::ALL.len()],
+}
+
+impl ColumnMap {
+    /// Matches headers to fields by name or alias, ignoring case.
+    pub fn detect(headers: &[String]) -> ColumnMap {
+        let mut map = ColumnMap { columns: [None; Field::ALL.len()] };
+        for field in Field::ALL {
+            map.columns[field as usize] = headers.iter().position(|header| {
+                let header = header.trim().to_lowercase();
+                header == field.name() || field.aliases().contains(&header.as_str())
+            });
+        }
+        map
+    }
+
+    /// Overrides the mapping from a spec such as `title=Task,due=3,tags=-`:
+    /// each field is given a header name, a 1-based column number, or `-`
+    /// to leave it out.
+    pub fn apply(&mut self, spec: &str, headers: &[String]) -> Result<(), String> {
+        for pair in spec.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+            let (name, column) = pair
+                .split_once('=')
+                .ok_or_else(|| format!("Invalid column mapping '{}' (use field=column)", pair))?;
+            let field = Field::from_name(name).ok_or_else(|| {
+                let names: Vec<&str> = Field::ALL.iter().map(|f| f.name()).collect();
+                format!("Unknown field '{}' (use {})", name.trim(), names.join(", "))
+            })?;
+            let column = column.trim();
+            self.columns[field as usize] = if column == "-" {
+                None
+            } else if let Some(index) = headers.iter().position(|h| h.trim().eq_ignore_ascii_case(column)) {
+                Some(index)
+            } else {
+                match column.parse::<usize>() {
+                    Ok(n) if (1..=headers.len()).contains(&n) => Some(n - 1),
+                    _ => return Err(format!("No column '{}' (the file has {})", column, headers.join(", "))),
+                }
+            };
+        }
+        Ok(())
+    }
+
+    pub fn get(&self, field: Field) -> Option<usize> {
+        self.columns[field as usize]
+    }
+
+    pub fn set(&mut self, field: Field, column: Option<usize>) {
+        self.columns[field as usize] = column;
+    }
+}
+
+/// A CSV file as read: the header row and the rows below it.
+#[derive(Debug, Clone, Default)]
+pub struct Table {
+    pub headers: Vec<String>,
+    pub rows: Vec<Vec<String>>,
+}
+
+impl Table {
+    pub fn read(input: &str) -> Result<Table, ExchangeError> {
+        let parse_error = |e: ::csv::Error| ExchangeError::Parse(e.to_string());
+        let mut reader = ::csv::ReaderBuilder::new()
+            .flexible(true)
+            .trim(::csv::Trim::All)
+            .from_reader(input.as_bytes());
+        let headers = reader.headers().map_err(parse_error)?.iter().map(str::to_string).collect();
+        let rows = reader
+            .records()
+            .map(|record| record.map(|r| r.iter().map(str::to_string).collect()))
+            .collect::<Result<_, _>>()
+            .map_err(parse_error)?;
+        Ok(Table { headers, rows })
+    }
+
+    /// Turns every row into a task using `map`. Rows that fail are left
+    /// out of the dataset and reported instead.
+    pub fn tasks(&self, map: &ColumnMap) -> Result<(Dataset, Vec<RowError>), ExchangeError> {
+        if map.get(Field::Title).is_none() {
+            return Err(ExchangeError::Parse("no column is mapped to the title".to_string()));
+        }
+        let mut data = Dataset::default();
+        let mut errors = Vec::new();
+        for (i, row) in self.rows.iter().enumerate() {
+            // the header is row 1
+            let number = i + 2;
+            if row.iter().all(String::is_empty) {
+                continue;
+            }
+            match self.task(row, map, &mut data) {
+                Ok(task) => data.tasks.push(task),
+                Err(message) => errors.push(RowError { row: number, message }),
+            }
+        }
+        Ok((data, errors))
+    }
+
+    fn task(&self, row: &[String], map: &ColumnMap, data: &mut Dataset) -> Result<Task, String> {
+        let cell = |field: Field| map.get(field).and_then(|i| row.get(i)).map(String::as_str).filter(|c| !c.is_empty());
+        let title = cell(Field::Title).ok_or("Title is empty")?;
+        let mut task = Task {
+            title: title.split_whitespace().collect::<Vec<_>>().join(" "),
+            description: cell(Field::Description).map(str::to_string),
+            ..Task::default()
+        };
+        if let Some(completed) = cell(Field::Completed) {
+            task.completed = parse_completed(completed)?;
+        }
+        if let Some(due) = cell(Field::Due) {
+            task.due = Some(Task::parse_due(due)?);
+        }
+        if let Some(priority) = cell(Field::Priority) {
+            task.priority = priority.parse()?;
+        }
+        if let Some(rule) = cell(Field::Repeat) {
+            task.recurrence = Some(rule.parse::<Recurrence>()?);
+        }
+        // checked last so a rejected row doesn't leave its project and tags behind
+        if let Some(project) = cell(Field::Project) {
+            task.project_id = data.project_id(project);
+        }
+        if let Some(tags) = cell(Field::Tags) {
+            task.tags = Tag::parse_list(&tags.replace(';', ","));
+            for tag in &task.tags {
+                data.add_tag(tag);
+            }
+        }
+        Ok(task)
+    }
+}
+
+fn parse_completed(input: &str) -> Result<bool, String> {
+    match input.to_lowercase().as_str() {
+        "yes" | "y" | "true" | "1" | "x" | "done" | "completed" | "complete" => Ok(true),
+        "no" | "n" | "false" | "0" | "open" | "todo" | "pending" | "needs-action" => Ok(false),
+        _ => Err(format!("Invalid completed value '{}' (use yes or no)", input)),
+    }
+}
+
+pub fn render(data: &Dataset) -> Result<String, ExchangeError> {
+    let write_error = |e: ::csv::Error| ExchangeError::Parse(e.to_string());
+    let mut writer = ::csv::Writer::from_writer(Vec::new());
+    writer.write_record(Field::ALL.map(Field::name)).map_err(write_error)?;
+    for task in &data.tasks {
+        let project = data.projects.iter().find(|p| p.id == task.project_id && !p.is_inbox());
+        writer
+            .write_record([
+                task.title.clone(),
+                task.description.clone().unwrap_or_default(),
+                if task.completed { "yes" } else { "no" }.to_string(),
+                task.due.as_ref().map(Task::format_due).unwrap_or_default(),
+                Some(task.priority).filter(|p| *p != Priority::None).map(|p| p.label().to_string()).unwrap_or_default(),
+                project.map(|p| p.name.clone()).unwrap_or_default(),
+                task.tags.join(", "),
+                task.recurrence.as_ref().map(Recurrence::to_string).unwrap_or_default(),
+            ])
+            .map_err(write_error)?;
+    }
+    let bytes = writer.into_inner().map_err(|e| ExchangeError::Parse(e.to_string()))?;
+    String::from_utf8(bytes).map_err(|e| ExchangeError::Parse(e.to_string()))
+}
+
+/// Reads a file with the default mapping, failing if any row is invalid.
+pub fn parse(input: &str) -> Result<Dataset, ExchangeError> {
+    let table = Table::read(input)?;
+    let (data, errors) = table.tasks(&ColumnMap::detect(&table.headers))?;
+    if !errors.is_empty() {
+        return Err(ExchangeError::InvalidRows(errors));
+    }
+    Ok(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::persistence::project::Project;
+    use chrono::NaiveDate;
+
+    fn headers(names: &[&str]) -> Vec<String> {
+        names.iter().map(|n| n.to_string()).collect()
+    }
+
+    #[test]
+    fn round_trip_keeps_tasks() {
+        let due = NaiveDate::from_ymd_opt(2026, 3, 1).unwrap().and_time(Task::end_of_day());
+        let data = Dataset {
+            projects: vec![Project { id: Some(2), name: "My Project".to_string(), ..Project::default() }],
+            tasks: vec![
+                Task {
+                    title: "Write report, part 1; \"draft\"".to_string(),
+                    description: Some("Line one\nLine two".to_string()),
+                    due: Some(due),
+                    priority: Priority::High,
+                    project_id: Some(2),
+                    tags: vec!["deep work".to_string(), "office".to_string()],
+                    recurrence: "weekly".parse().ok(),
+                    ..Task::default()
+                },
+                Task { title: "Sources".to_string(), completed: true, ..Task::default() },
+            ],
+            ..Dataset::default()
+        };
+
+        let parsed = parse(&render(&data).unwrap()).unwrap();
+        assert_eq!(parsed.projects.len(), 1);
+        assert_eq!(parsed.projects[0].name, "My Project");
+        let [first, second] = parsed.tasks.as_slice() else { panic!("expected two tasks") };
+        assert_eq!(first.title, data.tasks[0].title);
+        assert_eq!(first.description, data.tasks[0].description);
+        assert_eq!(first.due, Some(due));
+        assert_eq!(first.priority, Priority::High);
+        assert_eq!(first.project_id, parsed.projects[0].id);
+        assert_eq!(first.tags, ["deep work", "office"]);
+        assert_eq!(first.recurrence, data.tasks[0].recurrence);
+        assert!(!first.completed);
+        assert_eq!(second.title, "Sources");
+        assert!(second.completed);
+        assert_eq!(second.project_id, None);
+    }
+
+    #[test]
+    fn columns_are_detected_by_alias_and_overridden_by_spec() {
+        let headers = headers(&["Task", "Due Date", "Notes", "List"]);
+        let mut map = ColumnMap::detect(&headers);
+        assert_eq!(map.get(Field::Title), Some(0));
+        assert_eq!(map.get(Field::Due), Some(1));
+        assert_eq!(map.get(Field::Description), Some(2));
+        assert_eq!(map.get(Field::Project), Some(3));
+        assert_eq!(map.get(Field::Tags), None);
+
+        map.apply("description=-, tags=3, project=list", &headers).unwrap();
+        assert_eq!(map.get(Field::Description), None);
+        assert_eq!(map.get(Field::Tags), Some(2));
+        assert_eq!(map.get(Field::Project), Some(3));
+
+        assert!(map.apply("title", &headers).is_err());
+        assert!(map.apply("owner=1", &headers).is_err());
+        assert!(map.apply("title=5", &headers).is_err());
+    }
+
+    #[test]
+    fn invalid_rows_are_reported_without_leaving_projects_behind() {
+        let input = "title,due,project\nGood,,Home Office\n,,Empty\nBad,someday,Garden Shed\n,,\n";
+        let table = Table::read(input).unwrap();
+        let (data, errors) = table.tasks(&ColumnMap::detect(&table.headers)).unwrap();
+
+        assert_eq!(data.tasks.len(), 1);
+        let names: Vec<&str> = data.projects.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, ["Home Office"]);
+        let rows: Vec<usize> = errors.iter().map(|e| e.row).collect();
+        assert_eq!(rows, [3, 4]);
+        assert!(matches!(parse(input), Err(ExchangeError::InvalidRows(errors)) if errors.len() == 2));
+    }
+
+    #[test]
+    fn a_file_without_a_title_column_is_rejected() {
+        let table = Table::read("due,notes\n2026-03-01,x\n").unwrap();
+        assert!(matches!(table.tasks(&ColumnMap::detect(&table.headers)), Err(ExchangeError::Parse(_))));
+    }
+}
@@ -0,0 +1,360 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::exchange::error::ExchangeError;
+use crate::persistence::error::PersistenceError;
+use crate::persistence::persistence::{Persistence, Task};
+use crate::persistence::project::{Project, INBOX_ID};
+use crate::persistence::tag::Tag;
+
+/// How an import treats what's already in the database.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ImportMode {
+    /// Add new items and update matching ones; nothing is removed
+    Merge,
+    /// Make the database an exact copy of the imported data
+    Replace,
+}
+
+/// What an import changed, or would change in a dry run.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Counts {
+    pub added: usize,
+    pub updated: usize,
+    pub unchanged: usize,
+    pub removed: usize,
+    /// Merged items left alone because the local copy changed more recently
+    pub kept: usize,
+}
+
+impl fmt::Display for Counts {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} added, {} updated, {} removed, {} unchanged",
+            self.added, self.updated, self.removed, self.unchanged
+        )?;
+        if self.kept > 0 {
+            write!(f, ", {} kept (changed more recently here)", self.kept)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ImportReport {
+    pub projects: Counts,
+    pub tags: Counts,
+    pub tasks: Counts,
+    /// Problems the file had that didn't stop the import, see `Dataset::warnings`
+    pub warnings: Vec<String>,
+}
+
+impl fmt::Display for ImportReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Projects: {}", self.projects)?;
+        writeln!(f, "Tags:     {}", self.tags)?;
+        write!(f, "Tasks:    {}", self.tasks)?;
+        for warning in &self.warnings {
+            write!(f, "\nWarning: {}", warning)?;
+        }
+        Ok(())
+    }
+}
+
+/// The whole database: every project, tag and task with its id. Export
+/// formats render it, import formats produce it.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Dataset {
+    pub projects: Vec<Project>,
+    pub tags: Vec<Tag>,
+    pub tasks: Vec<Task>,
+    /// What the parser left out of a file it could otherwise read, e.g. a
+    /// parent link that would make a task its own ancestor
+    #[serde(skip)]
+    pub warnings: Vec<String>,
+}
+
+impl Dataset {
+    /// Reads everything, ordered by id so exports are stable.
+    pub fn load(persistence: &Persistence) -> Result<Dataset, PersistenceError> {
+        let mut data = Dataset {
+            projects: persistence.get_all::<Project>()?,
+            tags: persistence.get_all::<Tag>()?,
+            tasks: persistence.get_all::<Task>()?,
+            warnings: Vec::new(),
+        };
+        data.projects.sort_by_key(|p| p.id);
+        data.tags.sort_by_key(|t| t.id);
+        data.tasks.sort_by_key(|t| t.id);
+        Ok(data)
+    }
+
+    /// Id of the project named `name`, added if it's new. Used by formats
+    /// that name projects instead of numbering them. The Inbox has none:
+    /// tasks without a project land there anyway.
+    pub fn project_id(&mut self, name: &str) -> Option<i64> {
+        if name.eq_ignore_ascii_case("inbox") {
+            return None;
+        }
+        if let Some(project) = self.projects.iter().find(|p| p.name.eq_ignore_ascii_case(name)) {
+            return project.id;
+        }
+        // numbered after the Inbox so a replace import doesn't take its id
+        let id = INBOX_ID + 1 + self.projects.len() as i64;
+        self.projects.push(Project { id: Some(id), name: name.to_string(), ..Project::default() });
+        Some(id)
+    }
+
+    /// Adds a tag named `name` unless there already is one.
+    pub fn add_tag(&mut self, name: &str) {
+        if !self.tags.iter().any(|t| t.name.eq_ignore_ascii_case(name)) {
+            self.tags.push(Tag { name: name.to_string(), ..Tag::default() });
+        }
+    }
+
+    /// Writes the dataset into the database in one transaction. With
+    /// `dry_run` nothing is written and the report says what would change.
+    /// Clears the undo history: imported changes can't be undone.
+    pub fn import(&self, persistence: &Persistence, mode: ImportMode, dry_run: bool) -> Result<ImportReport, ExchangeError> {
+        let run = |persistence: &Persistence| {
+            // references between tasks are only checked once everything is in
+            persistence.execute("PRAGMA defer_foreign_keys = ON", &[])?;
+            // imports aren't logged, so earlier snapshots could no longer be
+            // restored over them
+            persistence.clear_history()?;
+            let report = match mode {
+                ImportMode::Merge => self.merge(persistence)?,
+                ImportMode::Replace => self.replace(persistence)?,
+            };
+            check_foreign_keys(persistence)?;
+            Ok(ImportReport { warnings: self.warnings.clone(), ..report })
+        };
+        if dry_run {
+            persistence.dry_run(run)
+        } else {
+            persistence.transaction(run)
+        }
+    }
+
+    fn replace(&self, persistence: &Persistence) -> Result<ImportReport, PersistenceError> {
+        let existing = Dataset::load(persistence)?;
+        let report = ImportReport {
+            projects: diff(&existing.projects, &self.projects, |p| p.id, |a, b| a.name == b.name),
+            tags: diff(&existing.tags, &self.tags, |t| t.id, |a, b| a.name == b.name),
+            tasks: diff(&existing.tasks, &self.tasks, |t| t.id, same_task),
+            ..ImportReport::default()
+        };
+
+        for table in ["tasks", "tags", "projects"] {
+            persistence.execute(&format!("DELETE FROM {}", table), &[])?;
+        }
+        for project in &self.projects {
+            persistence.save(project)?;
+        }
+        persistence.execute("INSERT OR IGNORE INTO projects (id, name) VALUES (?1, 'Inbox')", &[&INBOX_ID])?;
+        for tag in &self.tags {
+            persistence.save(tag)?;
+        }
+        for task in &self.tasks {
+            persistence.save(task)?;
+        }
+        Ok(report)
+    }
+
+    /// Projects and tags are matched by name. A task matches the local task
+    /// with the same `uid`, or with the same id if both were created at the
+    /// same time; otherwise it is added under a fresh id.
+    fn merge(&self, persistence: &Persistence) -> Result<ImportReport, PersistenceError> {
+        let existing = Dataset::load(persistence)?;
+        let mut report = ImportReport::default();
+
+        let mut project_ids = HashMap::new();
+        for project in &self.projects {
+            let local = existing.projects.iter().find(|p| p.name.eq_ignore_ascii_case(&project.name));
+            let id = match local {
+                Some(local) => {
+                    report.projects.unchanged += 1;
+                    local.id
+                }
+                None => {
+                    report.projects.added += 1;
+                    persistence.save(&Project { id: None, ..project.clone() })?
+                }
+            };
+            project_ids.insert(project.id, id);
+        }
+
+        for tag in &self.tags {
+            if existing.tags.iter().any(|t| t.name.eq_ignore_ascii_case(&tag.name)) {
+                report.tags.unchanged += 1;
+            } else {
+                report.tags.added += 1;
+                persistence.save(&Tag { id: None, ..tag.clone() })?;
+            }
+        }
+
+        // Decide every task's local id up front so references can be
+        // rewritten regardless of the order tasks are written in.
+        let mut next_id = existing.tasks.iter().filter_map(|t| t.id).max().unwrap_or(0);
+        let mut locals = Vec::new();
+        let mut task_ids = HashMap::new();
+        for task in &self.tasks {
+            let local = existing.tasks.iter().find(|t| {
+                (t.uid.is_some() && t.uid == task.uid)
+                    || (t.id.is_some() && t.id == task.id && t.created_at.is_some() && t.created_at == task.created_at)
+            });
+            let id = match local {
+                Some(local) => local.id,
+                None => {
+                    next_id += 1;
+                    Some(next_id)
+                }
+            };
+            if let Some(imported) = task.id {
+                task_ids.insert(imported, id);
+            }
+            locals.push((local, id));
+        }
+        let remap = |id: Option<i64>| id.and_then(|id| task_ids.get(&id).copied().flatten());
+
+        for (task, (local, id)) in self.tasks.iter().zip(locals) {
+            let mut merged = Task {
+                id,
+                project_id: project_ids.get(&task.project_id).copied().flatten().or(Some(INBOX_ID)),
+                parent_id: remap(task.parent_id),
+                occurrence_of: remap(task.occurrence_of),
+                ..task.clone()
+            };
+            // timestamps the file doesn't carry are taken to be the local ones
+            if let Some(local) = local {
+                merged.created_at = merged.created_at.or(local.created_at);
+                if merged.completed {
+                    merged.completed_at = merged.completed_at.or(local.completed_at);
+                }
+            }
+            match local {
+                None => {
+                    report.tasks.added += 1;
+                    persistence.save(&merged)?;
+                }
+                Some(local) if same_task(local, &merged) => report.tasks.unchanged += 1,
+                Some(local) if newer_here(local, &merged) => {
+                    report.tasks.kept += 1;
+                }
+                Some(_) => {
+                    report.tasks.updated += 1;
+                    persistence.update(&merged)?;
+                    // updates stamp completions with the current time; the
+                    // file's own date is kept so merging it again is a no-op
+                    if merged.completed_at.is_some() {
+                        persistence.execute(
+                            "UPDATE tasks SET completed_at = ?1 WHERE id = ?2",
+                            &[&merged.completed_at, &merged.id],
+                        )?;
+                    }
+                }
+            }
+        }
+        Ok(report)
+    }
+}
+
+/// Whether the local copy of a task changed no earlier than the imported
+/// one, so merging keeps it. Formats without modification times import
+/// tasks with no `updated_at`; as they only get here when they differ from
+/// the local task, the file was edited and wins. A local task without one,
+/// which only editing the database by hand leaves, is older than anything
+/// imported.
+fn newer_here(local: &Task, imported: &Task) -> bool {
+    match (local.updated_at, imported.updated_at) {
+        (Some(local), Some(imported)) => local >= imported,
+        (Some(_), None) | (None, _) => false,
+    }
+}
+
+/// Equal apart from `updated_at`, which every write moves.
+fn same_task(a: &Task, b: &Task) -> bool {
+    let strip = |task: &Task| serde_json::to_value(Task { updated_at: None, ..task.clone() }).ok();
+    strip(a) == strip(b)
+}
+
+/// Compares items by id.
+fn diff<T>(existing: &[T], imported: &[T], id: impl Fn(&T) -> Option<i64>, same: impl Fn(&T, &T) -> bool) -> Counts {
+    let mut counts = Counts::default();
+    for item in imported {
+        match existing.iter().find(|e| id(e) == id(item)) {
+            Some(local) if same(local, item) => counts.unchanged += 1,
+            Some(_) => counts.updated += 1,
+            None => counts.added += 1,
+        }
+    }
+    counts.removed = existing.iter().filter(|e| !imported.iter().any(|i| id(i) == id(e))).count();
+    counts
+}
+
+/// Fails if the imported data references tasks or projects that don't exist.
+fn check_foreign_keys(persistence: &Persistence) -> Result<(), ExchangeError> {
+    let Some(conn) = &persistence.connection else { return Ok(()) };
+    let broken: i64 = conn
+        .query_row("SELECT COUNT(*) FROM pragma_foreign_key_check", [], |row| row.get(0))
+        .map_err(PersistenceError::from)?;
+    if broken > 0 {
+        return Err(ExchangeError::Parse(format!(
+            "{} imported rows reference missing tasks or projects",
+            broken
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exchange::todotxt;
+
+    fn import(p: &Persistence, tasks: Vec<Task>, mode: ImportMode) -> ImportReport {
+        Dataset { tasks, ..Dataset::default() }.import(p, mode, false).unwrap()
+    }
+
+    #[test]
+    fn replace_clears_the_undo_history() {
+        let p = Persistence::in_memory();
+        p.add_logged(&Task { title: "Local".to_string(), ..Task::default() }).unwrap();
+        import(&p, vec![Task { id: Some(7), title: "Imported".to_string(), ..Task::default() }], ImportMode::Replace);
+        assert!(p.undo().unwrap().is_none());
+        assert_eq!(p.get::<Task>(7).unwrap().unwrap().title, "Imported");
+    }
+
+    #[test]
+    fn merge_keeps_local_changes_over_older_imports() {
+        let p = Persistence::in_memory();
+        let id = p.save(&Task { title: "Local".to_string(), uid: Some("a".to_string()), ..Task::default() }).unwrap();
+        let local = p.get::<Task>(id.unwrap()).unwrap().unwrap();
+        let imported = |updated_at| Task { title: "Imported".to_string(), uid: Some("a".to_string()), updated_at, ..Task::default() };
+        let earlier = local.updated_at.map(|t| t - chrono::Duration::days(1));
+        let later = local.updated_at.map(|t| t + chrono::Duration::days(1));
+
+        assert_eq!(import(&p, vec![imported(earlier)], ImportMode::Merge).tasks.kept, 1);
+        assert_eq!(import(&p, vec![imported(later)], ImportMode::Merge).tasks.updated, 1);
+        assert_eq!(p.get::<Task>(id.unwrap()).unwrap().unwrap().title, "Imported");
+    }
+
+    #[test]
+    fn merge_applies_edits_to_undated_exports() {
+        let p = Persistence::in_memory();
+        let id = p.save(&Task { title: "Pay rent".to_string(), ..Task::default() }).unwrap();
+        let exported = todotxt::render(&Dataset::load(&p).unwrap());
+        let edited = format!("x 2026-10-17 {}", exported.trim_start_matches(|c: char| c.is_ascii_digit() || c == '-' || c == ' '));
+
+        let report = todotxt::parse(&edited).unwrap().import(&p, ImportMode::Merge, false).unwrap();
+        assert_eq!((report.tasks.updated, report.tasks.kept, report.tasks.added), (1, 0, 0));
+        assert!(p.get::<Task>(id.unwrap()).unwrap().unwrap().completed);
+
+        let report = todotxt::parse(&edited).unwrap().import(&p, ImportMode::Merge, false).unwrap();
+        assert_eq!(report.tasks.unchanged, 1);
+    }
+}
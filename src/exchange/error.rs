@@ -0,0 +1,78 @@
+use std::{error::Error, fmt, io};
+
+use crate::persistence::error::PersistenceError;
+
+#[derive(Debug)]
+pub enum ExchangeError {
+    /// Reading or writing the export file failed
+    Io(io::Error),
+    /// The database couldn't be read or written
+    Persistence(PersistenceError),
+    /// The input isn't a valid document of the chosen format
+    Parse(String),
+    /// The document was written by a newer build than this one
+    UnsupportedVersion { found: u32, supported: u32 },
+    /// Rows of a spreadsheet that can't be turned into tasks
+    InvalidRows(Vec<RowError>),
+    /// The named format can only be imported
+    ImportOnly(&'static str),
+}
+
+/// Why one row of an imported file was rejected.
+#[derive(Debug, Clone)]
+pub struct RowError {
+    /// Line number in the file, counting the header
+    pub row: usize,
+    pub message: String,
+}
+
+impl fmt::Display for RowError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "row {}: {}", self.row, self.message)
+    }
+}
+
+impl fmt::Display for ExchangeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExchangeError::Io(e) => write!(f, "I/O error: {}", e),
+            ExchangeError::Persistence(e) => write!(f, "{}", e),
+            ExchangeError::Parse(msg) => write!(f, "Invalid input: {}", msg),
+            ExchangeError::UnsupportedVersion { found, supported } => write!(
+                f,
+                "Export version {} is newer than this build supports ({}). Please update todo.",
+                found, supported
+            ),
+            ExchangeError::ImportOnly(format) => write!(f, "{} files can only be imported", format),
+            ExchangeError::InvalidRows(errors) => {
+                write!(f, "{} invalid rows", errors.len())?;
+                for error in errors {
+                    write!(f, "\n  {}", error)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl Error for ExchangeError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ExchangeError::Io(e) => Some(e),
+            ExchangeError::Persistence(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for ExchangeError {
+    fn from(e: io::Error) -> Self {
+        ExchangeError::Io(e)
+    }
+}
+
+impl From<PersistenceError> for ExchangeError {
+    fn from(e: PersistenceError) -> Self {
+        ExchangeError::Persistence(e)
+    }
+}
@@ -0,0 +1,126 @@
+use std::{env, fmt, path::{Path, PathBuf}, str::FromStr};
+
+use crate::exchange::dataset::{Dataset, ImportMode, ImportReport};
+use crate::exchange::error::ExchangeError;
+use crate::exchange::{csv, ics, json, markdown, taskwarrior, todotxt};
+use crate::persistence::persistence::Persistence;
+
+/// File formats tasks can be exported to and imported from.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    Json,
+    /// One task per line, see `todotxt`
+    TodoTxt,
+    /// iCalendar with one VTODO per task, see `ics`
+    Ics,
+    /// A spreadsheet, one task per row; see `csv`
+    Csv,
+    /// A Markdown checklist; see `markdown`
+    Markdown,
+    /// Taskwarrior's `task export` output, import only; see `taskwarrior`
+    Taskwarrior,
+}
+
+impl Format {
+    pub const ALL: [Format; 6] = [
+        Format::Json,
+        Format::TodoTxt,
+        Format::Ics,
+        Format::Csv,
+        Format::Markdown,
+        Format::Taskwarrior,
+    ];
+
+    /// Name accepted by `--format`.
+    pub fn name(self) -> &'static str {
+        match self {
+            Format::Json => "json",
+            Format::TodoTxt => "todotxt",
+            Format::Ics => "ics",
+            Format::Csv => "csv",
+            Format::Markdown => "markdown",
+            Format::Taskwarrior => "taskwarrior",
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            Format::Json => "json",
+            Format::TodoTxt => "txt",
+            Format::Ics => "ics",
+            Format::Csv => "csv",
+            Format::Markdown => "md",
+            // .json files are read as JSON, which recognises these too
+            Format::Taskwarrior => "json",
+        }
+    }
+
+    /// Renders the whole database.
+    pub fn export(self, persistence: &Persistence) -> Result<String, ExchangeError> {
+        let data = Dataset::load(persistence)?;
+        match self {
+            Format::Json => json::render(&data),
+            Format::TodoTxt => Ok(todotxt::render(&data)),
+            Format::Ics => Ok(ics::render(&data)),
+            Format::Csv => csv::render(&data),
+            Format::Markdown => Ok(markdown::render(&data)),
+            Format::Taskwarrior => Err(ExchangeError::ImportOnly(self.name())),
+        }
+    }
+
+    pub fn parse(self, input: &str) -> Result<Dataset, ExchangeError> {
+        match self {
+            Format::Json => json::parse(input),
+            Format::TodoTxt => todotxt::parse(input),
+            Format::Ics => ics::parse(input),
+            Format::Csv => csv::parse(input),
+            Format::Markdown => markdown::parse(input),
+            Format::Taskwarrior => taskwarrior::parse(input),
+        }
+    }
+
+    /// Parses `input` and imports it; see `Dataset::import`.
+    pub fn import(
+        self,
+        persistence: &Persistence,
+        input: &str,
+        mode: ImportMode,
+        dry_run: bool,
+    ) -> Result<ImportReport, ExchangeError> {
+        self.parse(input)?.import(persistence, mode, dry_run)
+    }
+
+    /// The format matching a file's extension, e.g. `json` for `tasks.json`
+    /// and `todotxt` for `todo.txt`.
+    pub fn from_path(path: &Path) -> Option<Format> {
+        let extension = path.extension()?.to_str()?;
+        extension.parse().ok()
+    }
+
+    /// Suggested export file: `todo-export.<ext>` in the home directory.
+    pub fn default_path(self) -> PathBuf {
+        let home = env::var_os("HOME").or_else(|| env::var_os("USERPROFILE")).unwrap_or_default();
+        PathBuf::from(home).join(format!("todo-export.{}", self.extension()))
+    }
+}
+
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let input = input.trim().to_lowercase();
+        Format::ALL
+            .into_iter()
+            .find(|f| f.name() == input || f.extension() == input)
+            .ok_or_else(|| {
+                let names: Vec<String> = Format::ALL.iter().map(Format::to_string).collect();
+                format!("Unknown format '{}' (use {})", input, names.join(", "))
+            })
+    }
+}
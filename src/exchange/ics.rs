@@ -0,0 +1,568 @@
+//! iCalendar (RFC 5545) files holding one VTODO per task, as read and
+//! written by calendar clients.
+//!
+//! SUMMARY, DESCRIPTION, STATUS, DUE, PRIORITY, CATEGORIES (tags), RRULE and
+//! RELATED-TO (the parent task) map onto `Task` fields. The project,
+//! repeat rules RRULE can't express, the previous occurrence and todo.txt
+//! extensions travel in `X-TODO-*` properties, which other clients ignore.
+//! Times in other time zones than UTC are read as local time.
+
+use std::collections::HashMap;
+
+use chrono::{DateTime, Datelike, Local, NaiveDate, NaiveDateTime, TimeZone, Utc, Weekday};
+
+use crate::exchange::dataset::Dataset;
+use crate::exchange::error::ExchangeError;
+use crate::persistence::persistence::{Priority, Task};
+use crate::persistence::recurrence::Recurrence;
+
+/// UIDs of exported tasks end with this, so re-importing them can keep
+/// their ids.
+const UID_DOMAIN: &str = "@todo";
+
+const DATE: &str = "%Y%m%d";
+const DATE_TIME: &str = "%Y%m%dT%H%M%S";
+
+/// Longest line allowed before it has to be folded, in bytes.
+const LINE_LIMIT: usize = 75;
+
+pub fn render(data: &Dataset) -> String {
+    let uids: HashMap<Option<i64>, String> = data.tasks.iter().map(|t| (t.id, uid(t))).collect();
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        format!("PRODID:-//todo//todo {}//EN", env!("CARGO_PKG_VERSION")),
+    ];
+    for task in &data.tasks {
+        let mut todo = vec![
+            "BEGIN:VTODO".to_string(),
+            format!("UID:{}", uids[&task.id]),
+            format!("DTSTAMP:{}", utc(task.updated_at.unwrap_or_else(|| Local::now().naive_local()))),
+        ];
+        if let Some(created) = task.created_at {
+            todo.push(format!("CREATED:{}", utc(created)));
+        }
+        if let Some(updated) = task.updated_at {
+            todo.push(format!("LAST-MODIFIED:{}", utc(updated)));
+        }
+        todo.push(format!("SUMMARY:{}", escape(&task.title)));
+        if let Some(description) = task.description.as_deref().filter(|d| !d.is_empty()) {
+            todo.push(format!("DESCRIPTION:{}", escape(description)));
+        }
+        if task.completed {
+            todo.push("STATUS:COMPLETED".to_string());
+            if let Some(completed) = task.completed_at {
+                todo.push(format!("COMPLETED:{}", utc(completed)));
+            }
+        } else {
+            todo.push("STATUS:NEEDS-ACTION".to_string());
+        }
+        if let Some(due) = task.due {
+            if due.time() == Task::end_of_day() {
+                todo.push(format!("DUE;VALUE=DATE:{}", due.format(DATE)));
+            } else {
+                todo.push(format!("DUE:{}", due.format(DATE_TIME)));
+            }
+        }
+        if task.priority != Priority::None {
+            todo.push(format!("PRIORITY:{}", priority_number(task.priority)));
+        }
+        if !task.tags.is_empty() {
+            let tags: Vec<String> = task.tags.iter().map(|t| escape(t)).collect();
+            todo.push(format!("CATEGORIES:{}", tags.join(",")));
+        }
+        if let Some(rule) = &task.recurrence {
+            match rrule(rule) {
+                Some(rrule) => todo.push(format!("RRULE:{}", rrule)),
+                None => todo.push(format!("X-TODO-REPEAT:{}", rule)),
+            }
+        }
+        if let Some(parent) = task.parent_id.and_then(|id| uids.get(&Some(id))) {
+            todo.push(format!("RELATED-TO;RELTYPE=PARENT:{}", parent));
+        }
+        if let Some(previous) = task.occurrence_of.and_then(|id| uids.get(&Some(id))) {
+            todo.push(format!("X-TODO-OCCURRENCE-OF:{}", previous));
+        }
+        if let Some(project) = data.projects.iter().find(|p| p.id == task.project_id && !p.is_inbox()) {
+            todo.push(format!("X-TODO-PROJECT:{}", escape(&project.name)));
+        }
+        if !task.extensions.is_empty() {
+            todo.push(format!("X-TODO-EXTENSIONS:{}", escape(&task.extensions.to_string())));
+        }
+        todo.push("END:VTODO".to_string());
+        lines.extend(todo);
+    }
+    lines.push("END:VCALENDAR".to_string());
+
+    let mut out = String::new();
+    for line in lines {
+        out.push_str(&fold(&line));
+    }
+    out
+}
+
+/// `<id>-<creation time>@todo`: unique across databases, and stable. A task
+/// imported from another client keeps that client's UID instead. The
+/// todo.txt export writes it too.
+pub(crate) fn uid(task: &Task) -> String {
+    if let Some(uid) = &task.uid {
+        return uid.clone();
+    }
+    let created = task.created_at.map(|c| c.format(DATE_TIME).to_string()).unwrap_or_default();
+    format!("{}-{}{}", task.id.unwrap_or_default(), created, UID_DOMAIN)
+}
+
+/// The task id in a UID written by `uid`.
+pub(crate) fn id_from_uid(uid: &str) -> Option<i64> {
+    let (id, _) = uid.strip_suffix(UID_DOMAIN)?.split_once('-')?;
+    id.parse().ok().filter(|&id| id > 0)
+}
+
+/// The creation time in a UID written by `uid`, to the second.
+pub(crate) fn created_from_uid(uid: &str) -> Option<NaiveDateTime> {
+    let (_, created) = uid.strip_suffix(UID_DOMAIN)?.split_once('-')?;
+    NaiveDateTime::parse_from_str(created, DATE_TIME).ok()
+}
+
+fn utc(local: NaiveDateTime) -> String {
+    let utc = Local.from_local_datetime(&local).earliest().map(|t| t.with_timezone(&Utc).naive_utc());
+    format!("{}Z", utc.unwrap_or(local).format(DATE_TIME))
+}
+
+/// Splits a line into chunks of at most 75 bytes, continuation lines
+/// starting with a space.
+fn fold(line: &str) -> String {
+    let mut out = String::new();
+    let mut width = 0;
+    for c in line.chars() {
+        if width + c.len_utf8() > LINE_LIMIT {
+            out.push_str("\r\n ");
+            width = 1;
+        }
+        out.push(c);
+        width += c.len_utf8();
+    }
+    out.push_str("\r\n");
+    out
+}
+
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace("\r\n", "\\n")
+        .replace('\n', "\\n")
+}
+
+fn unescape(text: &str) -> String {
+    let mut out = String::new();
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n' | 'N') => out.push('\n'),
+            Some(other) => out.push(other),
+            None => out.push('\\'),
+        }
+    }
+    out
+}
+
+/// Splits on commas that aren't escaped, as in CATEGORIES.
+fn split_list(text: &str) -> Vec<String> {
+    let mut items = vec![String::new()];
+    let mut escaped = false;
+    for c in text.chars() {
+        match c {
+            ',' if !escaped => items.push(String::new()),
+            _ => items.last_mut().unwrap().push(c),
+        }
+        escaped = c == '\\' && !escaped;
+    }
+    items.iter().map(|i| unescape(i).trim().to_string()).filter(|i| !i.is_empty()).collect()
+}
+
+/// 1 is the highest priority and 9 the lowest; 0 means none.
+fn priority_number(priority: Priority) -> u8 {
+    match priority {
+        Priority::None => 0,
+        Priority::Urgent => 1,
+        Priority::High => 3,
+        Priority::Medium => 5,
+        Priority::Low => 7,
+    }
+}
+
+fn priority_from_number(number: u8) -> Priority {
+    match number {
+        1..=2 => Priority::Urgent,
+        3..=4 => Priority::High,
+        5 => Priority::Medium,
+        6..=9 => Priority::Low,
+        _ => Priority::None,
+    }
+}
+
+const WEEKDAYS: [(Weekday, &str); 7] = [
+    (Weekday::Mon, "MO"),
+    (Weekday::Tue, "TU"),
+    (Weekday::Wed, "WE"),
+    (Weekday::Thu, "TH"),
+    (Weekday::Fri, "FR"),
+    (Weekday::Sat, "SA"),
+    (Weekday::Sun, "SU"),
+];
+
+/// The RRULE for a repeat rule; repeating after completion has none.
+fn rrule(rule: &Recurrence) -> Option<String> {
+    match rule {
+        Recurrence::Daily => Some("FREQ=DAILY".to_string()),
+        Recurrence::Weekly(days) => {
+            let days: Vec<&str> = days
+                .iter()
+                .filter_map(|d| WEEKDAYS.iter().find(|(w, _)| w == d).map(|(_, name)| *name))
+                .collect();
+            Some(format!("FREQ=WEEKLY;BYDAY={}", days.join(",")))
+        }
+        Recurrence::Monthly(day) => Some(format!("FREQ=MONTHLY;BYMONTHDAY={}", day)),
+        Recurrence::AfterCompletion(_) => None,
+    }
+}
+
+/// The repeat rule for the simple RRULEs `rrule` writes, taking a missing
+/// day from the due date. Intervals, counts and the like aren't supported.
+fn parse_rrule(value: &str, due: Option<NaiveDateTime>) -> Option<Recurrence> {
+    let parts: HashMap<&str, &str> = value.split(';').filter_map(|p| p.split_once('=')).collect();
+    if parts.get("INTERVAL").is_some_and(|i| *i != "1") || parts.contains_key("COUNT") || parts.contains_key("UNTIL") {
+        return None;
+    }
+    match *parts.get("FREQ")? {
+        "DAILY" => Some(Recurrence::Daily),
+        "WEEKLY" => {
+            let mut days = match parts.get("BYDAY") {
+                Some(by_day) => by_day
+                    .split(',')
+                    .map(|name| WEEKDAYS.iter().find(|(_, n)| *n == name).map(|(w, _)| *w))
+                    .collect::<Option<Vec<Weekday>>>()?,
+                None => vec![due?.weekday()],
+            };
+            days.sort_by_key(|d| d.num_days_from_monday());
+            days.dedup();
+            Some(Recurrence::Weekly(days))
+        }
+        "MONTHLY" => {
+            let day = match parts.get("BYMONTHDAY") {
+                Some(day) => day.parse().ok().filter(|d| (1..=31).contains(d))?,
+                None => due?.day(),
+            };
+            Some(Recurrence::Monthly(day))
+        }
+        _ => None,
+    }
+}
+
+/// A content line: `NAME;PARAM=VALUE:value`.
+struct Property {
+    name: String,
+    params: Vec<(String, String)>,
+    value: String,
+}
+
+impl Property {
+    fn parse(line: &str) -> Option<Property> {
+        // the value starts at the first colon outside a quoted parameter
+        let mut quoted = false;
+        let split = line.char_indices().find(|&(_, c)| {
+            if c == '"' {
+                quoted = !quoted;
+            }
+            c == ':' && !quoted
+        })?;
+        let (head, value) = (&line[..split.0], &line[split.0 + 1..]);
+        let mut head = head.split(';');
+        let name = head.next()?.trim().to_uppercase();
+        let params = head
+            .filter_map(|p| p.split_once('='))
+            .map(|(k, v)| (k.to_uppercase(), v.trim_matches('"').to_string()))
+            .collect();
+        Some(Property { name, params, value: value.to_string() })
+    }
+
+    fn param(&self, name: &str) -> Option<&str> {
+        self.params.iter().find(|(k, _)| k == name).map(|(_, v)| v.as_str())
+    }
+
+    /// A DATE or DATE-TIME value in local time, with whether it was a date.
+    fn date_time(&self) -> Option<(NaiveDateTime, bool)> {
+        let value = self.value.trim();
+        if let Some(utc) = value.strip_suffix('Z') {
+            let utc = NaiveDateTime::parse_from_str(utc, DATE_TIME).ok()?;
+            let local = DateTime::<Utc>::from_naive_utc_and_offset(utc, Utc).with_timezone(&Local);
+            return Some((local.naive_local(), false));
+        }
+        if let Ok(local) = NaiveDateTime::parse_from_str(value, DATE_TIME) {
+            return Some((local, false));
+        }
+        let date = NaiveDate::parse_from_str(value, DATE).ok()?;
+        Some((date.and_hms_opt(0, 0, 0)?, true))
+    }
+}
+
+/// Joins folded lines back together.
+fn unfold(input: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for line in input.lines() {
+        let line = line.trim_end_matches('\r');
+        match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(rest), Some(last)) => last.push_str(rest),
+            _ if line.is_empty() => {}
+            _ => lines.push(line.to_string()),
+        }
+    }
+    lines
+}
+
+/// A VTODO as read, before task references are resolved.
+struct Parsed {
+    task: Task,
+    uid: Option<String>,
+    parent: Option<String>,
+    previous: Option<String>,
+}
+
+pub fn parse(input: &str) -> Result<Dataset, ExchangeError> {
+    let mut data = Dataset::default();
+    let mut todos: Vec<Parsed> = Vec::new();
+    let mut current: Option<Vec<Property>> = None;
+    // components nested in a VTODO, such as VALARM, are skipped
+    let mut nested = 0;
+    let mut seen_calendar = false;
+
+    for (number, line) in unfold(input).iter().enumerate() {
+        let property = Property::parse(line)
+            .ok_or_else(|| ExchangeError::Parse(format!("line {}: expected NAME:value", number + 1)))?;
+        let component = property.value.trim().to_uppercase();
+        match (property.name.as_str(), current.as_mut()) {
+            ("BEGIN", None) if component == "VCALENDAR" => seen_calendar = true,
+            ("BEGIN", None) if component == "VTODO" => current = Some(Vec::new()),
+            ("BEGIN", Some(_)) => nested += 1,
+            ("END", Some(properties)) if nested == 0 && component == "VTODO" => {
+                let properties = std::mem::take(properties);
+                current = None;
+                let todo = parse_todo(properties, &mut data)
+                    .map_err(|e| ExchangeError::Parse(format!("task {}: {}", todos.len() + 1, e)))?;
+                todos.push(todo);
+            }
+            ("END", Some(_)) => nested -= 1,
+            (_, Some(properties)) if nested == 0 => properties.push(property),
+            _ => {}
+        }
+    }
+    if !seen_calendar {
+        return Err(ExchangeError::Parse("not an iCalendar file (no BEGIN:VCALENDAR)".to_string()));
+    }
+
+    // Tasks exported from here keep their id; the rest are numbered after
+    // them, and those from other clients keep their UID for merges to match.
+    let mut used: Vec<i64> = Vec::new();
+    for todo in &mut todos {
+        match todo.uid.as_deref().map(|uid| (uid, id_from_uid(uid))) {
+            Some((_, Some(id))) if !used.contains(&id) => {
+                used.push(id);
+                todo.task.id = Some(id);
+            }
+            Some((uid, None)) => todo.task.uid = Some(uid.to_string()),
+            _ => {}
+        }
+    }
+    let mut next_id = used.iter().max().copied().unwrap_or(0);
+    for todo in &mut todos {
+        if todo.task.id.is_none() {
+            next_id += 1;
+            todo.task.id = Some(next_id);
+        }
+    }
+
+    let ids: HashMap<&str, Option<i64>> =
+        todos.iter().filter_map(|t| Some((t.uid.as_deref()?, t.task.id))).collect();
+    let resolve = |uid: &Option<String>| uid.as_deref().and_then(|uid| ids.get(uid).copied().flatten());
+    // Links are taken in file order; one that would make a task its own
+    // ancestor closes a cycle and is dropped.
+    let mut parents: HashMap<i64, i64> = HashMap::new();
+    for todo in &todos {
+        let mut parent_id = resolve(&todo.parent);
+        if let (Some(id), Some(parent)) = (todo.task.id, parent_id) {
+            let mut ancestor = Some(parent);
+            while let Some(a) = ancestor
+                && a != id
+            {
+                ancestor = parents.get(&a).copied();
+            }
+            if ancestor.is_some() {
+                data.warnings.push(format!(
+                    "'{}' would become its own ancestor through RELATED-TO; the link was dropped",
+                    todo.task.title
+                ));
+                parent_id = None;
+            } else {
+                parents.insert(id, parent);
+            }
+        }
+        data.tasks.push(Task { parent_id, occurrence_of: resolve(&todo.previous), ..todo.task.clone() });
+    }
+    Ok(data)
+}
+
+fn parse_todo(properties: Vec<Property>, data: &mut Dataset) -> Result<Parsed, String> {
+    let mut task = Task::default();
+    let mut parsed = Parsed { task: Task::default(), uid: None, parent: None, previous: None };
+    let mut rrule = None;
+    let mut repeat = None;
+    let time = |p: &Property| p.date_time().ok_or_else(|| format!("invalid date '{}' in {}", p.value, p.name));
+
+    for property in &properties {
+        let value = property.value.as_str();
+        match property.name.as_str() {
+            "UID" => parsed.uid = Some(value.to_string()),
+            "SUMMARY" => task.title = unescape(value).split_whitespace().collect::<Vec<_>>().join(" "),
+            "DESCRIPTION" => task.description = Some(unescape(value)).filter(|d| !d.trim().is_empty()),
+            "STATUS" => task.completed = matches!(value.trim(), "COMPLETED" | "CANCELLED"),
+            "COMPLETED" => {
+                task.completed = true;
+                task.completed_at = Some(time(property)?.0);
+            }
+            "DUE" => {
+                let (due, all_day) = time(property)?;
+                task.due = Some(if all_day { due.date().and_time(Task::end_of_day()) } else { due });
+            }
+            "PRIORITY" => task.priority = priority_from_number(value.trim().parse().unwrap_or(0)),
+            "CATEGORIES" => {
+                for tag in split_list(value) {
+                    if !task.tags.iter().any(|t| t.eq_ignore_ascii_case(&tag)) {
+                        data.add_tag(&tag);
+                        task.tags.push(tag);
+                    }
+                }
+            }
+            "CREATED" => task.created_at = Some(time(property)?.0),
+            "LAST-MODIFIED" => task.updated_at = Some(time(property)?.0),
+            "RRULE" => rrule = Some(value),
+            "X-TODO-REPEAT" => repeat = value.parse::<Recurrence>().ok(),
+            "RELATED-TO" if property.param("RELTYPE").is_none_or(|r| r.eq_ignore_ascii_case("PARENT")) => {
+                parsed.parent = Some(value.to_string());
+            }
+            "X-TODO-OCCURRENCE-OF" => parsed.previous = Some(value.to_string()),
+            "X-TODO-PROJECT" => task.project_id = data.project_id(&unescape(value)),
+            "X-TODO-EXTENSIONS" => task.extensions = unescape(value).parse().unwrap_or_default(),
+            _ => {}
+        }
+    }
+
+    task.recurrence = repeat.or_else(|| rrule.and_then(|r| parse_rrule(r, task.due)));
+    if task.title.is_empty() {
+        return Err("no SUMMARY".to_string());
+    }
+    if !task.completed {
+        task.completed_at = None;
+    }
+    parsed.task = task;
+    Ok(parsed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exchange::dataset::ImportMode;
+    use crate::persistence::persistence::Persistence;
+    use crate::persistence::project::Project;
+
+    fn todo(uid: &str, title: &str, parent: Option<&str>) -> String {
+        let related = parent.map(|p| format!("RELATED-TO:{}\r\n", p)).unwrap_or_default();
+        format!("BEGIN:VTODO\r\nUID:{}\r\nSUMMARY:{}\r\n{}END:VTODO\r\n", uid, title, related)
+    }
+
+    fn calendar(todos: &[String]) -> String {
+        format!("BEGIN:VCALENDAR\r\nVERSION:2.0\r\n{}END:VCALENDAR\r\n", todos.concat())
+    }
+
+    #[test]
+    fn round_trip_keeps_tasks() {
+        let due = NaiveDate::from_ymd_opt(2026, 3, 1).unwrap().and_time(Task::end_of_day());
+        let data = Dataset {
+            projects: vec![Project { id: Some(2), name: "My Project".to_string(), ..Project::default() }],
+            tasks: vec![
+                Task {
+                    id: Some(1),
+                    title: "Write report, part 1; draft".to_string(),
+                    description: Some("Line one\nLine two".to_string()),
+                    due: Some(due),
+                    priority: Priority::High,
+                    project_id: Some(2),
+                    tags: vec!["work".to_string()],
+                    recurrence: "weekly".parse().ok(),
+                    ..Task::default()
+                },
+                Task { id: Some(2), title: "Sources".to_string(), parent_id: Some(1), completed: true, ..Task::default() },
+            ],
+            ..Dataset::default()
+        };
+
+        let parsed = parse(&render(&data)).unwrap();
+        assert!(parsed.warnings.is_empty());
+        assert_eq!(parsed.projects[0].name, "My Project");
+        let [first, second] = parsed.tasks.as_slice() else { panic!("expected two tasks") };
+        assert_eq!(first.id, Some(1));
+        assert_eq!(first.title, data.tasks[0].title);
+        assert_eq!(first.description, data.tasks[0].description);
+        assert_eq!(first.due, Some(due));
+        assert_eq!(first.priority, Priority::High);
+        assert_eq!(first.project_id, parsed.projects[0].id);
+        assert_eq!(first.tags, ["work"]);
+        assert_eq!(first.recurrence, data.tasks[0].recurrence);
+        assert_eq!(second.parent_id, Some(1));
+        assert!(second.completed);
+    }
+
+    #[test]
+    fn related_to_cycle_drops_the_closing_link() {
+        let input = calendar(&[todo("a", "A", Some("b")), todo("b", "B", Some("a"))]);
+        let data = parse(&input).unwrap();
+        assert_eq!(data.tasks[0].parent_id, data.tasks[1].id);
+        assert_eq!(data.tasks[1].parent_id, None);
+        assert_eq!(data.warnings.len(), 1);
+        assert!(data.warnings[0].contains("'B'"));
+    }
+
+    #[test]
+    fn longer_cycles_and_self_links_are_dropped() {
+        let input = calendar(&[
+            todo("a", "A", Some("c")),
+            todo("b", "B", Some("a")),
+            todo("c", "C", Some("b")),
+            todo("d", "D", Some("d")),
+        ]);
+        let data = parse(&input).unwrap();
+        let parents: Vec<Option<i64>> = data.tasks.iter().map(|t| t.parent_id).collect();
+        assert_eq!(parents, [Some(3), Some(1), None, None]);
+        assert_eq!(data.warnings.len(), 2);
+    }
+
+    #[test]
+    fn merging_a_file_from_another_client_twice_adds_nothing() {
+        let input = calendar(&[todo("f81d4fae-7dec@example.com", "Call mom", None), todo("2@phone", "Buy milk", None)]);
+        let p = Persistence::in_memory();
+        let report = parse(&input).unwrap().import(&p, ImportMode::Merge, false).unwrap();
+        assert_eq!(report.tasks.added, 2);
+
+        let report = parse(&input).unwrap().import(&p, ImportMode::Merge, false).unwrap();
+        assert_eq!((report.tasks.added, report.tasks.unchanged), (0, 2));
+        assert_eq!(p.get_all::<Task>().unwrap().len(), 2);
+
+        // and exports keep the other client's UID
+        let exported = render(&Dataset::load(&p).unwrap());
+        assert!(exported.contains("UID:f81d4fae-7dec@example.com\r\n"), "{}", exported);
+    }
+}
@@ -0,0 +1,49 @@
+use chrono::{Local, NaiveDateTime, Timelike};
+use serde::{Deserialize, Serialize};
+
+use crate::exchange::dataset::Dataset;
+use crate::exchange::error::ExchangeError;
+use crate::exchange::taskwarrior;
+
+/// Marks a file as a todo export.
+pub const FORMAT: &str = "todo";
+
+/// Version of the document layout. Bump it when a change would make older
+/// builds misread a document; adding fields doesn't need a bump.
+pub const VERSION: u32 = 1;
+
+/// A full export: the dataset plus a header identifying it.
+#[derive(Debug, Serialize, Deserialize)]
+struct Document {
+    format: String,
+    version: u32,
+    exported_at: Option<NaiveDateTime>,
+    #[serde(flatten)]
+    data: Dataset,
+}
+
+pub fn render(data: &Dataset) -> Result<String, ExchangeError> {
+    let document = Document {
+        format: FORMAT.to_string(),
+        version: VERSION,
+        exported_at: Local::now().naive_local().with_nanosecond(0),
+        data: data.clone(),
+    };
+    serde_json::to_string_pretty(&document).map_err(|e| ExchangeError::Parse(e.to_string()))
+}
+
+/// Also accepts `task export` output, so Taskwarrior files can be
+/// imported without naming the format.
+pub fn parse(input: &str) -> Result<Dataset, ExchangeError> {
+    if taskwarrior::detect(input) {
+        return taskwarrior::parse(input);
+    }
+    let document: Document = serde_json::from_str(input).map_err(|e| ExchangeError::Parse(e.to_string()))?;
+    if document.format != FORMAT {
+        return Err(ExchangeError::Parse(format!("not a todo export (format '{}')", document.format)));
+    }
+    if document.version > VERSION {
+        return Err(ExchangeError::UnsupportedVersion { found: document.version, supported: VERSION });
+    }
+    Ok(document.data)
+}
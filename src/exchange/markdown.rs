@@ -0,0 +1,225 @@
+//! GitHub-flavoured Markdown checklists:
+//!
+//! ```markdown
+//! ## Work
+//!
+//! - [ ] Prepare the release
+//!   Check the changelog first.
+//!   - [x] Bump the version
+//! ```
+//!
+//! Exports have a heading per project, subtasks nested under their parent
+//! and descriptions as indented text. On import every `- [ ]` / `- [x]`
+//! item (or `*`, `+`, `1.`) is a task, nested items are subtasks and other
+//! text indented under an item is its description. Headings and everything
+//! else are ignored, so checklists can be pulled out of meeting notes;
+//! imported tasks land in the Inbox.
+
+use std::collections::HashMap;
+
+use crate::exchange::dataset::Dataset;
+use crate::exchange::error::ExchangeError;
+use crate::persistence::persistence::Task;
+
+/// Indentation of one nesting level: where the text of `- [ ]` starts.
+const INDENT: usize = 2;
+
+pub fn render(data: &Dataset) -> String {
+    let ids: Vec<Option<i64>> = data.tasks.iter().map(|t| t.id).collect();
+    let mut children: HashMap<Option<i64>, Vec<&Task>> = HashMap::new();
+    for task in &data.tasks {
+        // subtasks whose parent isn't exported are shown at the top
+        let parent = task.parent_id.filter(|p| ids.contains(&Some(*p)));
+        children.entry(parent).or_default().push(task);
+    }
+
+    let mut sections = Vec::new();
+    for project in &data.projects {
+        let top: Vec<&Task> = children
+            .get(&None)
+            .map(|tasks| tasks.iter().copied().filter(|t| t.project_id == project.id).collect())
+            .unwrap_or_default();
+        if top.is_empty() {
+            continue;
+        }
+        let mut section = format!("## {}\n\n", project.name);
+        for task in top {
+            render_item(task, 0, &children, &mut section);
+        }
+        sections.push(section);
+    }
+    // tasks of projects that weren't exported, if any
+    let orphans: Vec<&Task> = children
+        .get(&None)
+        .map(|tasks| {
+            tasks
+                .iter()
+                .copied()
+                .filter(|t| !data.projects.iter().any(|p| p.id == t.project_id))
+                .collect()
+        })
+        .unwrap_or_default();
+    if !orphans.is_empty() {
+        let mut section = String::new();
+        for task in orphans {
+            render_item(task, 0, &children, &mut section);
+        }
+        sections.push(section);
+    }
+    sections.join("\n")
+}
+
+fn render_item(task: &Task, depth: usize, children: &HashMap<Option<i64>, Vec<&Task>>, out: &mut String) {
+    let indent = " ".repeat(depth * INDENT);
+    let check = if task.completed { 'x' } else { ' ' };
+    let title = task.title.split_whitespace().collect::<Vec<_>>().join(" ");
+    out.push_str(&format!("{}- [{}] {}\n", indent, check, title));
+
+    if let Some(description) = task.description.as_deref().filter(|d| !d.trim().is_empty()) {
+        let indent = " ".repeat((depth + 1) * INDENT);
+        for line in description.trim_end().lines() {
+            if line.trim().is_empty() {
+                out.push('\n');
+            } else {
+                out.push_str(&format!("{}{}\n", indent, line.trim_end()));
+            }
+        }
+    }
+    for child in children.get(&task.id).into_iter().flatten() {
+        render_item(child, depth + 1, children, out);
+    }
+}
+
+/// Number of whitespace characters `line` starts with.
+fn indent(line: &str) -> usize {
+    line.chars().take_while(|c| c.is_whitespace()).count()
+}
+
+/// `line` without its first `chars` characters.
+fn after(line: &str, chars: usize) -> &str {
+    line.char_indices().nth(chars).map_or("", |(i, _)| &line[i..])
+}
+
+/// A checklist item: its indentation, whether it's ticked and its text.
+fn parse_item(line: &str) -> Option<(usize, bool, &str)> {
+    let text = line.trim_start();
+    let indent = indent(line);
+    let rest = match text.strip_prefix(['-', '*', '+']) {
+        Some(rest) => rest,
+        None => {
+            let digits = text.len() - text.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+            if digits == 0 {
+                return None;
+            }
+            text[digits..].strip_prefix(['.', ')'])?
+        }
+    };
+    let rest = rest.strip_prefix(' ')?.trim_start();
+    let (completed, title) = if let Some(title) = rest.strip_prefix("[ ]") {
+        (false, title)
+    } else if let Some(title) = rest.strip_prefix("[x]").or_else(|| rest.strip_prefix("[X]")) {
+        (true, title)
+    } else {
+        return None;
+    };
+    if !title.is_empty() && !title.starts_with(char::is_whitespace) {
+        return None;
+    }
+    Some((indent, completed, title.trim()))
+}
+
+pub fn parse(input: &str) -> Result<Dataset, ExchangeError> {
+    let mut data = Dataset::default();
+    // open items: (indentation of their marker, index in data.tasks)
+    let mut stack: Vec<(usize, usize)> = Vec::new();
+    let mut description: Vec<&str> = Vec::new();
+
+    // text indented under an item is its description
+    let flush = |description: &mut Vec<&str>, stack: &[(usize, usize)], data: &mut Dataset| {
+        let text = description.join("\n").trim().to_string();
+        description.clear();
+        if let (false, Some(&(_, i))) = (text.is_empty(), stack.last()) {
+            let task = &mut data.tasks[i];
+            task.description = Some(match task.description.take() {
+                Some(before) => format!("{}\n{}", before, text),
+                None => text,
+            });
+        }
+    };
+
+    for line in input.lines() {
+        let line = line.trim_end();
+        if let Some((indent, completed, title)) = parse_item(line) {
+            flush(&mut description, &stack, &mut data);
+            while stack.last().is_some_and(|&(open, _)| open >= indent) {
+                stack.pop();
+            }
+            if title.is_empty() {
+                continue;
+            }
+            let id = data.tasks.len() as i64 + 1;
+            data.tasks.push(Task {
+                id: Some(id),
+                title: title.to_string(),
+                completed,
+                parent_id: stack.last().map(|&(_, i)| i as i64 + 1),
+                ..Task::default()
+            });
+            stack.push((indent, data.tasks.len() - 1));
+            continue;
+        }
+
+        if line.is_empty() {
+            description.push("");
+            continue;
+        }
+        let indent = indent(line);
+        while stack.last().is_some_and(|&(open, _)| open >= indent) {
+            flush(&mut description, &stack, &mut data);
+            stack.pop();
+        }
+        match stack.last() {
+            // keep the text's own indentation relative to the item
+            Some(&(open, _)) => description.push(after(line, indent.min(open + INDENT))),
+            None => description.clear(),
+        }
+    }
+    flush(&mut description, &stack, &mut data);
+    Ok(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip_keeps_nesting_and_descriptions() {
+        let data = Dataset {
+            tasks: vec![
+                Task {
+                    id: Some(1),
+                    title: "Prepare the release".to_string(),
+                    description: Some("Check the changelog first.\n  - not a task".to_string()),
+                    ..Task::default()
+                },
+                Task { id: Some(2), title: "Bump the version".to_string(), completed: true, parent_id: Some(1), ..Task::default() },
+            ],
+            ..Dataset::default()
+        };
+        let parsed = parse(&render(&data)).unwrap();
+        let [release, bump] = parsed.tasks.as_slice() else { panic!("expected two tasks") };
+        assert_eq!(release.title, "Prepare the release");
+        assert_eq!(release.description, data.tasks[0].description);
+        assert_eq!(bump.parent_id, Some(1));
+        assert!(bump.completed);
+    }
+
+    #[test]
+    fn non_ascii_indentation_is_cut_on_char_boundaries() {
+        let data = parse("- [ ] Item\n \u{a0}note\n \u{a0}\u{2003} deeper\n").unwrap();
+        assert_eq!(data.tasks[0].description.as_deref(), Some("note\n\u{2003} deeper"));
+
+        let data = parse("- [ ] Item\n\u{a0}\u{a0}- [x] Sub\n").unwrap();
+        assert_eq!(data.tasks[1].parent_id, Some(1));
+    }
+}
@@ -0,0 +1,9 @@
+pub mod csv;
+pub mod dataset;
+pub mod error;
+pub mod format;
+pub mod ics;
+pub mod json;
+pub mod markdown;
+pub mod taskwarrior;
+pub mod todotxt;
@@ -0,0 +1,209 @@
+//! Taskwarrior's `task export` output: a JSON array of tasks, or one task
+//! object per line from older versions.
+//!
+//! description, status, entry/modified/end, due, priority, project, tags,
+//! annotations and simple `recur` rules map onto `Task`. The UUID is kept in
+//! `Task::uid`, so importing a newer export again matches tasks instead of
+//! adding them twice. Deleted tasks and recurring templates are skipped.
+
+use chrono::{DateTime, Datelike, Local, NaiveDateTime, NaiveTime, Utc};
+use serde::Deserialize;
+
+use crate::exchange::dataset::Dataset;
+use crate::exchange::error::ExchangeError;
+use crate::persistence::annotation::Annotation;
+use crate::persistence::persistence::{Priority, Task};
+use crate::persistence::recurrence::Recurrence;
+
+#[derive(Debug, Deserialize)]
+struct Record {
+    uuid: String,
+    description: String,
+    status: String,
+    entry: Option<String>,
+    modified: Option<String>,
+    end: Option<String>,
+    due: Option<String>,
+    priority: Option<String>,
+    project: Option<String>,
+    recur: Option<String>,
+    #[serde(default)]
+    tags: Vec<String>,
+    #[serde(default)]
+    annotations: Vec<RecordAnnotation>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RecordAnnotation {
+    entry: Option<String>,
+    description: String,
+}
+
+/// Whether `input` looks like `task export` output rather than a todo
+/// export, which is a single object.
+pub fn detect(input: &str) -> bool {
+    let first = input.lines().map(str::trim).find(|l| !l.is_empty()).unwrap_or_default();
+    first.starts_with('[') || (first.starts_with('{') && first.contains("\"uuid\""))
+}
+
+pub fn parse(input: &str) -> Result<Dataset, ExchangeError> {
+    let records: Vec<Record> = if input.trim_start().starts_with('[') {
+        serde_json::from_str(input).map_err(|e| ExchangeError::Parse(e.to_string()))?
+    } else {
+        input
+            .lines()
+            .map(|l| l.trim().trim_end_matches(','))
+            .enumerate()
+            .filter(|(_, l)| !l.is_empty())
+            .map(|(i, l)| serde_json::from_str(l).map_err(|e| ExchangeError::Parse(format!("line {}: {}", i + 1, e))))
+            .collect::<Result<_, _>>()?
+    };
+
+    let mut data = Dataset::default();
+    for (i, record) in records.into_iter().enumerate() {
+        if let Some(task) = task(record, &mut data).map_err(|e| ExchangeError::Parse(format!("task {}: {}", i + 1, e)))? {
+            data.tasks.push(task);
+        }
+    }
+    Ok(data)
+}
+
+/// The task for a record, or `None` for records that aren't imported.
+fn task(record: Record, data: &mut Dataset) -> Result<Option<Task>, String> {
+    let completed = match record.status.as_str() {
+        "pending" | "waiting" => false,
+        "completed" => true,
+        "deleted" | "recurring" => return Ok(None),
+        other => return Err(format!("unknown status '{}'", other)),
+    };
+    let title = record.description.split_whitespace().collect::<Vec<_>>().join(" ");
+    if title.is_empty() {
+        return Err("the description is empty".to_string());
+    }
+    let time = |value: &Option<String>| value.as_deref().map(parse_time).transpose();
+
+    let mut task = Task {
+        title,
+        completed,
+        uid: Some(record.uuid),
+        created_at: time(&record.entry)?,
+        updated_at: time(&record.modified)?,
+        completed_at: time(&record.end)?.filter(|_| completed),
+        // Taskwarrior dates without a time are due at midnight; here they
+        // are due by the end of that day
+        due: time(&record.due)?.map(|due| {
+            if due.time() == NaiveTime::MIN { due.date().and_time(Task::end_of_day()) } else { due }
+        }),
+        priority: match record.priority.as_deref() {
+            Some("H") => Priority::High,
+            Some("M") => Priority::Medium,
+            Some("L") => Priority::Low,
+            _ => Priority::None,
+        },
+        ..Task::default()
+    };
+    task.recurrence = record.recur.as_deref().and_then(|r| recurrence(r, task.due));
+    if let Some(project) = record.project.as_deref().filter(|p| !p.is_empty()) {
+        task.project_id = data.project_id(project);
+    }
+    for tag in record.tags {
+        if !task.tags.iter().any(|t| t.eq_ignore_ascii_case(&tag)) {
+            data.add_tag(&tag);
+            task.tags.push(tag);
+        }
+    }
+    for annotation in record.annotations {
+        task.annotations.push(Annotation { entry: time(&annotation.entry)?, description: annotation.description });
+    }
+    // oldest first, as they are read back
+    task.annotations.sort_by_key(|a| a.entry);
+    Ok(Some(task))
+}
+
+/// A Taskwarrior timestamp, `20260301T120000Z` (or ISO 8601), in local time.
+fn parse_time(value: &str) -> Result<NaiveDateTime, String> {
+    let utc = NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%SZ")
+        .or_else(|_| NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%SZ"))
+        .map_err(|_| format!("invalid date '{}'", value))?;
+    Ok(DateTime::<Utc>::from_naive_utc_and_offset(utc, Utc).with_timezone(&Local).naive_local())
+}
+
+/// Daily, weekly and monthly rules; the day comes from the due date.
+/// Taskwarrior's other periods have no equivalent and are dropped.
+fn recurrence(recur: &str, due: Option<NaiveDateTime>) -> Option<Recurrence> {
+    match recur {
+        "daily" | "day" | "1d" => Some(Recurrence::Daily),
+        "weekly" | "week" | "1w" => Some(Recurrence::Weekly(vec![due?.weekday()])),
+        "monthly" | "month" | "1m" | "1mo" => Some(Recurrence::Monthly(due?.day())),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exchange::dataset::ImportMode;
+    use crate::persistence::persistence::Persistence;
+
+    const EXPORT: &str = r#"[
+{"uuid":"a1b2c3d4-0000-4000-8000-000000000001","description":"Call  the bank","status":"pending","entry":"20260301T093000Z","modified":"20260302T100000Z","due":"20260304T120000Z","priority":"H","project":"Home Office","tags":["on the phone","admin"],"recur":"weekly","annotations":[{"entry":"20260303T080000Z","description":"second"},{"entry":"20260302T080000Z","description":"first"}]},
+{"uuid":"a1b2c3d4-0000-4000-8000-000000000002","description":"Old chore","status":"deleted"},
+{"uuid":"a1b2c3d4-0000-4000-8000-000000000003","description":"Filed taxes","status":"completed","end":"20260305T170000Z","project":"Home Office"}
+]"#;
+
+    #[test]
+    fn export_fields_map_onto_tasks() {
+        assert!(detect(EXPORT));
+        let data = parse(EXPORT).unwrap();
+        let names: Vec<&str> = data.projects.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, ["Home Office"]);
+
+        let [first, done] = data.tasks.as_slice() else { panic!("expected the deleted task to be skipped") };
+        assert_eq!(first.uid.as_deref(), Some("a1b2c3d4-0000-4000-8000-000000000001"));
+        assert_eq!(first.title, "Call the bank");
+        assert!(!first.completed);
+        assert_eq!(first.created_at, parse_time("20260301T093000Z").ok());
+        assert_eq!(first.due, parse_time("20260304T120000Z").ok());
+        assert_eq!(first.priority, Priority::High);
+        assert_eq!(first.project_id, data.projects[0].id);
+        assert_eq!(first.tags, ["on the phone", "admin"]);
+        assert_eq!(first.recurrence, Some(Recurrence::Weekly(vec![first.due.unwrap().weekday()])));
+        let notes: Vec<&str> = first.annotations.iter().map(|a| a.description.as_str()).collect();
+        assert_eq!(notes, ["first", "second"]);
+
+        assert!(done.completed);
+        assert_eq!(done.completed_at, parse_time("20260305T170000Z").ok());
+        assert_eq!(done.project_id, first.project_id);
+    }
+
+    #[test]
+    fn line_per_task_exports_are_read() {
+        let input = "{\"uuid\":\"x\",\"description\":\"One\",\"status\":\"pending\"},\n\n{\"uuid\":\"y\",\"description\":\"Two\",\"status\":\"waiting\"}\n";
+        assert!(detect(input));
+        let titles: Vec<String> = parse(input).unwrap().tasks.into_iter().map(|t| t.title).collect();
+        assert_eq!(titles, ["One", "Two"]);
+        assert!(!detect("{\"version\":1,\"tasks\":[]}"));
+    }
+
+    #[test]
+    fn invalid_records_name_the_task() {
+        let input = r#"[{"uuid":"x","description":"Fine","status":"pending"},{"uuid":"y","description":"  ","status":"pending"}]"#;
+        assert!(matches!(parse(input), Err(ExchangeError::Parse(m)) if m.starts_with("task 2:")));
+        let input = r#"[{"uuid":"x","description":"Fine","status":"pending","due":"tomorrow"}]"#;
+        assert!(matches!(parse(input), Err(ExchangeError::Parse(m)) if m.contains("invalid date 'tomorrow'")));
+    }
+
+    #[test]
+    fn importing_a_newer_export_matches_tasks_by_uuid() {
+        let p = Persistence::in_memory();
+        let report = parse(EXPORT).unwrap().import(&p, ImportMode::Merge, false).unwrap();
+        assert_eq!(report.tasks.added, 2);
+
+        let newer = EXPORT.replace("20260302T100000Z", "20990101T000000Z").replace("Call  the bank", "Call the bank again");
+        let report = parse(&newer).unwrap().import(&p, ImportMode::Merge, false).unwrap();
+        assert_eq!((report.projects.added, report.tasks.added, report.tasks.updated), (0, 0, 1));
+        let tasks = p.get_all::<Task>().unwrap();
+        assert_eq!(tasks.len(), 2);
+        assert!(tasks.iter().any(|t| t.title == "Call the bank again"));
+    }
+}
@@ -0,0 +1,354 @@
+//! The todo.txt format (<https://github.com/todotxt/todo.txt>): one task per
+//! line, e.g.
+//!
+//! ```text
+//! (A) 2026-03-01 Call the bank +Finance @phone due:2026-03-04
+//! x 2026-03-02 2026-03-01 Pay rent +Home pri:B
+//! ```
+//!
+//! Priorities A to D map to urgent, high, medium and low. The first
+//! `+project` is the task's project and every `@context` becomes a tag.
+//! `due:` and the common `rec:` forms are read into the task; any other
+//! `key:value` pair is kept in `Task::extensions` and written back on export.
+//! Descriptions, subtasks and due times have no place in a line and are
+//! dropped on export.
+//!
+//! Spaces in project and tag names are written as `_`, and read back as
+//! spaces on lines with a `uid:`; in files from elsewhere `@follow_up` stays
+//! as it is. Every exported task carries a `uid:`, its own identifier from an
+//! earlier import or else the one the iCalendar export uses, so importing
+//! the file again updates the same tasks instead of adding them twice.
+
+use chrono::{Datelike, NaiveDate, NaiveDateTime};
+
+use crate::exchange::dataset::Dataset;
+use crate::exchange::error::ExchangeError;
+use crate::exchange::ics;
+use crate::persistence::extensions::Extensions;
+use crate::persistence::persistence::{Priority, Task};
+use crate::persistence::project::Project;
+use crate::persistence::recurrence::Recurrence;
+
+const DATE: &str = "%Y-%m-%d";
+
+pub fn render(data: &Dataset) -> String {
+    let mut out = String::new();
+    for task in &data.tasks {
+        let project = data.projects.iter().find(|p| p.id == task.project_id);
+        out.push_str(&render_task(task, project));
+        out.push('\n');
+    }
+    out
+}
+
+fn render_task(task: &Task, project: Option<&Project>) -> String {
+    let mut words: Vec<String> = Vec::new();
+    let date = |dt: &NaiveDateTime| dt.format(DATE).to_string();
+
+    if task.completed {
+        words.push("x".to_string());
+        // a creation date is only allowed after a completion date
+        if let Some(completed) = task.completed_at.as_ref().or(task.created_at.as_ref()) {
+            words.push(date(completed));
+            words.extend(task.created_at.as_ref().map(date));
+        }
+    } else {
+        words.extend(priority_letter(task.priority).map(|p| format!("({})", p)));
+        words.extend(task.created_at.as_ref().map(date));
+    }
+
+    let title = task.title.split_whitespace().collect::<Vec<_>>().join(" ");
+    match project.filter(|p| !p.is_inbox()) {
+        // the first +project is read back as the project, so it has to
+        // come before any the title mentions
+        Some(project) if title.split(' ').any(|w| w.len() > 1 && w.starts_with('+')) => {
+            words.push(format!("+{}", word(&project.name)));
+            words.push(title);
+        }
+        Some(project) => {
+            words.push(title);
+            words.push(format!("+{}", word(&project.name)));
+        }
+        None => words.push(title),
+    }
+    words.extend(task.tags.iter().map(|tag| format!("@{}", word(tag))));
+
+    let mut extensions = task.extensions.clone();
+    if let Some(due) = &task.due {
+        extensions.insert("due", &date(due));
+    }
+    if let Some(rec) = task.recurrence.as_ref().and_then(|rule| rec(rule, task.due)) {
+        extensions.insert("rec", &rec);
+    }
+    // completed tasks lose their (A) prefix, so keep it as pri:A like todo.sh
+    if let Some(letter) = priority_letter(task.priority).filter(|_| task.completed) {
+        extensions.insert("pri", &letter.to_string());
+    }
+    let uid = ics::uid(task);
+    // identifiers that wouldn't read back as one word are left out
+    if Extensions::parse_pair(&format!("uid:{}", uid)).is_some() {
+        extensions.insert("uid", &uid);
+    }
+    if !extensions.is_empty() {
+        words.push(extensions.to_string());
+    }
+    words.join(" ")
+}
+
+/// Project and tag names can't contain spaces in a line; see `name`.
+fn word(name: &str) -> String {
+    name.split_whitespace().collect::<Vec<_>>().join("_")
+}
+
+/// The project or tag name a `word` stands for. Only lines written by the
+/// export, which all carry a `uid:`, have their spaces escaped; in other
+/// files an `_` is part of the name.
+fn name(word: &str, exported: bool) -> String {
+    if exported { word.replace('_', " ") } else { word.to_string() }
+}
+
+fn priority_letter(priority: Priority) -> Option<char> {
+    match priority {
+        Priority::None => None,
+        Priority::Low => Some('D'),
+        Priority::Medium => Some('C'),
+        Priority::High => Some('B'),
+        Priority::Urgent => Some('A'),
+    }
+}
+
+/// Letters after D are all low priority.
+fn priority_from_letter(letter: char) -> Option<Priority> {
+    match letter {
+        'A' => Some(Priority::Urgent),
+        'B' => Some(Priority::High),
+        'C' => Some(Priority::Medium),
+        'D'..='Z' => Some(Priority::Low),
+        _ => None,
+    }
+}
+
+/// The `rec:` value for a repeat rule: `Nd` repeats N days after
+/// completion, `+1d`, `+1w` and `+1m` from the due date. Rules it can't
+/// express, such as weekly on several days, are left out.
+fn rec(rule: &Recurrence, due: Option<NaiveDateTime>) -> Option<String> {
+    match rule {
+        Recurrence::Daily => Some("+1d".to_string()),
+        Recurrence::AfterCompletion(days) => Some(format!("{}d", days)),
+        Recurrence::Weekly(days) if days.len() == 1 && due.map(|d| d.weekday()) == Some(days[0]) => {
+            Some("+1w".to_string())
+        }
+        Recurrence::Monthly(day) if due.map(|d| d.day()) == Some(*day) => Some("+1m".to_string()),
+        _ => None,
+    }
+}
+
+/// The repeat rule for a `rec:` value; weekly and monthly rules take the
+/// day from the due date.
+fn parse_rec(value: &str, due: Option<NaiveDateTime>) -> Option<Recurrence> {
+    let (strict, period) = match value.strip_prefix('+') {
+        Some(period) => (true, period),
+        None => (false, value),
+    };
+    match (strict, period) {
+        (true, "1d") => Some(Recurrence::Daily),
+        (true, "1w") => Some(Recurrence::Weekly(vec![due?.weekday()])),
+        (true, "1m") => Some(Recurrence::Monthly(due?.day())),
+        (false, _) => match period.strip_suffix('d')?.parse() {
+            Ok(days) if days > 0 => Some(Recurrence::AfterCompletion(days)),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+pub fn parse(input: &str) -> Result<Dataset, ExchangeError> {
+    let mut data = Dataset::default();
+    for (number, line) in input.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let mut task = parse_task(line, &mut data)
+            .map_err(|e| ExchangeError::Parse(format!("line {}: {}", number + 1, e)))?;
+        // a task exported from here keeps its id, unless the file repeats it
+        if task.id.is_some_and(|id| data.tasks.iter().any(|t| t.id == Some(id))) {
+            task.id = None;
+        }
+        data.tasks.push(task);
+    }
+    Ok(data)
+}
+
+/// Parses one line, adding the projects and tags it names to `data`.
+fn parse_task(line: &str, data: &mut Dataset) -> Result<Task, String> {
+    let mut words = line.split_whitespace().peekable();
+    let mut task = Task::default();
+    let date = |word: Option<&&str>| word.and_then(|w| NaiveDate::parse_from_str(w, DATE).ok());
+    let midnight = |date: NaiveDate| date.and_hms_opt(0, 0, 0);
+
+    if words.peek() == Some(&"x") {
+        words.next();
+        task.completed = true;
+        if let Some(completed) = date(words.peek()) {
+            words.next();
+            task.completed_at = midnight(completed);
+            if let Some(created) = date(words.peek()) {
+                words.next();
+                task.created_at = midnight(created);
+            }
+        }
+    } else {
+        let priority = words.peek().and_then(|w| {
+            let letter = w.strip_prefix('(')?.strip_suffix(')')?;
+            let mut chars = letter.chars();
+            chars.next().filter(|_| chars.next().is_none()).and_then(priority_from_letter)
+        });
+        if let Some(priority) = priority {
+            words.next();
+            task.priority = priority;
+        }
+        if let Some(created) = date(words.peek()) {
+            words.next();
+            task.created_at = midnight(created);
+        }
+    }
+
+    let mut title = Vec::new();
+    let mut extensions = Extensions::default();
+    let mut project = None;
+    let mut tags = Vec::new();
+    for word in words {
+        // further +projects stay in the title, a task has only one
+        if let Some(name) = word.strip_prefix('+').filter(|n| !n.is_empty())
+            && project.is_none()
+        {
+            project = Some(name);
+            continue;
+        }
+        if let Some(tag) = word.strip_prefix('@').filter(|n| !n.is_empty()) {
+            tags.push(tag);
+            continue;
+        } else if let Some((key, value)) = Extensions::parse_pair(word) {
+            extensions.insert(key, value);
+            continue;
+        }
+        title.push(word);
+    }
+
+    // known keys are only taken out when their value makes sense
+    if let Some(due) = extensions.0.get("due").and_then(|d| Task::parse_due(d).ok()) {
+        task.due = Some(due);
+        extensions.0.remove("due");
+    }
+    if let Some(rule) = extensions.0.get("rec").and_then(|r| parse_rec(r, task.due)) {
+        task.recurrence = Some(rule);
+        extensions.0.remove("rec");
+    }
+    if task.completed {
+        let letter = extensions.0.get("pri").and_then(|p| p.chars().next().filter(|_| p.len() == 1));
+        if let Some(priority) = letter.and_then(priority_from_letter) {
+            task.priority = priority;
+            extensions.0.remove("pri");
+        }
+    }
+    let exported = extensions.0.contains_key("uid");
+    if let Some(project) = project {
+        task.project_id = data.project_id(&name(project, exported));
+    }
+    for tag in tags {
+        let tag = name(tag, exported);
+        if !task.tags.iter().any(|t| t.eq_ignore_ascii_case(&tag)) {
+            task.tags.push(tag.clone());
+        }
+        data.add_tag(&tag);
+    }
+    // matched on by merge imports; see `ics::uid`
+    if let Some(uid) = extensions.0.remove("uid") {
+        match ics::id_from_uid(&uid) {
+            Some(id) => {
+                task.id = Some(id);
+                task.created_at = ics::created_from_uid(&uid).or(task.created_at);
+            }
+            None => task.uid = Some(uid),
+        }
+    }
+    task.extensions = extensions;
+
+    task.title = title.join(" ");
+    if task.title.is_empty() {
+        return Err("the task has no text".to_string());
+    }
+    Ok(task)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exchange::dataset::ImportMode;
+    use crate::persistence::persistence::Persistence;
+
+    #[test]
+    fn round_trip_keeps_fields_and_names_with_spaces() {
+        let due = NaiveDate::from_ymd_opt(2026, 3, 4).unwrap().and_time(Task::end_of_day());
+        let created = NaiveDate::from_ymd_opt(2026, 3, 1).unwrap().and_hms_opt(9, 30, 15);
+        let data = Dataset {
+            projects: vec![Project { id: Some(2), name: "My Project".to_string(), ..Project::default() }],
+            tasks: vec![Task {
+                id: Some(5),
+                title: "Call the bank".to_string(),
+                priority: Priority::Urgent,
+                due: Some(due),
+                project_id: Some(2),
+                tags: vec!["on the phone".to_string()],
+                recurrence: Some(Recurrence::AfterCompletion(3)),
+                created_at: created,
+                ..Task::default()
+            }],
+            ..Dataset::default()
+        };
+
+        let line = render(&data);
+        assert!(line.contains("+My_Project @on_the_phone"), "{}", line);
+        let parsed = parse(&line).unwrap();
+        assert_eq!(parsed.projects[0].name, "My Project");
+        assert_eq!(parsed.tags[0].name, "on the phone");
+        let task = &parsed.tasks[0];
+        assert_eq!((task.id, task.created_at), (Some(5), created));
+        assert_eq!(task.title, "Call the bank");
+        assert_eq!(task.priority, Priority::Urgent);
+        assert_eq!(task.due, Some(due));
+        assert_eq!(task.tags, ["on the phone"]);
+        assert_eq!(task.recurrence, Some(Recurrence::AfterCompletion(3)));
+        assert!(task.extensions.is_empty());
+    }
+
+    #[test]
+    fn imported_identifiers_are_written_back() {
+        let line = "Water plants uid:7f3c2a10-1b2c-4d5e-8f90-0a1b2c3d4e5f";
+        let task = &parse(line).unwrap().tasks[0];
+        assert_eq!(task.uid.as_deref(), Some("7f3c2a10-1b2c-4d5e-8f90-0a1b2c3d4e5f"));
+        assert_eq!(task.id, None);
+        assert_eq!(render(&Dataset { tasks: vec![task.clone()], ..Dataset::default() }).trim_end(), line);
+    }
+
+    #[test]
+    fn importing_an_export_again_adds_nothing() {
+        let p = Persistence::in_memory();
+        let project = p.save(&Project { name: "My Project".to_string(), ..Project::default() }).unwrap();
+        p.save(&Task { title: "Pay rent".to_string(), project_id: project, tags: vec!["home".to_string()], ..Task::default() })
+            .unwrap();
+
+        let data = parse(&render(&Dataset::load(&p).unwrap())).unwrap();
+        let report = data.import(&p, ImportMode::Merge, false).unwrap();
+        assert_eq!((report.projects.added, report.tags.added, report.tasks.added), (0, 0, 0));
+        assert_eq!(p.get_all::<Task>().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn underscores_in_other_files_are_kept() {
+        let data = parse("Email Sam +side_project @follow_up").unwrap();
+        assert_eq!(data.projects[0].name, "side_project");
+        assert_eq!(data.tags[0].name, "follow_up");
+        assert_eq!(data.tasks[0].tags, ["follow_up"]);
+    }
+}
@@ -8,22 +8,33 @@ use crossterm::{
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
 
+mod api;
+mod cli;
+mod exchange;
+mod mcp;
 mod persistence;
 mod screens;
 mod system;
 mod ui;
 
 use screens::menu::MenuScreen;
+use screens::tasks::ListOptions;
 use ui::screen::{Screen, ScreenAction};
 
 fn main() -> Result<(), io::Error> {
+    // Any arguments select a non-interactive subcommand; bare `todo` opens the UI
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if !args.is_empty() {
+        std::process::exit(cli::commands::run(&args));
+    }
+
     enable_raw_mode()?;
     let mut stdout = io::stdout();
     execute!(stdout, EnterAlternateScreen)?;
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
 
-    let mut current_screen: Box<dyn Screen> = Box::new(MenuScreen::new());
+    let mut current_screen: Box<dyn Screen> = Box::new(MenuScreen::new(ListOptions::default()));
 
     loop {
         terminal.draw(|f| {
@@ -31,28 +42,27 @@ fn main() -> Result<(), io::Error> {
             current_screen.render(f, f.area());
         })?;
 
-        if event::poll(std::time::Duration::from_millis(16))? {
-            if let Event::Key(key) = event::read()? {
-                if key.kind == event::KeyEventKind::Press {
-                    match current_screen.handle_input(key) {
-                        Some(ScreenAction::Exit) => break,
-                        Some(ScreenAction::UpdateAndExit) => {
-                            // Restore terminal before running update
-                            disable_raw_mode()?;
-                            execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
-                            
-                            println!("Starting update process...");
-                            if let Err(e) = crate::system::update::perform_update() {
-                                eprintln!("Update failed: {}", e);
-                            }
-                            return Ok(());
-                        }
-                        Some(ScreenAction::Switch(next_screen)) => {
-                            current_screen = next_screen;
-                        }
-                        None => {}
+        if event::poll(std::time::Duration::from_millis(16))?
+            && let Event::Key(key) = event::read()?
+            && key.kind == event::KeyEventKind::Press
+        {
+            match current_screen.handle_input(key) {
+                Some(ScreenAction::Exit) => break,
+                Some(ScreenAction::UpdateAndExit) => {
+                    // Restore terminal before running update
+                    disable_raw_mode()?;
+                    execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+                    
+                    println!("Starting update process...");
+                    if let Err(e) = crate::system::update::perform_update() {
+                        eprintln!("Update failed: {}", e);
                     }
+                    return Ok(());
+                }
+                Some(ScreenAction::Switch(next_screen)) => {
+                    current_screen = next_screen;
                 }
+                None => {}
             }
         }
     }
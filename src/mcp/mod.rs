@@ -0,0 +1,3 @@
+pub mod prompts;
+pub mod resources;
+pub mod server;
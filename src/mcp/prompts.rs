@@ -0,0 +1,85 @@
+use chrono::Local;
+use rmcp::model::{GetPromptResult, JsonObject, Prompt, PromptArgument, PromptMessage, PromptMessageRole};
+
+use crate::mcp::server::{internal_error, to_json};
+use crate::persistence::persistence::{Persistence, Task};
+
+pub fn list() -> Vec<Prompt> {
+    vec![
+        Prompt::new(
+            "plan_my_day",
+            Some("Plan today around the current open tasks"),
+            Some(vec![PromptArgument {
+                name: "hours".to_string(),
+                description: Some("Hours available for work today".to_string()),
+                required: Some(false),
+            }]),
+        ),
+        Prompt::new(
+            "break_down_task",
+            Some("Split a task into subtasks"),
+            Some(vec![PromptArgument {
+                name: "id".to_string(),
+                description: Some("Id of the task to break down".to_string()),
+                required: Some(true),
+            }]),
+        ),
+    ]
+}
+
+/// Fills in prompt `name` with the current tasks.
+pub fn get(persistence: &Persistence, name: &str, arguments: Option<&JsonObject>) -> Result<GetPromptResult, rmcp::Error> {
+    let argument = |key: &str| {
+        arguments
+            .and_then(|args| args.get(key))
+            .and_then(|value| value.as_str())
+            .map(str::trim)
+            .filter(|value| !value.is_empty())
+    };
+    let now = Local::now().naive_local();
+
+    let (description, text) = match name {
+        "plan_my_day" => {
+            let mut tasks = persistence.get_all::<Task>().map_err(internal_error)?;
+            tasks.retain(|task| !task.completed);
+            let hours = argument("hours")
+                .map(|hours| format!(" I have {} hours available.", hours))
+                .unwrap_or_default();
+            let text = format!(
+                "Help me plan my day. It is {}.{}\n\n\
+                 These are my open tasks, as JSON, most important first:\n\n{}\n\n\
+                 Pick what I should work on today and in which order. Put overdue \
+                 and due-today tasks first, respect priorities, and say briefly why. \
+                 Mention anything that should be rescheduled.",
+                now.format("%A %Y-%m-%d %H:%M"),
+                hours,
+                to_json(&tasks).map_err(internal_error)?
+            );
+            ("Plan today around the current open tasks", text)
+        }
+        "break_down_task" => {
+            let id = argument("id")
+                .and_then(|id| id.parse::<i64>().ok())
+                .ok_or_else(|| rmcp::Error::invalid_params("'id' must be a task id", None))?;
+            let task = persistence
+                .get::<Task>(id)
+                .map_err(internal_error)?
+                .ok_or_else(|| rmcp::Error::invalid_params(format!("No task with id {}", id), None))?;
+            let text = format!(
+                "Break this task into small, concrete subtasks that each take under \
+                 an hour:\n\n{}\n\n\
+                 List them in the order they should be done. Once I agree, add them \
+                 with the add_task tool using parent_id {}.",
+                to_json(&task).map_err(internal_error)?,
+                id
+            );
+            ("Split a task into subtasks", text)
+        }
+        _ => return Err(rmcp::Error::invalid_params(format!("No prompt named '{}'", name), None)),
+    };
+
+    Ok(GetPromptResult {
+        description: Some(description.to_string()),
+        messages: vec![PromptMessage::new_text(PromptMessageRole::User, text)],
+    })
+}
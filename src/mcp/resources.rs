@@ -0,0 +1,104 @@
+use chrono::{Local, NaiveDateTime};
+use rmcp::model::{AnnotateAble, RawResource, RawResourceTemplate, Resource, ResourceContents, ResourceTemplate};
+
+use crate::mcp::server::{internal_error, to_json};
+use crate::persistence::persistence::{DueStatus, Persistence, Task};
+
+const MIME_TYPE: &str = "application/json";
+
+/// Prefix of the URI of a single task, e.g. `todo://task/12`.
+pub const TASK_URI: &str = "todo://task/";
+
+/// Filtered lists of tasks published as resources.
+#[derive(Debug, Clone, Copy)]
+pub enum View {
+    Open,
+    Completed,
+    Overdue,
+}
+
+impl View {
+    pub const ALL: [View; 3] = [View::Open, View::Completed, View::Overdue];
+
+    pub fn uri(self) -> &'static str {
+        match self {
+            View::Open => "todo://tasks/open",
+            View::Completed => "todo://tasks/completed",
+            View::Overdue => "todo://tasks/overdue",
+        }
+    }
+
+    fn description(self) -> &'static str {
+        match self {
+            View::Open => "Tasks that are not completed yet, most important first",
+            View::Completed => "Completed tasks",
+            View::Overdue => "Open tasks whose due date has passed",
+        }
+    }
+
+    pub fn matches(self, task: &Task, now: NaiveDateTime) -> bool {
+        match self {
+            View::Open => !task.completed,
+            View::Completed => task.completed,
+            View::Overdue => task.due_status(now) == Some(DueStatus::Overdue),
+        }
+    }
+}
+
+fn json_contents(uri: &str, text: String) -> ResourceContents {
+    ResourceContents::TextResourceContents {
+        uri: uri.to_string(),
+        mime_type: Some(MIME_TYPE.to_string()),
+        text,
+    }
+}
+
+/// The views followed by every task.
+pub fn list(persistence: &Persistence) -> Result<Vec<Resource>, rmcp::Error> {
+    let mut resources: Vec<Resource> = View::ALL
+        .iter()
+        .map(|view| {
+            let mut resource = RawResource::new(view.uri(), format!("{:?} tasks", view));
+            resource.description = Some(view.description().to_string());
+            resource.mime_type = Some(MIME_TYPE.to_string());
+            resource.no_annotation()
+        })
+        .collect();
+
+    let tasks = persistence.get_all::<Task>().map_err(internal_error)?;
+    resources.extend(tasks.iter().filter_map(|task| {
+        let mut resource = RawResource::new(format!("{}{}", TASK_URI, task.id?), task.title.clone());
+        resource.mime_type = Some(MIME_TYPE.to_string());
+        Some(resource.no_annotation())
+    }));
+    Ok(resources)
+}
+
+pub fn templates() -> Vec<ResourceTemplate> {
+    vec![
+        RawResourceTemplate {
+            uri_template: format!("{}{{id}}", TASK_URI),
+            name: "Task".to_string(),
+            description: Some("A single task by id".to_string()),
+            mime_type: Some(MIME_TYPE.to_string()),
+        }
+        .no_annotation(),
+    ]
+}
+
+/// Contents of `uri`: a task as a JSON object, or a view as a JSON array.
+pub fn read(persistence: &Persistence, uri: &str) -> Result<ResourceContents, rmcp::Error> {
+    let not_found = || rmcp::Error::resource_not_found(format!("No resource {}", uri), None);
+
+    if let Some(id) = uri.strip_prefix(TASK_URI) {
+        let id = id.parse::<i64>().map_err(|_| not_found())?;
+        let task = persistence.get::<Task>(id).map_err(internal_error)?.ok_or_else(not_found)?;
+        return Ok(json_contents(uri, to_json(&task).map_err(internal_error)?));
+    }
+
+    let view = View::ALL.into_iter().find(|v| v.uri() == uri).ok_or_else(not_found)?;
+    let now = Local::now().naive_local();
+    let mut tasks = persistence.get_all::<Task>().map_err(internal_error)?;
+    tasks.retain(|task| view.matches(task, now));
+    Ok(json_contents(uri, to_json(&tasks).map_err(internal_error)?))
+}
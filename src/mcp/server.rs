@@ -0,0 +1,322 @@
+use std::net::{Ipv4Addr, SocketAddr};
+
+use rmcp::model::{
+    GetPromptRequestParam, GetPromptResult, Implementation, ListPromptsResult, ListResourceTemplatesResult,
+    ListResourcesResult, PaginatedRequestParam, ReadResourceRequestParam, ReadResourceResult, ServerCapabilities,
+    ServerInfo,
+};
+use rmcp::service::RequestContext;
+use rmcp::transport::sse_server::SseServer;
+use rmcp::transport::stdio;
+use rmcp::{schemars, tool, RoleServer, ServerHandler, ServiceExt};
+use serde::{Deserialize, Serialize};
+
+use crate::mcp::{prompts, resources};
+use crate::persistence::error::PersistenceError;
+use crate::persistence::filter::ast::Expr;
+use crate::persistence::filter::parser;
+use crate::persistence::persistence::{Persistence, Priority, Task};
+use crate::persistence::project::Project;
+use crate::persistence::query::{Status, TaskQuery};
+use crate::persistence::recurrence::Recurrence;
+
+pub const DEFAULT_PORT: u16 = 7420;
+
+/// Fields shared by `add_task` and `update_task`. Omitted fields are left
+/// alone; an empty string clears an optional field.
+#[derive(Debug, Default, Deserialize, schemars::JsonSchema)]
+pub struct TaskFields {
+    #[schemars(description = "Longer description of the task")]
+    pub description: Option<String>,
+    #[schemars(description = "Due date: YYYY-MM-DD, YYYY-MM-DD HH:MM, today or tomorrow")]
+    pub due: Option<String>,
+    #[schemars(description = "none, low, medium, high or urgent")]
+    pub priority: Option<String>,
+    #[schemars(description = "Name of the project the task belongs to (default: Inbox)")]
+    pub project: Option<String>,
+    #[schemars(description = "Tags of the task; replaces the existing tags")]
+    pub tags: Option<Vec<String>>,
+    #[schemars(description = "Repeat rule: daily, weekly:mon,fri, monthly:15 or after:3")]
+    pub repeat: Option<String>,
+    #[schemars(description = "Id of the parent task, making this a subtask")]
+    pub parent_id: Option<i64>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct ListTasksRequest {
+    #[schemars(description = "Include completed tasks")]
+    pub all: Option<bool>,
+    #[schemars(description = "Only list tasks of the project with this name")]
+    pub project: Option<String>,
+    #[schemars(description = "Filter expression, e.g. `tag:work due<7d priority>=high \"release notes\"`")]
+    pub query: Option<String>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct AddTaskRequest {
+    #[schemars(description = "Title of the new task")]
+    pub title: String,
+    #[serde(flatten)]
+    pub fields: TaskFields,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct UpdateTaskRequest {
+    #[schemars(description = "Id of the task to change")]
+    pub id: i64,
+    #[schemars(description = "New title")]
+    pub title: Option<String>,
+    #[serde(flatten)]
+    pub fields: TaskFields,
+}
+
+/// Result of `complete_task`.
+#[derive(Debug, Serialize)]
+struct Completed {
+    task: Task,
+    /// Next occurrence generated for a recurring task
+    next: Option<Task>,
+}
+
+/// MCP server backed by the same `tasks.db` as the UI. Every call opens its
+/// own `Persistence` so changes made elsewhere are always seen.
+#[derive(Debug, Clone, Default)]
+pub struct TodoServer;
+
+type ToolResult = Result<String, String>;
+
+pub fn open() -> Result<Persistence, String> {
+    let persistence = Persistence::new().map_err(|e| e.to_string())?;
+    persistence.sync_schema().map_err(|e| e.to_string())?;
+    Ok(persistence)
+}
+
+fn failed(e: PersistenceError) -> String {
+    e.to_string()
+}
+
+/// Error for failures that aren't the client's fault, for handlers that
+/// answer with protocol errors rather than tool results.
+pub fn internal_error(e: impl ToString) -> rmcp::Error {
+    rmcp::Error::internal_error(e.to_string(), None)
+}
+
+pub fn to_json<T: Serialize>(value: &T) -> ToolResult {
+    serde_json::to_string_pretty(value).map_err(|e| e.to_string())
+}
+
+fn find_task(persistence: &Persistence, id: i64) -> Result<Task, String> {
+    persistence
+        .get::<Task>(id)
+        .map_err(failed)?
+        .ok_or_else(|| format!("No task with id {}", id))
+}
+
+fn find_project(persistence: &Persistence, name: &str) -> Result<Project, String> {
+    persistence
+        .get_all::<Project>()
+        .map_err(failed)?
+        .into_iter()
+        .find(|p| p.name.eq_ignore_ascii_case(name.trim()))
+        .ok_or_else(|| format!("No project named '{}'", name.trim()))
+}
+
+impl TaskFields {
+    fn apply(&self, persistence: &Persistence, task: &mut Task) -> Result<(), String> {
+        let non_empty = |value: &str| Some(value.trim()).filter(|v| !v.is_empty()).map(str::to_string);
+
+        if let Some(desc) = &self.description {
+            task.description = non_empty(desc);
+        }
+        if let Some(due) = &self.due {
+            task.due = non_empty(due).map(|d| Task::parse_due(&d)).transpose()?;
+        }
+        if let Some(priority) = &self.priority {
+            task.priority = priority.parse::<Priority>()?;
+        }
+        if let Some(project) = &self.project {
+            task.project_id = find_project(persistence, project)?.id;
+        }
+        if let Some(tags) = &self.tags {
+            task.tags = crate::persistence::tag::Tag::parse_list(&tags.join(","));
+        }
+        if let Some(repeat) = &self.repeat {
+            task.recurrence = non_empty(repeat).map(|r| r.parse::<Recurrence>()).transpose()?;
+        }
+        if let Some(parent_id) = self.parent_id {
+            let parent = find_task(persistence, parent_id)?;
+            persistence.check_parent(task.id, parent_id).map_err(failed)?;
+            // subtasks live in their parent's project
+            task.project_id = parent.project_id;
+            task.parent_id = parent.id;
+        }
+        Ok(())
+    }
+}
+
+#[tool(tool_box)]
+impl TodoServer {
+    #[tool(description = "List tasks, most important first. Only open tasks unless `all` is set or `query` filters on status. `query` takes a filter expression: words to search for and fields such as status:, tag:, project:, priority>=, due<7d, title~TEXT and is:overdue, combined with and, or, not and parentheses.")]
+    fn list_tasks(&self, #[tool(aggr)] request: ListTasksRequest) -> ToolResult {
+        let filter = request.query.as_deref().map(parser::parse).transpose().map_err(|e| e.to_string())?.flatten();
+        let persistence = open()?;
+        let project_id = match &request.project {
+            Some(name) => find_project(&persistence, name)?.id,
+            None => None,
+        };
+        // like the CLI, a filter that mentions completion overrides `all`
+        let status = if request.all.unwrap_or(false) || filter.as_ref().is_some_and(Expr::mentions_status) {
+            Status::All
+        } else {
+            Status::Open
+        };
+        let tasks = persistence.tasks(&TaskQuery { project_id, status, filter, ..TaskQuery::default() }).map_err(failed)?;
+        to_json(&tasks)
+    }
+
+    #[tool(description = "Add a task and return it.")]
+    fn add_task(&self, #[tool(aggr)] request: AddTaskRequest) -> ToolResult {
+        let title = request.title.trim().to_string();
+        if title.is_empty() {
+            return Err("Title cannot be empty".to_string());
+        }
+        let persistence = open()?;
+        let mut task = Task { title, ..Default::default() };
+        request.fields.apply(&persistence, &mut task)?;
+        let id = persistence.add_logged(&task).map_err(failed)?.unwrap_or_default();
+        to_json(&find_task(&persistence, id)?)
+    }
+
+    #[tool(description = "Mark a task complete. For a recurring task the next occurrence is created and returned as `next`.")]
+    fn complete_task(
+        &self,
+        #[tool(param)]
+        #[schemars(description = "Id of the task")]
+        id: i64,
+    ) -> ToolResult {
+        let persistence = open()?;
+        let task = find_task(&persistence, id)?;
+        let next = persistence.set_completed_logged(&task, true, false).map_err(failed)?;
+        let next = next.map(|next| find_task(&persistence, next)).transpose()?;
+        to_json(&Completed { task: find_task(&persistence, id)?, next })
+    }
+
+    #[tool(description = "Change a task. Only the given fields are changed; pass an empty string to clear one.")]
+    fn update_task(&self, #[tool(aggr)] request: UpdateTaskRequest) -> ToolResult {
+        let persistence = open()?;
+        let mut task = find_task(&persistence, request.id)?;
+        if let Some(title) = &request.title {
+            if title.trim().is_empty() {
+                return Err("Title cannot be empty".to_string());
+            }
+            task.title = title.trim().to_string();
+        }
+        request.fields.apply(&persistence, &mut task)?;
+        persistence.update_logged(&task, "edit").map_err(failed)?;
+        to_json(&find_task(&persistence, request.id)?)
+    }
+
+    #[tool(description = "Delete a task together with its subtasks.")]
+    fn delete_task(
+        &self,
+        #[tool(param)]
+        #[schemars(description = "Id of the task")]
+        id: i64,
+    ) -> ToolResult {
+        let persistence = open()?;
+        let task = find_task(&persistence, id)?;
+        persistence.delete_logged(&task).map_err(failed)?;
+        Ok(format!("Deleted task {}", id))
+    }
+}
+
+#[tool(tool_box)]
+impl ServerHandler for TodoServer {
+    fn get_info(&self) -> ServerInfo {
+        ServerInfo {
+            capabilities: ServerCapabilities::builder()
+                .enable_tools()
+                .enable_resources()
+                .enable_prompts()
+                .build(),
+            server_info: Implementation {
+                name: "todo".to_string(),
+                version: env!("CARGO_PKG_VERSION").to_string(),
+            },
+            instructions: Some(
+                "Manages the user's todo list. Tasks are returned as JSON; ids are stable and used by the other tools."
+                    .to_string(),
+            ),
+            ..Default::default()
+        }
+    }
+
+    async fn list_resources(
+        &self,
+        _request: PaginatedRequestParam,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<ListResourcesResult, rmcp::Error> {
+        let persistence = open().map_err(internal_error)?;
+        Ok(ListResourcesResult { resources: resources::list(&persistence)?, next_cursor: None })
+    }
+
+    async fn list_resource_templates(
+        &self,
+        _request: PaginatedRequestParam,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<ListResourceTemplatesResult, rmcp::Error> {
+        Ok(ListResourceTemplatesResult { resource_templates: resources::templates(), next_cursor: None })
+    }
+
+    async fn read_resource(
+        &self,
+        request: ReadResourceRequestParam,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<ReadResourceResult, rmcp::Error> {
+        let persistence = open().map_err(internal_error)?;
+        Ok(ReadResourceResult { contents: vec![resources::read(&persistence, &request.uri)?] })
+    }
+
+    async fn list_prompts(
+        &self,
+        _request: PaginatedRequestParam,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<ListPromptsResult, rmcp::Error> {
+        Ok(ListPromptsResult { prompts: prompts::list(), next_cursor: None })
+    }
+
+    async fn get_prompt(
+        &self,
+        request: GetPromptRequestParam,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<GetPromptResult, rmcp::Error> {
+        let persistence = open().map_err(internal_error)?;
+        prompts::get(&persistence, &request.name, request.arguments.as_ref())
+    }
+}
+
+/// Serves MCP over SSE on `127.0.0.1:port` until Ctrl-C.
+pub fn run_sse(port: u16) -> std::io::Result<()> {
+    let runtime = tokio::runtime::Runtime::new()?;
+    runtime.block_on(async {
+        let bind = SocketAddr::from((Ipv4Addr::LOCALHOST, port));
+        let server = SseServer::serve(bind).await?;
+        eprintln!("MCP server listening on http://{}/sse (Ctrl-C to stop)", bind);
+        let ct = server.with_service(TodoServer::default);
+        tokio::signal::ctrl_c().await?;
+        ct.cancel();
+        Ok(())
+    })
+}
+
+/// Serves MCP over stdin/stdout until the client closes stdin. Stdout
+/// carries the JSON-RPC stream, so anything else goes to stderr.
+pub fn run_stdio() -> std::io::Result<()> {
+    let runtime = tokio::runtime::Runtime::new()?;
+    runtime.block_on(async {
+        eprintln!("MCP server running on stdio");
+        let service = TodoServer.serve(stdio()).await?;
+        service.waiting().await?;
+        Ok(())
+    })
+}
@@ -0,0 +1,12 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+
+/// A timestamped note attached to a task, as Taskwarrior keeps them.
+/// Stored in the `annotations` table and saved along with the task.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Annotation {
+    /// When the note was written
+    pub entry: Option<NaiveDateTime>,
+    pub description: String,
+}
@@ -0,0 +1,62 @@
+use std::{error::Error, fmt, io};
+
+#[derive(Debug)]
+pub enum PersistenceError {
+    /// Creating the data directory or database file failed
+    Io(io::Error),
+    /// Any failure reported by SQLite itself (locked, corrupt, bad SQL...)
+    Sqlite(rusqlite::Error),
+    /// The environment variable used to locate the data directory is unset
+    MissingEnv(&'static str),
+    /// The database was written by a newer build than this one
+    SchemaMismatch { found: u32, supported: u32 },
+    /// A task would become its own ancestor
+    InvalidParent { task: i64, parent: i64 },
+    /// The next occurrence of a recurring task would be due past the last
+    /// date chrono can represent
+    DateOutOfRange,
+}
+
+impl fmt::Display for PersistenceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PersistenceError::Io(e) => write!(f, "I/O error: {}", e),
+            PersistenceError::Sqlite(e) => write!(f, "Database error: {}", e),
+            PersistenceError::MissingEnv(var) => write!(f, "Environment variable {} is not set", var),
+            PersistenceError::SchemaMismatch { found, supported } => write!(
+                f,
+                "Database schema version {} is newer than this build supports ({}). Please update todo.",
+                found, supported
+            ),
+            PersistenceError::InvalidParent { task, parent } if task == parent => {
+                write!(f, "A task cannot be its own parent")
+            }
+            PersistenceError::InvalidParent { task, parent } => {
+                write!(f, "Task {} is a subtask of task {}, so it cannot be its parent", parent, task)
+            }
+            PersistenceError::DateOutOfRange => write!(f, "The next occurrence would be due too far in the future"),
+        }
+    }
+}
+
+impl Error for PersistenceError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            PersistenceError::Io(e) => Some(e),
+            PersistenceError::Sqlite(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for PersistenceError {
+    fn from(e: io::Error) -> Self {
+        PersistenceError::Io(e)
+    }
+}
+
+impl From<rusqlite::Error> for PersistenceError {
+    fn from(e: rusqlite::Error) -> Self {
+        PersistenceError::Sqlite(e)
+    }
+}
@@ -0,0 +1,92 @@
+use std::{collections::BTreeMap, fmt, str::FromStr};
+
+/// `key:value` pairs carried by a task that todo has no field for, such as
+/// `t:2026-03-01` or `pomodoros:3` from a todo.txt file. They aren't used
+/// here, only kept so exporting gives them back. Stored in the `extensions`
+/// column in the same form: `key:value` pairs separated by spaces.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Extensions(pub BTreeMap<String, String>);
+
+impl Extensions {
+    /// Splits a todo.txt word into key and value. Neither may be empty or
+    /// contain a colon, the key starts with a letter and the value doesn't
+    /// start with `/`, so URLs and times such as `10:30` are left as text.
+    pub fn parse_pair(word: &str) -> Option<(&str, &str)> {
+        let (key, value) = word.split_once(':')?;
+        let valid = key.starts_with(|c: char| c.is_alphabetic())
+            && !value.is_empty()
+            && !value.starts_with('/')
+            && !value.contains(':')
+            && !word.contains(char::is_whitespace);
+        valid.then_some((key, value))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn insert(&mut self, key: &str, value: &str) {
+        self.0.insert(key.to_string(), value.to_string());
+    }
+}
+
+impl fmt::Display for Extensions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let pairs: Vec<String> = self.0.iter().map(|(k, v)| format!("{}:{}", k, v)).collect();
+        write!(f, "{}", pairs.join(" "))
+    }
+}
+
+impl FromStr for Extensions {
+    type Err = String;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let mut extensions = Extensions::default();
+        for word in input.split_whitespace() {
+            let (key, value) = Self::parse_pair(word).ok_or_else(|| format!("Invalid extension '{}' (use key:value)", word))?;
+            extensions.insert(key, value);
+        }
+        Ok(extensions)
+    }
+}
+
+/// Empty extensions are stored as NULL.
+impl rusqlite::ToSql for Extensions {
+    fn to_sql(&self) -> rusqlite::Result<rusqlite::types::ToSqlOutput<'_>> {
+        if self.is_empty() {
+            return Ok(rusqlite::types::Null.into());
+        }
+        Ok(self.to_string().into())
+    }
+}
+
+impl rusqlite::types::FromSql for Extensions {
+    fn column_result(value: rusqlite::types::ValueRef<'_>) -> rusqlite::types::FromSqlResult<Self> {
+        if let rusqlite::types::ValueRef::Null = value {
+            return Ok(Extensions::default());
+        }
+        value
+            .as_str()?
+            .parse()
+            .map_err(|e: String| rusqlite::types::FromSqlError::Other(e.into()))
+    }
+}
+
+/// Serialized as a JSON object, e.g. `{"t": "2026-03-01"}`.
+impl serde::Serialize for Extensions {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serde::Serialize::serialize(&self.0, serializer)
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for Extensions {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let map: BTreeMap<String, String> = serde::Deserialize::deserialize(deserializer)?;
+        for (key, value) in &map {
+            if Self::parse_pair(&format!("{}:{}", key, value)) != Some((key, value)) {
+                return Err(serde::de::Error::custom(format!("Invalid extension '{}:{}'", key, value)));
+            }
+        }
+        Ok(Extensions(map))
+    }
+}
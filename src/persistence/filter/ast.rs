@@ -0,0 +1,145 @@
+use chrono::{Duration, NaiveDateTime, NaiveTime};
+
+use crate::persistence::persistence::Priority;
+use crate::persistence::search::Search;
+
+/// A parsed filter. Terms written one after the other must all hold.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    And(Vec<Expr>),
+    Or(Vec<Expr>),
+    Not(Box<Expr>),
+    Test(Test),
+}
+
+/// A single condition on a task.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Test {
+    /// `status:open` / `status:completed`
+    Completed(bool),
+    /// `tag:NAME`
+    Tag(String),
+    /// `tag:none`
+    Untagged,
+    /// `project:NAME` or `project~TEXT`
+    Project(TextMatch),
+    /// `priority>=high`
+    Priority(Cmp, Priority),
+    /// `due<7d`, `created:today`, `completed>=2026-03-01`
+    Date(DateField, Cmp, Moment),
+    /// `due:none`
+    NoDate(DateField),
+    /// `title~TEXT` or `title=TEXT`
+    Title(TextMatch),
+    /// `description~TEXT` or `description=TEXT`
+    Description(TextMatch),
+    /// `id<=12`
+    Id(Cmp, i64),
+    /// `parent:12`
+    Parent(i64),
+    /// `parent:none`
+    NoParent,
+    /// `is:overdue`: open and past its due date
+    Overdue,
+    /// `is:recurring`
+    Recurring,
+    /// `is:subtask`
+    Subtask,
+    /// Bare words, matched against titles and descriptions through the
+    /// full-text index
+    Words(Search),
+    /// A quoted string: its words next to each other, in order
+    Phrase(Search),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Cmp {
+    Eq,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TextMatch {
+    /// Contains the text, ignoring case
+    Contains(String),
+    /// Is exactly the text, ignoring case
+    Equals(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DateField {
+    Due,
+    Created,
+    Updated,
+    Completed,
+}
+
+impl DateField {
+    pub fn column(self) -> &'static str {
+        match self {
+            DateField::Due => "due",
+            DateField::Created => "created_at",
+            DateField::Updated => "updated_at",
+            DateField::Completed => "completed_at",
+        }
+    }
+}
+
+/// What a date is compared with: a whole day (`2026-03-01`, `today`, `7d`)
+/// or an instant (`now`, `2h`, `2026-03-01T14:30`).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Moment {
+    pub start: NaiveDateTime,
+    /// Start of the next day for a day; `start` for an instant
+    pub end: NaiveDateTime,
+}
+
+impl Moment {
+    pub fn day(at: NaiveDateTime) -> Self {
+        let start = at.date().and_time(NaiveTime::MIN);
+        Moment { start, end: start + Duration::days(1) }
+    }
+
+    pub fn instant(at: NaiveDateTime) -> Self {
+        Moment { start: at, end: at }
+    }
+
+    pub fn is_instant(&self) -> bool {
+        self.start == self.end
+    }
+}
+
+impl Expr {
+    /// The words searched for, e.g. to highlight or rank matches. Words
+    /// that must not appear (under `not`) are left out.
+    pub fn search(&self) -> Search {
+        let mut search = Search::default();
+        self.collect_words(&mut search);
+        search
+    }
+
+    fn collect_words(&self, search: &mut Search) {
+        match self {
+            Expr::And(exprs) | Expr::Or(exprs) => exprs.iter().for_each(|e| e.collect_words(search)),
+            Expr::Not(_) => {}
+            Expr::Test(Test::Words(words) | Test::Phrase(words)) => search.extend(words),
+            Expr::Test(_) => {}
+        }
+    }
+
+    /// Whether the filter says anything about completion, in which case
+    /// callers shouldn't add their own default (such as open tasks only).
+    pub fn mentions_status(&self) -> bool {
+        match self {
+            Expr::And(exprs) | Expr::Or(exprs) => exprs.iter().any(Expr::mentions_status),
+            Expr::Not(expr) => expr.mentions_status(),
+            Expr::Test(test) => matches!(
+                test,
+                Test::Completed(_) | Test::Date(DateField::Completed, ..) | Test::NoDate(DateField::Completed)
+            ),
+        }
+    }
+}
@@ -0,0 +1,36 @@
+use std::{error::Error, fmt};
+
+use super::lexer::Span;
+
+/// A filter that couldn't be parsed: what is wrong, and the part of the
+/// input it is about.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FilterError {
+    pub message: String,
+    pub span: Span,
+}
+
+impl FilterError {
+    pub fn new(message: impl Into<String>, span: Span) -> Self {
+        FilterError { message: message.into(), span }
+    }
+
+    /// `input` with the offending part marked below it, for terminals:
+    ///
+    /// ```text
+    /// status:opn tag:work
+    ///        ^^^
+    /// ```
+    pub fn pointer(&self, input: &str) -> String {
+        let width = self.span.end.saturating_sub(self.span.start).max(1);
+        format!("{}\n{}{}", input, " ".repeat(self.span.start), "^".repeat(width))
+    }
+}
+
+impl fmt::Display for FilterError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} (at column {})", self.message, self.span.start + 1)
+    }
+}
+
+impl Error for FilterError {}
@@ -0,0 +1,263 @@
+use super::error::FilterError;
+
+/// Where a token is in the input, in characters; `end` is exclusive.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+/// Comparison written between a field and its value.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Op {
+    /// `:`, the usual "is" or "has"
+    Colon,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    /// `~`, "contains"
+    Tilde,
+}
+
+impl Op {
+    pub fn symbol(self) -> &'static str {
+        match self {
+            Op::Colon => ":",
+            Op::Eq => "=",
+            Op::Ne => "!=",
+            Op::Lt => "<",
+            Op::Le => "<=",
+            Op::Gt => ">",
+            Op::Ge => ">=",
+            Op::Tilde => "~",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TokenKind {
+    LParen,
+    RParen,
+    /// `-` right before a term, negating it
+    Minus,
+    /// A bare word: text to search for, or one of `and`, `or` and `not`
+    Word(String),
+    /// A double-quoted string, with `\"` and `\\` unescaped
+    Quoted(String),
+    /// A field name and the operator right after it, e.g. `due<`. The
+    /// next token is always its value.
+    Field(String, Op),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Token {
+    pub kind: TokenKind,
+    pub span: Span,
+}
+
+pub fn tokenize(input: &str) -> Result<Vec<Token>, FilterError> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let start = i;
+        let kind = match c {
+            c if c.is_whitespace() => {
+                i += 1;
+                continue;
+            }
+            '(' => {
+                i += 1;
+                TokenKind::LParen
+            }
+            ')' => {
+                i += 1;
+                TokenKind::RParen
+            }
+            '"' => {
+                let (text, end) = quoted(&chars, i)?;
+                i = end;
+                TokenKind::Quoted(text)
+            }
+            '-' if chars.get(i + 1).is_some_and(|n| !n.is_whitespace() && *n != ')') => {
+                i += 1;
+                TokenKind::Minus
+            }
+            _ => {
+                let name_end = (i..chars.len()).find(|&j| !(chars[j].is_alphabetic() || chars[j] == '_')).unwrap_or(chars.len());
+                if let Some((op, op_end)) = operator(&chars, name_end).filter(|_| name_end > i) {
+                    let name: String = chars[i..name_end].iter().collect();
+                    let span = Span { start, end: op_end };
+                    tokens.push(Token { kind: TokenKind::Field(name.to_lowercase(), op), span });
+                    i = op_end;
+                    tokens.push(value(&chars, &mut i, span, &name, op)?);
+                    continue;
+                }
+                i = word_end(&chars, i);
+                TokenKind::Word(chars[start..i].iter().collect())
+            }
+        };
+        tokens.push(Token { kind, span: Span { start, end: i } });
+    }
+    Ok(tokens)
+}
+
+/// The operator starting at `i`, if any, and where it ends.
+fn operator(chars: &[char], i: usize) -> Option<(Op, usize)> {
+    let next = chars.get(i + 1).copied();
+    let (op, len) = match (chars.get(i)?, next) {
+        (':', _) => (Op::Colon, 1),
+        ('=', _) => (Op::Eq, 1),
+        ('~', _) => (Op::Tilde, 1),
+        ('!', Some('=')) => (Op::Ne, 2),
+        ('<', Some('=')) => (Op::Le, 2),
+        ('<', _) => (Op::Lt, 1),
+        ('>', Some('=')) => (Op::Ge, 2),
+        ('>', _) => (Op::Gt, 1),
+        _ => return None,
+    };
+    Some((op, i + len))
+}
+
+/// The value right after `name` and its operator, which ends at `i`.
+fn value(chars: &[char], i: &mut usize, field: Span, name: &str, op: Op) -> Result<Token, FilterError> {
+    let start = *i;
+    if chars.get(start) == Some(&'"') {
+        let (text, end) = quoted(chars, start)?;
+        *i = end;
+        return Ok(Token { kind: TokenKind::Quoted(text), span: Span { start, end } });
+    }
+    *i = word_end(chars, start);
+    if *i == start {
+        return Err(FilterError::new(format!("Missing value after '{}{}'", name, op.symbol()), field));
+    }
+    Ok(Token { kind: TokenKind::Word(chars[start..*i].iter().collect()), span: Span { start, end: *i } })
+}
+
+/// `value` written so it reads back as a single field value, e.g. in
+/// `tag:VALUE`: as is where possible, otherwise as a quoted string.
+pub fn quote(value: &str) -> String {
+    let plain = !value.is_empty()
+        && !value.starts_with('"')
+        && !value.chars().any(|c| c.is_whitespace() || c == '(' || c == ')');
+    if plain {
+        return value.to_string();
+    }
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn word_end(chars: &[char], start: usize) -> usize {
+    (start..chars.len())
+        .find(|&j| chars[j].is_whitespace() || chars[j] == '(' || chars[j] == ')')
+        .unwrap_or(chars.len())
+}
+
+/// The text of the string whose opening quote is at `start`, and the
+/// position after its closing quote.
+fn quoted(chars: &[char], start: usize) -> Result<(String, usize), FilterError> {
+    let mut text = String::new();
+    let mut i = start + 1;
+    while i < chars.len() {
+        match chars[i] {
+            '"' => return Ok((text, i + 1)),
+            '\\' if i + 1 < chars.len() => {
+                text.push(chars[i + 1]);
+                i += 2;
+            }
+            c => {
+                text.push(c);
+                i += 1;
+            }
+        }
+    }
+    Err(FilterError::new("Missing closing '\"'", Span { start, end: chars.len() }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(input: &str) -> Vec<TokenKind> {
+        tokenize(input).unwrap().into_iter().map(|t| t.kind).collect()
+    }
+
+    fn word(text: &str) -> TokenKind {
+        TokenKind::Word(text.to_string())
+    }
+
+    #[test]
+    fn splits_fields_words_and_parentheses() {
+        assert_eq!(
+            kinds("(Tag:work or -due<=7d) report"),
+            [
+                TokenKind::LParen,
+                TokenKind::Field("tag".to_string(), Op::Colon),
+                word("work"),
+                word("or"),
+                TokenKind::Minus,
+                TokenKind::Field("due".to_string(), Op::Le),
+                word("7d"),
+                TokenKind::RParen,
+                word("report"),
+            ]
+        );
+        // a dash on its own or inside a word doesn't negate
+        assert_eq!(kinds("- well-known"), [word("-"), word("well-known")]);
+        // no field name before the operator: a word
+        assert_eq!(kinds(":x 10:30"), [word(":x"), word("10:30")]);
+    }
+
+    #[test]
+    fn every_operator_is_recognised() {
+        for op in [Op::Colon, Op::Eq, Op::Ne, Op::Lt, Op::Le, Op::Gt, Op::Ge, Op::Tilde] {
+            let input = format!("id{}3", op.symbol());
+            assert_eq!(kinds(&input), [TokenKind::Field("id".to_string(), op), word("3")], "{}", input);
+        }
+    }
+
+    #[test]
+    fn quoted_strings_unescape() {
+        assert_eq!(
+            kinds(r#""say \"hi\" \\ now" title:"a (b)""#),
+            [
+                TokenKind::Quoted(r#"say "hi" \ now"#.to_string()),
+                TokenKind::Field("title".to_string(), Op::Colon),
+                TokenKind::Quoted("a (b)".to_string()),
+            ]
+        );
+        for value in ["plain", "two words", "(paren", r#"a "quote""#, r"back\slash", ""] {
+            let input = format!("title:{}", quote(value));
+            let tokens = kinds(&input);
+            let read = match &tokens[1] {
+                TokenKind::Word(text) | TokenKind::Quoted(text) => text.clone(),
+                other => panic!("{:?}", other),
+            };
+            assert_eq!((tokens.len(), read.as_str()), (2, value), "{}", input);
+        }
+    }
+
+    #[test]
+    fn spans_count_characters() {
+        let tokens = tokenize("élan tag:x").unwrap();
+        assert_eq!(tokens[0].span, Span { start: 0, end: 4 });
+        assert_eq!(tokens[1].span, Span { start: 5, end: 9 });
+        assert_eq!(tokens[2].span, Span { start: 9, end: 10 });
+    }
+
+    #[test]
+    fn errors_point_at_the_problem() {
+        let error = tokenize(r#"a "open \" end"#).unwrap_err();
+        assert_eq!(error.span, Span { start: 2, end: 14 });
+        assert_eq!(error.to_string(), "Missing closing '\"' (at column 3)");
+
+        let error = tokenize("x due< y").unwrap_err();
+        assert_eq!(error.message, "Missing value after 'due<'");
+        assert_eq!(error.span, Span { start: 2, end: 6 });
+        assert_eq!(error.pointer("x due< y"), "x due< y\n  ^^^^");
+    }
+}
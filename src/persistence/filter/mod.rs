@@ -0,0 +1,12 @@
+//! Filter expressions such as `status:open tag:work due<7d title~"deploy"`,
+//! used to pick tasks in the task list, `todo list` and the APIs.
+//!
+//! Input is split into tokens (`lexer`), parsed into an `Expr` (`parser`,
+//! `ast`) and compiled to a parameterised SQL condition (`sql`). Errors
+//! carry the position of the offending token.
+
+pub mod ast;
+pub mod error;
+pub mod lexer;
+pub mod parser;
+pub mod sql;
@@ -0,0 +1,419 @@
+//! Grammar, loosest binding first:
+//!
+//! ```text
+//! or      = and ("or" and)*
+//! and     = unary (["and"] unary)*
+//! unary   = ("not" | "-") unary | primary
+//! primary = "(" or ")" | field op value | word | "quoted string"
+//! ```
+
+use chrono::{Duration, Local, NaiveDate, NaiveDateTime};
+
+use super::ast::{Cmp, DateField, Expr, Moment, Test, TextMatch};
+use super::error::FilterError;
+use super::lexer::{tokenize, Op, Span, Token, TokenKind};
+use crate::persistence::persistence::Priority;
+use crate::persistence::search::Search;
+
+/// Field names accepted before an operator, for error messages.
+const FIELDS: &str = "status, tag, project, priority, due, created, updated, completed, title, description, id, parent or is";
+
+/// Parses `input`, returning `None` when it has no terms at all.
+pub fn parse(input: &str) -> Result<Option<Expr>, FilterError> {
+    let tokens = tokenize(input)?;
+    if tokens.is_empty() {
+        return Ok(None);
+    }
+    let mut parser = Parser { tokens, pos: 0, now: Local::now().naive_local() };
+    let expr = parser.or()?;
+    match parser.peek() {
+        None => Ok(Some(expr)),
+        Some(token) => Err(FilterError::new("Unexpected ')'", token.span)),
+    }
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+    /// What relative dates such as `today` and `7d` count from
+    now: NaiveDateTime,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    /// Whether the next token is the keyword `keyword`, in any case.
+    fn at_keyword(&self, keyword: &str) -> bool {
+        matches!(self.peek(), Some(Token { kind: TokenKind::Word(w), .. }) if w.eq_ignore_ascii_case(keyword))
+    }
+
+    /// Span just past the last token, for errors about missing input.
+    fn end(&self) -> Span {
+        let end = self.tokens.last().map(|t| t.span.end).unwrap_or(0);
+        Span { start: end, end: end + 1 }
+    }
+
+    fn or(&mut self) -> Result<Expr, FilterError> {
+        let mut exprs = vec![self.and()?];
+        while self.at_keyword("or") {
+            self.next();
+            exprs.push(self.and()?);
+        }
+        Ok(if exprs.len() == 1 { exprs.remove(0) } else { Expr::Or(exprs) })
+    }
+
+    fn and(&mut self) -> Result<Expr, FilterError> {
+        let mut exprs = vec![self.unary()?];
+        loop {
+            match self.peek() {
+                None | Some(Token { kind: TokenKind::RParen, .. }) => break,
+                _ if self.at_keyword("or") => break,
+                _ if self.at_keyword("and") => {
+                    self.next();
+                }
+                _ => {}
+            }
+            exprs.push(self.unary()?);
+        }
+        Ok(if exprs.len() == 1 { exprs.remove(0) } else { Expr::And(exprs) })
+    }
+
+    fn unary(&mut self) -> Result<Expr, FilterError> {
+        if matches!(self.peek(), Some(Token { kind: TokenKind::Minus, .. })) || self.at_keyword("not") {
+            self.next();
+            return Ok(Expr::Not(Box::new(self.unary()?)));
+        }
+        self.primary()
+    }
+
+    fn primary(&mut self) -> Result<Expr, FilterError> {
+        let Some(token) = self.next() else {
+            return Err(FilterError::new("Expected a term", self.end()));
+        };
+        match token.kind {
+            TokenKind::LParen => {
+                let expr = self.or()?;
+                match self.next() {
+                    Some(Token { kind: TokenKind::RParen, .. }) => Ok(expr),
+                    _ => Err(FilterError::new("Missing ')' to close this '('", token.span)),
+                }
+            }
+            TokenKind::RParen => Err(FilterError::new("Unexpected ')'", token.span)),
+            TokenKind::Word(word) if ["and", "or"].iter().any(|k| word.eq_ignore_ascii_case(k)) => {
+                Err(FilterError::new(format!("Expected a term before '{}'", word), token.span))
+            }
+            TokenKind::Word(word) => Ok(Expr::Test(Test::Words(text(&word, token.span)?))),
+            TokenKind::Quoted(phrase) => Ok(Expr::Test(Test::Phrase(text(&phrase, token.span)?))),
+            TokenKind::Minus => Err(FilterError::new("Expected a term after '-'", token.span)),
+            TokenKind::Field(name, op) => {
+                // the lexer always follows a field with its value
+                let value = self.next().expect("field without a value");
+                let text = match value.kind {
+                    TokenKind::Word(text) | TokenKind::Quoted(text) => text,
+                    _ => unreachable!("field value is a word or a string"),
+                };
+                let test = self.test(&name, op, token.span, &text, value.span)?;
+                // `!=` is the negation of `=`
+                Ok(if op == Op::Ne { Expr::Not(Box::new(test)) } else { test })
+            }
+        }
+    }
+
+    /// The condition `name op value`. `field` and `at` are the spans of
+    /// `name op` and of the value.
+    fn test(&self, name: &str, op: Op, field: Span, value: &str, at: Span) -> Result<Expr, FilterError> {
+        let lower = value.to_lowercase();
+        let test = match name {
+            "status" => {
+                equality(name, op, field)?;
+                Test::Completed(status(&lower).ok_or_else(|| {
+                    FilterError::new(format!("Unknown status '{}' (use open or completed)", value), at)
+                })?)
+            }
+            "tag" | "tags" => {
+                equality(name, op, field)?;
+                if lower == "none" { Test::Untagged } else { Test::Tag(value.to_string()) }
+            }
+            "project" => Test::Project(match op {
+                Op::Colon | Op::Eq | Op::Ne => TextMatch::Equals(value.to_string()),
+                Op::Tilde => TextMatch::Contains(value.to_string()),
+                _ => return Err(unsupported(name, op, field, "use project:NAME or project~TEXT")),
+            }),
+            "title" | "description" | "desc" => {
+                let text = match op {
+                    Op::Colon | Op::Tilde => TextMatch::Contains(value.to_string()),
+                    Op::Eq | Op::Ne => TextMatch::Equals(value.to_string()),
+                    _ => return Err(unsupported(name, op, field, "use ~ to search for text")),
+                };
+                if name == "title" { Test::Title(text) } else { Test::Description(text) }
+            }
+            "priority" | "prio" => Test::Priority(
+                compare(name, op, field)?,
+                value.parse::<Priority>().map_err(|e| FilterError::new(e, at))?,
+            ),
+            "due" | "created" | "updated" | "completed" => {
+                let date_field = match name {
+                    "due" => DateField::Due,
+                    "created" => DateField::Created,
+                    "updated" => DateField::Updated,
+                    _ => DateField::Completed,
+                };
+                if lower == "none" {
+                    equality(name, op, field)?;
+                    Test::NoDate(date_field)
+                } else {
+                    let moment = moment(&lower, self.now).ok_or_else(|| {
+                        FilterError::new(
+                            format!(
+                                "Invalid date '{}' (use YYYY-MM-DD, today, tomorrow, yesterday, now, \
+                                 an offset such as 7d, -2w or 3h, or none)",
+                                value
+                            ),
+                            at,
+                        )
+                    })?;
+                    Test::Date(date_field, compare(name, op, field)?, moment)
+                }
+            }
+            "id" => Test::Id(compare(name, op, field)?, number(value, at)?),
+            "parent" => {
+                equality(name, op, field)?;
+                if lower == "none" { Test::NoParent } else { Test::Parent(number(value, at)?) }
+            }
+            "is" => {
+                equality(name, op, field)?;
+                match lower.as_str() {
+                    "overdue" => Test::Overdue,
+                    "recurring" => Test::Recurring,
+                    "subtask" => Test::Subtask,
+                    other => match status(other) {
+                        Some(completed) => Test::Completed(completed),
+                        None => {
+                            return Err(FilterError::new(
+                                format!("Unknown state '{}' (use open, completed, overdue, recurring or subtask)", value),
+                                at,
+                            ));
+                        }
+                    },
+                }
+            }
+            _ => {
+                let span = Span { start: field.start, end: field.start + name.chars().count() };
+                return Err(FilterError::new(format!("Unknown field '{}' (use {})", name, FIELDS), span));
+            }
+        };
+        Ok(Expr::Test(test))
+    }
+}
+
+fn status(value: &str) -> Option<bool> {
+    match value {
+        "open" | "pending" | "todo" => Some(false),
+        "completed" | "complete" | "done" => Some(true),
+        _ => None,
+    }
+}
+
+/// Checks `op` is one of `:`, `=` and `!=`.
+fn equality(name: &str, op: Op, field: Span) -> Result<(), FilterError> {
+    match op {
+        Op::Colon | Op::Eq | Op::Ne => Ok(()),
+        _ => Err(unsupported(name, op, field, &format!("use {}:VALUE or {}!=VALUE", name, name))),
+    }
+}
+
+/// The comparison `op` stands for; `!=` is parsed as `=` and negated.
+fn compare(name: &str, op: Op, field: Span) -> Result<Cmp, FilterError> {
+    Ok(match op {
+        Op::Colon | Op::Eq | Op::Ne => Cmp::Eq,
+        Op::Lt => Cmp::Lt,
+        Op::Le => Cmp::Le,
+        Op::Gt => Cmp::Gt,
+        Op::Ge => Cmp::Ge,
+        Op::Tilde => return Err(unsupported(name, op, field, "use :, <, <=, > or >=")),
+    })
+}
+
+fn unsupported(name: &str, op: Op, field: Span, hint: &str) -> FilterError {
+    FilterError::new(format!("'{}' can't be used with {} ({})", op.symbol(), name, hint), field)
+}
+
+fn number(value: &str, at: Span) -> Result<i64, FilterError> {
+    value.parse().map_err(|_| FilterError::new(format!("'{}' is not a task id", value), at))
+}
+
+fn text(text: &str, at: Span) -> Result<Search, FilterError> {
+    let search = Search::new(text);
+    if search.is_empty() {
+        return Err(FilterError::new(format!("Nothing to search for in '{}'", text), at));
+    }
+    Ok(search)
+}
+
+/// A date value, relative to `now` where it isn't absolute.
+fn moment(value: &str, now: NaiveDateTime) -> Option<Moment> {
+    match value {
+        "now" => return Some(Moment::instant(now)),
+        "today" => return Some(Moment::day(now)),
+        "tomorrow" => return Some(Moment::day(now + Duration::days(1))),
+        "yesterday" => return Some(Moment::day(now - Duration::days(1))),
+        _ => {}
+    }
+    if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        return Some(Moment::day(date.and_time(now.time())));
+    }
+    if let Ok(at) = NaiveDateTime::parse_from_str(value, "%Y-%m-%dt%H:%M") {
+        return Some(Moment::instant(at));
+    }
+
+    // offsets: 3h, 7d, -2w
+    let unit = value.chars().last()?;
+    let count: i64 = value[..value.len() - unit.len_utf8()].trim_start_matches('+').parse().ok()?;
+    let offset = match unit {
+        'h' => Duration::try_hours(count)?,
+        'd' | 'w' => Duration::try_days(if unit == 'w' { count.checked_mul(7)? } else { count })?,
+        _ => return None,
+    };
+    let at = now.checked_add_signed(offset)?;
+    Some(if unit == 'h' { Moment::instant(at) } else { Moment::day(at) })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn words(text: &str) -> Expr {
+        Expr::Test(Test::Words(Search::new(text)))
+    }
+
+    fn parsed(input: &str) -> Expr {
+        parse(input).unwrap().unwrap()
+    }
+
+    fn error(input: &str) -> FilterError {
+        parse(input).unwrap_err()
+    }
+
+    fn now() -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2026, 3, 10).unwrap().and_hms_opt(15, 45, 0).unwrap()
+    }
+
+    fn at(date: &str, time: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(&format!("{} {}", date, time), "%Y-%m-%d %H:%M").unwrap()
+    }
+
+    #[test]
+    fn empty_input_has_no_filter() {
+        assert_eq!(parse("  "), Ok(None));
+    }
+
+    #[test]
+    fn and_binds_tighter_than_or() {
+        assert_eq!(
+            parsed("a b or c"),
+            Expr::Or(vec![Expr::And(vec![words("a"), words("b")]), words("c")])
+        );
+        assert_eq!(
+            parsed("a OR b and c"),
+            Expr::Or(vec![words("a"), Expr::And(vec![words("b"), words("c")])])
+        );
+        assert_eq!(parsed("a and b c"), Expr::And(vec![words("a"), words("b"), words("c")]));
+    }
+
+    #[test]
+    fn not_binds_tightest() {
+        assert_eq!(
+            parsed("not a b"),
+            Expr::And(vec![Expr::Not(Box::new(words("a"))), words("b")])
+        );
+        assert_eq!(parsed("--a"), Expr::Not(Box::new(Expr::Not(Box::new(words("a"))))));
+        assert_eq!(
+            parsed("status!=open"),
+            Expr::Not(Box::new(Expr::Test(Test::Completed(false))))
+        );
+    }
+
+    #[test]
+    fn parentheses_group() {
+        assert_eq!(
+            parsed("(a or b) c"),
+            Expr::And(vec![Expr::Or(vec![words("a"), words("b")]), words("c")])
+        );
+        assert_eq!(
+            parsed("-(a (b or c))"),
+            Expr::Not(Box::new(Expr::And(vec![words("a"), Expr::Or(vec![words("b"), words("c")])])))
+        );
+    }
+
+    #[test]
+    fn fields_become_tests() {
+        let test = |input: &str| match parsed(input) {
+            Expr::Test(test) => test,
+            other => panic!("{:?}", other),
+        };
+        assert_eq!(test("tag:none"), Test::Untagged);
+        assert_eq!(test(r#"tag:"on hold""#), Test::Tag("on hold".to_string()));
+        assert_eq!(test("project~home"), Test::Project(TextMatch::Contains("home".to_string())));
+        assert_eq!(test("title=Report"), Test::Title(TextMatch::Equals("Report".to_string())));
+        assert_eq!(test("priority>=high"), Test::Priority(Cmp::Ge, Priority::High));
+        assert_eq!(test("due:none"), Test::NoDate(DateField::Due));
+        assert_eq!(test("id<12"), Test::Id(Cmp::Lt, 12));
+        assert_eq!(test("parent:3"), Test::Parent(3));
+        assert_eq!(test("is:done"), Test::Completed(true));
+        assert_eq!(test(r#""next week""#), Test::Phrase(Search::new("next week")));
+    }
+
+    #[test]
+    fn date_keywords_and_offsets() {
+        let day = |value| moment(value, now()).filter(|m| !m.is_instant()).map(|m| m.start);
+        let instant = |value| moment(value, now()).filter(Moment::is_instant).map(|m| m.start);
+
+        assert_eq!(day("today"), Some(at("2026-03-10", "00:00")));
+        assert_eq!(day("tomorrow"), Some(at("2026-03-11", "00:00")));
+        assert_eq!(day("yesterday"), Some(at("2026-03-09", "00:00")));
+        assert_eq!(day("7d"), Some(at("2026-03-17", "00:00")));
+        assert_eq!(day("+1w"), Some(at("2026-03-17", "00:00")));
+        assert_eq!(day("-2w"), Some(at("2026-02-24", "00:00")));
+        assert_eq!(day("2026-04-01"), Some(at("2026-04-01", "00:00")));
+        assert_eq!(instant("now"), Some(now()));
+        assert_eq!(instant("3h"), Some(at("2026-03-10", "18:45")));
+        assert_eq!(instant("2026-04-01t09:30"), Some(at("2026-04-01", "09:30")));
+        assert_eq!(moment("today", now()).map(|m| m.end), Some(at("2026-03-11", "00:00")));
+
+        for invalid in ["7x", "d", "2026-13-01", "soon", "99999999999999w", "9223372036854775807d"] {
+            assert_eq!(moment(invalid, now()), None, "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn errors_point_at_the_problem() {
+        let cases = [
+            ("status:opn", "Unknown status 'opn' (use open or completed)", 7, 10),
+            ("a colour:red", "Unknown field 'colour'", 2, 8),
+            ("due~3d", "'~' can't be used with due", 0, 4),
+            ("tag>x", "'>' can't be used with tag", 0, 4),
+            ("id:seven", "'seven' is not a task id", 3, 8),
+            ("due<someday", "Invalid date 'someday'", 4, 11),
+            ("(a or b", "Missing ')' to close this '('", 0, 1),
+            ("a b)", "Unexpected ')'", 3, 4),
+            ("or a", "Expected a term before 'or'", 0, 2),
+            ("a or", "Expected a term", 4, 5),
+            ("a -)", "Nothing to search for in '-'", 2, 3),
+            ("a ()", "Unexpected ')'", 3, 4),
+            ("\"!!\"", "Nothing to search for", 0, 4),
+        ];
+        for (input, message, start, end) in cases {
+            let e = error(input);
+            assert!(e.message.starts_with(message), "{}: {}", input, e.message);
+            assert_eq!((e.span.start, e.span.end), (start, end), "{}", input);
+        }
+    }
+}
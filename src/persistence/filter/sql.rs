@@ -0,0 +1,227 @@
+use chrono::{Local, NaiveDateTime};
+use rusqlite::types::Value;
+
+use super::ast::{Cmp, Expr, Moment, Test, TextMatch};
+
+/// Format of the timestamps stored in the `tasks` table, which compare
+/// correctly as text.
+const STORED_TIME: &str = "%Y-%m-%d %H:%M:%S";
+
+impl Expr {
+    /// A condition on the `tasks` table for `WHERE`. Values are appended to
+    /// `params` and referred to by number (`?N`), so the condition can be
+    /// combined with others that use `params` the same way.
+    ///
+    /// Every condition is true or false, never NULL, so `not` picks exactly
+    /// the tasks its operand doesn't (`not due<7d` includes undated tasks).
+    pub fn to_sql(&self, params: &mut Vec<Value>) -> String {
+        match self {
+            Expr::And(exprs) => join(exprs, " AND ", params),
+            Expr::Or(exprs) => join(exprs, " OR ", params),
+            Expr::Not(expr) => format!("NOT ({})", expr.to_sql(params)),
+            Expr::Test(test) => test.to_sql(params),
+        }
+    }
+}
+
+fn join(exprs: &[Expr], separator: &str, params: &mut Vec<Value>) -> String {
+    let parts: Vec<String> = exprs.iter().map(|e| e.to_sql(params)).collect();
+    format!("({})", parts.join(separator))
+}
+
+/// Adds `value` to `params`, returning its placeholder.
+fn bind(params: &mut Vec<Value>, value: Value) -> String {
+    params.push(value);
+    format!("?{}", params.len())
+}
+
+fn time(at: NaiveDateTime) -> Value {
+    Value::Text(at.format(STORED_TIME).to_string())
+}
+
+fn operator(cmp: Cmp) -> &'static str {
+    match cmp {
+        Cmp::Eq => "=",
+        Cmp::Lt => "<",
+        Cmp::Le => "<=",
+        Cmp::Gt => ">",
+        Cmp::Ge => ">=",
+    }
+}
+
+/// `column` matching `text`, ignoring case; NULL counts as empty.
+fn text_match(column: &str, text: &TextMatch, params: &mut Vec<Value>) -> String {
+    match text {
+        TextMatch::Equals(text) => format!("COALESCE({}, '') = {} COLLATE NOCASE", column, bind(params, Value::Text(text.clone()))),
+        TextMatch::Contains(text) => {
+            let pattern = format!("%{}%", text.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_"));
+            format!("COALESCE({}, '') LIKE {} ESCAPE '\\'", column, bind(params, Value::Text(pattern)))
+        }
+    }
+}
+
+impl Test {
+    fn to_sql(&self, params: &mut Vec<Value>) -> String {
+        match self {
+            Test::Completed(true) => "completed".to_string(),
+            Test::Completed(false) => "NOT completed".to_string(),
+            Test::Tag(name) => format!(
+                "EXISTS (SELECT 1 FROM task_tags tt JOIN tags g ON g.id = tt.tag_id
+                  WHERE tt.task_id = tasks.id AND g.name = {} COLLATE NOCASE)",
+                bind(params, Value::Text(name.clone()))
+            ),
+            Test::Untagged => "NOT EXISTS (SELECT 1 FROM task_tags tt WHERE tt.task_id = tasks.id)".to_string(),
+            Test::Project(text) => format!(
+                "EXISTS (SELECT 1 FROM projects p WHERE p.id = tasks.project_id AND {})",
+                text_match("p.name", text, params)
+            ),
+            Test::Priority(cmp, priority) => {
+                format!("priority {} {}", operator(*cmp), bind(params, Value::Integer(*priority as i64)))
+            }
+            Test::Date(field, cmp, moment) => {
+                let column = field.column();
+                let condition = match cmp {
+                    // equal to an instant means on its day
+                    Cmp::Eq => {
+                        let day = if moment.is_instant() { Moment::day(moment.start) } else { *moment };
+                        format!(
+                            "{} >= {} AND {} < {}",
+                            column,
+                            bind(params, time(day.start)),
+                            column,
+                            bind(params, time(day.end))
+                        )
+                    }
+                    Cmp::Lt => format!("{} < {}", column, bind(params, time(moment.start))),
+                    Cmp::Le if moment.is_instant() => format!("{} <= {}", column, bind(params, time(moment.start))),
+                    Cmp::Le => format!("{} < {}", column, bind(params, time(moment.end))),
+                    Cmp::Gt if moment.is_instant() => format!("{} > {}", column, bind(params, time(moment.start))),
+                    Cmp::Gt => format!("{} >= {}", column, bind(params, time(moment.end))),
+                    Cmp::Ge => format!("{} >= {}", column, bind(params, time(moment.start))),
+                };
+                format!("({} IS NOT NULL AND {})", column, condition)
+            }
+            Test::NoDate(field) => format!("{} IS NULL", field.column()),
+            Test::Title(text) => text_match("title", text, params),
+            Test::Description(text) => text_match("description", text, params),
+            Test::Id(cmp, id) => format!("id {} {}", operator(*cmp), bind(params, Value::Integer(*id))),
+            Test::Parent(id) => format!("COALESCE(parent_id = {}, 0)", bind(params, Value::Integer(*id))),
+            Test::NoParent => "parent_id IS NULL".to_string(),
+            Test::Overdue => format!(
+                "(NOT completed AND due IS NOT NULL AND due < {})",
+                bind(params, time(Local::now().naive_local()))
+            ),
+            Test::Recurring => "recurrence IS NOT NULL".to_string(),
+            Test::Subtask => "parent_id IS NOT NULL".to_string(),
+            Test::Words(search) => format!(
+                "id IN (SELECT rowid FROM tasks_fts WHERE tasks_fts MATCH {})",
+                bind(params, Value::Text(search.match_expression()))
+            ),
+            Test::Phrase(search) => format!(
+                "id IN (SELECT rowid FROM tasks_fts WHERE tasks_fts MATCH {})",
+                bind(params, Value::Text(search.phrase_expression()))
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::*;
+    use crate::persistence::filter::ast::{DateField, TextMatch};
+    use crate::persistence::filter::parser::parse;
+    use crate::persistence::persistence::{Persistence, Priority, Task};
+    use crate::persistence::query::TaskQuery;
+
+    fn sql(expr: &Expr) -> (String, Vec<Value>) {
+        let mut params = Vec::new();
+        (expr.to_sql(&mut params), params)
+    }
+
+    fn text(value: &str) -> Value {
+        Value::Text(value.to_string())
+    }
+
+    fn march(day: u32, hour: u32, minute: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2026, 3, day).unwrap().and_hms_opt(hour, minute, 0).unwrap()
+    }
+
+    #[test]
+    fn groups_are_parenthesised_and_params_numbered_in_order() {
+        let expr = Expr::And(vec![
+            Expr::Test(Test::Id(Cmp::Gt, 3)),
+            Expr::Or(vec![
+                Expr::Test(Test::Priority(Cmp::Ge, Priority::High)),
+                Expr::Not(Box::new(Expr::Test(Test::Completed(false)))),
+            ]),
+        ]);
+        assert_eq!(
+            sql(&expr),
+            (
+                "(id > ?1 AND (priority >= ?2 OR NOT (NOT completed)))".to_string(),
+                vec![Value::Integer(3), Value::Integer(Priority::High as i64)],
+            )
+        );
+    }
+
+    #[test]
+    fn days_and_instants_compare_differently() {
+        let day = Moment::day(march(10, 15, 45));
+        let instant = Moment::instant(march(10, 15, 45));
+        let due = |cmp, moment| sql(&Expr::Test(Test::Date(DateField::Due, cmp, moment)));
+
+        assert_eq!(
+            due(Cmp::Eq, instant),
+            (
+                "(due IS NOT NULL AND due >= ?1 AND due < ?2)".to_string(),
+                vec![text("2026-03-10 00:00:00"), text("2026-03-11 00:00:00")],
+            )
+        );
+        assert_eq!(due(Cmp::Le, day), ("(due IS NOT NULL AND due < ?1)".to_string(), vec![text("2026-03-11 00:00:00")]));
+        assert_eq!(due(Cmp::Le, instant), ("(due IS NOT NULL AND due <= ?1)".to_string(), vec![text("2026-03-10 15:45:00")]));
+        assert_eq!(due(Cmp::Gt, day), ("(due IS NOT NULL AND due >= ?1)".to_string(), vec![text("2026-03-11 00:00:00")]));
+        assert_eq!(due(Cmp::Lt, day), ("(due IS NOT NULL AND due < ?1)".to_string(), vec![text("2026-03-10 00:00:00")]));
+    }
+
+    #[test]
+    fn like_patterns_escape_wildcards() {
+        let (condition, params) = sql(&Expr::Test(Test::Title(TextMatch::Contains(r"50%_off\".to_string()))));
+        assert_eq!(condition, r"COALESCE(title, '') LIKE ?1 ESCAPE '\'");
+        assert_eq!(params, [text(r"%50\%\_off\\%")]);
+    }
+
+    #[test]
+    fn filters_select_the_right_tasks() {
+        let p = Persistence::in_memory();
+        let add = |task: Task| p.save(&task).unwrap().unwrap();
+        let report = add(Task {
+            title: "Write report".to_string(),
+            priority: Priority::High,
+            tags: vec!["work".to_string()],
+            due: Some(march(1, 23, 59)),
+            ..Task::default()
+        });
+        let figures = add(Task { title: "Check 50% figures".to_string(), parent_id: Some(report), ..Task::default() });
+        let shopping = add(Task { title: "Buy milk".to_string(), completed: true, ..Task::default() });
+
+        let ids = |filter: &str| -> Vec<i64> {
+            let query = TaskQuery { filter: parse(filter).unwrap(), ..TaskQuery::default() };
+            let mut ids: Vec<i64> = p.tasks(&query).unwrap().into_iter().filter_map(|t| t.id).collect();
+            ids.sort();
+            ids
+        };
+        assert_eq!(ids("tag:WORK"), [report]);
+        assert_eq!(ids("not tag:work"), [figures, shopping]);
+        assert_eq!(ids("tag:none status:open"), [figures]);
+        assert_eq!(ids("due<2026-03-02"), [report]);
+        assert_eq!(ids("-due<2026-03-02"), [figures, shopping]);
+        assert_eq!(ids("is:overdue or is:done"), [report, shopping]);
+        assert_eq!(ids("title~50%"), [figures]);
+        assert_eq!(ids("title~5_%"), Vec::<i64>::new());
+        assert_eq!(ids(&format!("parent:{}", report)), [figures]);
+        assert_eq!(ids("priority>medium or (milk status:done)"), [report, shopping]);
+        assert_eq!(ids(r#""write report" project:inbox"#), [report]);
+    }
+}
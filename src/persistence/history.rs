@@ -0,0 +1,406 @@
+use rusqlite::types::{FromSql, FromSqlError, FromSqlResult, ToSqlOutput, ValueRef};
+use rusqlite::ToSql;
+
+use super::error::PersistenceError;
+use super::persistence::{Persistable, Persistence, Task, TASK_COLUMNS};
+use super::project::{Project, INBOX_ID};
+use super::tag::Tag;
+
+/// Number of operations kept; older ones can no longer be undone.
+const KEPT_OPERATIONS: i64 = 100;
+
+/// Tasks as they were at one point, parents before their subtasks. Stored
+/// in their JSON form.
+#[derive(Debug, Clone, Default)]
+pub struct Snapshot(pub Vec<Task>);
+
+impl ToSql for Snapshot {
+    fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
+        let json = serde_json::to_string(&self.0).map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?;
+        Ok(json.into())
+    }
+}
+
+impl FromSql for Snapshot {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        serde_json::from_str(value.as_str()?).map(Snapshot).map_err(|e| FromSqlError::Other(Box::new(e)))
+    }
+}
+
+/// A logged change to tasks, with their state before and after it.
+#[derive(Debug, Clone, Default)]
+pub struct Operation {
+    pub id: Option<i64>,
+    /// What was done, e.g. `delete 'Write report'`
+    pub label: String,
+    pub before: Snapshot,
+    pub after: Snapshot,
+    /// Undone and not redone since
+    pub undone: bool,
+}
+
+impl Persistable for Operation {
+    fn id(&self) -> Option<i64> {
+        self.id
+    }
+
+    fn insert_sql(&self) -> String {
+        "INSERT INTO operations (label, before, after, undone, id) VALUES (?1, ?2, ?3, ?4, ?5)".to_string()
+    }
+
+    fn params(&self) -> Vec<&dyn rusqlite::ToSql> {
+        vec![&self.label, &self.before, &self.after, &self.undone, &self.id]
+    }
+
+    fn update_sql() -> String {
+        "UPDATE operations SET label = ?1, before = ?2, after = ?3, undone = ?4 WHERE id = ?5".to_string()
+    }
+
+    fn update_params(&self) -> Vec<&dyn rusqlite::ToSql> {
+        vec![&self.label, &self.before, &self.after, &self.undone, &self.id]
+    }
+
+    fn get_all_sql() -> String {
+        "SELECT id, label, before, after, undone FROM operations ORDER BY id".to_string()
+    }
+
+    fn get_sql() -> String {
+        "SELECT id, label, before, after, undone FROM operations WHERE id = ?1".to_string()
+    }
+
+    fn delete_sql() -> String {
+        "DELETE FROM operations WHERE id = ?1".to_string()
+    }
+
+    fn from_row(row: &rusqlite::Row) -> rusqlite::Result<Self> {
+        Ok(Operation {
+            id: row.get(0)?,
+            label: row.get(1)?,
+            before: row.get(2)?,
+            after: row.get(3)?,
+            undone: row.get(4)?,
+        })
+    }
+}
+
+impl Persistence {
+    /// Runs `change` in one transaction and logs it as `label`, so it can be
+    /// undone. `change` may alter or delete the tasks `ids` and their
+    /// subtasks, and returns the ids of the tasks it creates. Logging a new
+    /// operation drops the ones undone before it, which can't be redone any
+    /// more.
+    ///
+    /// Every change to tasks goes through here, see `add_logged` and the
+    /// methods after it. Imports are the exception: they clear the log, see
+    /// `clear_history`.
+    pub fn logged(
+        &self,
+        label: &str,
+        ids: &[i64],
+        change: impl FnOnce(&Self) -> Result<Vec<i64>, PersistenceError>,
+    ) -> Result<Vec<i64>, PersistenceError> {
+        self.transaction(|p| {
+            let before = p.snapshot(ids)?;
+            let created = change(p)?;
+            // the tasks in `before` are looked up again, so ones whose
+            // occurrence_of was cleared by a delete are still in `after`
+            let before_ids = before.0.iter().filter_map(|t| t.id);
+            let touched: Vec<i64> = ids.iter().chain(&created).copied().chain(before_ids).collect();
+            let after = p.snapshot(&touched)?;
+
+            let unchanged = serde_json::to_value(&before.0).ok() == serde_json::to_value(&after.0).ok();
+            if !unchanged {
+                p.execute("DELETE FROM operations WHERE undone", &[])?;
+                p.save(&Operation { label: label.to_string(), before, after, ..Default::default() })?;
+                p.execute(
+                    "DELETE FROM operations WHERE id <= (SELECT MAX(id) FROM operations) - ?1",
+                    &[&KEPT_OPERATIONS],
+                )?;
+            }
+            Ok(created)
+        })
+    }
+
+    /// Reverts the latest operation that isn't undone, returning it; `None`
+    /// when there is nothing to undo.
+    pub fn undo(&self) -> Result<Option<Operation>, PersistenceError> {
+        self.transaction(|p| {
+            let sql = "SELECT id, label, before, after, undone FROM operations WHERE NOT undone ORDER BY id DESC LIMIT 1";
+            let Some(mut operation) = p.query::<Operation>(sql, &[])?.pop() else { return Ok(None) };
+            p.restore(&operation.after, &operation.before)?;
+            operation.undone = true;
+            p.update(&operation)?;
+            Ok(Some(operation))
+        })
+    }
+
+    /// Applies the earliest undone operation again, returning it; `None`
+    /// when there is nothing to redo.
+    pub fn redo(&self) -> Result<Option<Operation>, PersistenceError> {
+        self.transaction(|p| {
+            let sql = "SELECT id, label, before, after, undone FROM operations WHERE undone ORDER BY id LIMIT 1";
+            let Some(mut operation) = p.query::<Operation>(sql, &[])?.pop() else { return Ok(None) };
+            p.restore(&operation.before, &operation.after)?;
+            operation.undone = false;
+            p.update(&operation)?;
+            Ok(Some(operation))
+        })
+    }
+
+    /// Saves a new task, logged as `add '<title>'`; returns its id.
+    pub fn add_logged(&self, task: &Task) -> Result<Option<i64>, PersistenceError> {
+        let created = self.logged(&format!("add '{}'", task.title), &[], |p| Ok(p.save(task)?.into_iter().collect()))?;
+        Ok(created.first().copied())
+    }
+
+    /// Writes an existing task back, logged as `action` (e.g. `edit`).
+    pub fn update_logged(&self, task: &Task, action: &str) -> Result<(), PersistenceError> {
+        let Some(id) = task.id else { return Ok(()) };
+        self.logged(&format!("{} '{}'", action, task.title), &[id], |p| {
+            p.update(task)?;
+            Ok(Vec::new())
+        })?;
+        Ok(())
+    }
+
+    /// `set_completed`, logged, completing the task's open subtasks first
+    /// when `subtasks` is set. Returns the id of the next occurrence if one
+    /// was created.
+    pub fn set_completed_logged(&self, task: &Task, completed: bool, subtasks: bool) -> Result<Option<i64>, PersistenceError> {
+        let Some(id) = task.id else { return Ok(None) };
+        let label = format!("{} '{}'", if completed { "complete" } else { "reopen" }, task.title);
+        let created = self.logged(&label, &[id], |p| {
+            if subtasks {
+                p.execute(&Task::complete_subtasks_sql(), &[&id])?;
+            }
+            Ok(p.set_completed(task, completed)?.into_iter().collect())
+        })?;
+        Ok(created.first().copied())
+    }
+
+    /// Deletes a task with its subtasks, logged.
+    pub fn delete_logged(&self, task: &Task) -> Result<(), PersistenceError> {
+        let Some(id) = task.id else { return Ok(()) };
+        self.logged(&format!("delete '{}'", task.title), &[id], |p| {
+            p.delete::<Task>(id)?;
+            Ok(Vec::new())
+        })?;
+        Ok(())
+    }
+
+    /// Deletes a project and, through the foreign key, its tasks. Logged as
+    /// a change to those tasks: the project itself isn't kept, so undoing it
+    /// brings the tasks back into the Inbox.
+    pub fn delete_project_logged(&self, project: &Project) -> Result<(), PersistenceError> {
+        let Some(id) = project.id else { return Ok(()) };
+        let ids = self.task_ids("SELECT id FROM tasks WHERE project_id = ?1", id)?;
+        self.logged(&format!("delete project '{}'", project.name), &ids, |p| {
+            p.delete::<Project>(id)?;
+            Ok(Vec::new())
+        })?;
+        Ok(())
+    }
+
+    /// Deletes a tag, logged as a change to the tasks carrying it; undoing
+    /// it creates the tag again.
+    pub fn delete_tag_logged(&self, tag: &Tag) -> Result<(), PersistenceError> {
+        let Some(id) = tag.id else { return Ok(()) };
+        let ids = self.task_ids("SELECT task_id FROM task_tags WHERE tag_id = ?1", id)?;
+        self.logged(&format!("delete tag '{}'", tag.name), &ids, |p| {
+            p.delete::<Tag>(id)?;
+            Ok(Vec::new())
+        })?;
+        Ok(())
+    }
+
+    /// Forgets every logged operation. Imports call this: they write too
+    /// much to log, and the snapshots taken before them may no longer match
+    /// the tasks.
+    pub fn clear_history(&self) -> Result<(), PersistenceError> {
+        self.execute("DELETE FROM operations", &[])?;
+        Ok(())
+    }
+
+    fn task_ids(&self, sql: &str, id: i64) -> Result<Vec<i64>, PersistenceError> {
+        let Some(conn) = &self.connection else { return Ok(Vec::new()) };
+        let mut statement = conn.prepare(sql)?;
+        let ids = statement.query_map([id], |row| row.get(0))?.collect::<Result<_, _>>()?;
+        Ok(ids)
+    }
+
+    /// The tasks `ids`, all their subtasks and the later occurrences of any
+    /// of them, as stored now. Later occurrences are included because
+    /// deleting a task clears their occurrence_of.
+    fn snapshot(&self, ids: &[i64]) -> Result<Snapshot, PersistenceError> {
+        let ids = serde_json::to_string(ids).unwrap_or_default();
+        let sql = format!(
+            "WITH RECURSIVE sub(sub_id, depth) AS (
+                SELECT value, 0 FROM json_each(?1)
+                UNION
+                SELECT t.id, sub.depth + 1 FROM tasks t
+                JOIN sub ON t.parent_id = sub.sub_id OR t.occurrence_of = sub.sub_id
+            )
+            SELECT {} FROM tasks JOIN (SELECT sub_id, MIN(depth) AS depth FROM sub GROUP BY sub_id) ON sub_id = tasks.id
+            ORDER BY depth, tasks.id",
+            TASK_COLUMNS
+        );
+        Ok(Snapshot(self.query::<Task>(&sql, &[&ids])?))
+    }
+
+    /// Turns the tasks in `current` back into `target`: tasks only in
+    /// `current` are deleted, the others are written back exactly as they
+    /// were, timestamps included. Tasks whose project has been deleted since
+    /// go to the Inbox, and links to tasks that are gone are cleared.
+    fn restore(&self, current: &Snapshot, target: &Snapshot) -> Result<(), PersistenceError> {
+        let Some(conn) = &self.connection else { return Ok(()) };
+        // tasks may be written back before the ones they refer to
+        conn.pragma_update(None, "defer_foreign_keys", true)?;
+
+        for task in &current.0 {
+            if let Some(id) = task.id
+                && !target.0.iter().any(|t| t.id == Some(id))
+            {
+                self.delete::<Task>(id)?;
+            }
+        }
+        for task in &target.0 {
+            let Some(id) = task.id else { continue };
+            let mut task = task.clone();
+            if let Some(project_id) = task.project_id
+                && self.get::<Project>(project_id)?.is_none()
+            {
+                task.project_id = Some(INBOX_ID);
+            }
+            if self.get::<Task>(id)?.is_some() {
+                self.update(&task)?;
+            } else {
+                self.save(&task)?;
+            }
+            self.execute(
+                "UPDATE tasks SET occurrence_of = ?1, created_at = ?2, updated_at = ?3, completed_at = ?4 WHERE id = ?5",
+                &[&task.occurrence_of, &task.created_at, &task.updated_at, &task.completed_at, &id],
+            )?;
+        }
+        let ids = serde_json::to_string(&target.0.iter().filter_map(|t| t.id).collect::<Vec<_>>()).unwrap_or_default();
+        for column in ["parent_id", "occurrence_of"] {
+            self.execute(
+                &format!(
+                    "UPDATE tasks SET {0} = NULL
+                     WHERE id IN (SELECT value FROM json_each(?1)) AND {0} NOT IN (SELECT id FROM tasks)",
+                    column
+                ),
+                &[&ids],
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::persistence::recurrence::Recurrence;
+
+    fn add(p: &Persistence, task: Task) -> Task {
+        let id = p.add_logged(&task).unwrap();
+        p.get::<Task>(id.unwrap()).unwrap().unwrap()
+    }
+
+    fn task(title: &str) -> Task {
+        Task { title: title.to_string(), ..Task::default() }
+    }
+
+    fn titles(p: &Persistence) -> Vec<String> {
+        p.get_all::<Task>().unwrap().into_iter().map(|t| t.title).collect()
+    }
+
+    #[test]
+    fn edits_undo_and_redo() {
+        let p = Persistence::in_memory();
+        let original = add(&p, task("Draft"));
+        p.update_logged(&Task { title: "Final".to_string(), ..original.clone() }, "edit").unwrap();
+
+        assert_eq!(p.undo().unwrap().unwrap().label, "edit 'Final'");
+        let restored = p.get::<Task>(original.id.unwrap()).unwrap().unwrap();
+        assert_eq!(restored.title, "Draft");
+        assert_eq!(restored.updated_at, original.updated_at);
+
+        p.redo().unwrap();
+        assert_eq!(titles(&p), ["Final"]);
+        p.undo().unwrap();
+        p.undo().unwrap();
+        assert!(titles(&p).is_empty());
+        assert!(p.undo().unwrap().is_none());
+        p.redo().unwrap();
+        assert_eq!(titles(&p), ["Draft"]);
+    }
+
+    #[test]
+    fn undoing_a_delete_restores_subtasks_and_later_occurrences() {
+        let p = Persistence::in_memory();
+        let repeating = add(&p, Task { recurrence: Some(Recurrence::Daily), ..task("Water plants") });
+        let sub = add(&p, Task { parent_id: repeating.id, ..task("Fill can") });
+        let next = p.set_completed_logged(&repeating, true, false).unwrap();
+
+        p.delete_logged(&repeating).unwrap();
+        assert!(p.get::<Task>(sub.id.unwrap()).unwrap().is_none());
+        assert_eq!(p.get::<Task>(next.unwrap()).unwrap().unwrap().occurrence_of, None);
+
+        p.undo().unwrap();
+        assert_eq!(p.get::<Task>(sub.id.unwrap()).unwrap().unwrap().parent_id, repeating.id);
+        assert_eq!(p.get::<Task>(next.unwrap()).unwrap().unwrap().occurrence_of, repeating.id);
+
+        p.redo().unwrap();
+        assert!(p.get::<Task>(repeating.id.unwrap()).unwrap().is_none());
+        assert_eq!(p.get::<Task>(next.unwrap()).unwrap().unwrap().occurrence_of, None);
+    }
+
+    #[test]
+    fn undoing_a_completion_removes_the_next_occurrence() {
+        let p = Persistence::in_memory();
+        let repeating = add(&p, Task { recurrence: Some(Recurrence::Daily), ..task("Stretch") });
+        let next = p.set_completed_logged(&repeating, true, false).unwrap().unwrap();
+
+        p.undo().unwrap();
+        assert!(p.get::<Task>(next).unwrap().is_none());
+        assert!(!p.get::<Task>(repeating.id.unwrap()).unwrap().unwrap().completed);
+        p.redo().unwrap();
+        assert!(p.get::<Task>(next).unwrap().is_some());
+    }
+
+    #[test]
+    fn undoing_a_project_delete_brings_its_tasks_to_the_inbox() {
+        let p = Persistence::in_memory();
+        let project_id = p.save(&Project { name: "Garden".to_string(), ..Project::default() }).unwrap();
+        let project = p.get::<Project>(project_id.unwrap()).unwrap().unwrap();
+        let weed = add(&p, Task { project_id, ..task("Weed") });
+
+        p.delete_project_logged(&project).unwrap();
+        assert!(titles(&p).is_empty());
+        p.undo().unwrap();
+        assert_eq!(p.get::<Task>(weed.id.unwrap()).unwrap().unwrap().project_id, Some(INBOX_ID));
+    }
+
+    #[test]
+    fn undoing_a_tag_delete_tags_the_tasks_again() {
+        let p = Persistence::in_memory();
+        let tagged = add(&p, Task { tags: vec!["home".to_string()], ..task("Vacuum") });
+        let tag = p.get_all::<Tag>().unwrap().pop().unwrap();
+
+        p.delete_tag_logged(&tag).unwrap();
+        assert!(p.get::<Task>(tagged.id.unwrap()).unwrap().unwrap().tags.is_empty());
+        p.undo().unwrap();
+        assert_eq!(p.get::<Task>(tagged.id.unwrap()).unwrap().unwrap().tags, ["home"]);
+    }
+
+    #[test]
+    fn new_changes_drop_the_redo_history() {
+        let p = Persistence::in_memory();
+        add(&p, task("One"));
+        p.undo().unwrap();
+        add(&p, task("Two"));
+        assert!(p.redo().unwrap().is_none());
+        p.clear_history().unwrap();
+        assert!(p.undo().unwrap().is_none());
+    }
+}
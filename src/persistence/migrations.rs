@@ -0,0 +1,209 @@
+use rusqlite::{Connection, Transaction};
+
+use super::error::PersistenceError;
+
+/// Ordered list of schema migrations. The database's `PRAGMA user_version`
+/// records how many of these have been applied, so entries must never be
+/// edited or reordered once released — only appended.
+const MIGRATIONS: &[&str] = &[
+    // 1: initial tasks table
+    "CREATE TABLE IF NOT EXISTS tasks (
+        id INTEGER PRIMARY KEY,
+        title TEXT NOT NULL,
+        description TEXT,
+        completed BOOLEAN NOT NULL
+    )",
+    // 2: optional due date/time
+    "ALTER TABLE tasks ADD COLUMN due TEXT",
+    // 3: priority level, 0 (none) to 4 (urgent)
+    "ALTER TABLE tasks ADD COLUMN priority INTEGER NOT NULL DEFAULT 0",
+    // 4: projects, with existing tasks moved into a default "Inbox"
+    "CREATE TABLE projects (
+        id INTEGER PRIMARY KEY,
+        name TEXT NOT NULL UNIQUE
+    );
+    INSERT INTO projects (id, name) VALUES (1, 'Inbox');
+    ALTER TABLE tasks ADD COLUMN project_id INTEGER REFERENCES projects(id) ON DELETE CASCADE;
+    UPDATE tasks SET project_id = 1;",
+    // 5: free-form tags, many-to-many with tasks
+    "CREATE TABLE tags (
+        id INTEGER PRIMARY KEY,
+        name TEXT NOT NULL UNIQUE COLLATE NOCASE
+    );
+    CREATE TABLE task_tags (
+        task_id INTEGER NOT NULL REFERENCES tasks(id) ON DELETE CASCADE,
+        tag_id INTEGER NOT NULL REFERENCES tags(id) ON DELETE CASCADE,
+        PRIMARY KEY (task_id, tag_id)
+    );",
+    // 6: subtasks, removed together with their parent
+    "ALTER TABLE tasks ADD COLUMN parent_id INTEGER REFERENCES tasks(id) ON DELETE CASCADE",
+    // 7: repeat rules, and the chain of occurrences generated from them
+    "ALTER TABLE tasks ADD COLUMN recurrence TEXT;
+    ALTER TABLE tasks ADD COLUMN occurrence_of INTEGER REFERENCES tasks(id) ON DELETE SET NULL;",
+    // 8: timestamps; existing tasks get the time of the upgrade
+    "ALTER TABLE tasks ADD COLUMN created_at TEXT;
+    ALTER TABLE tasks ADD COLUMN updated_at TEXT;
+    ALTER TABLE tasks ADD COLUMN completed_at TEXT;
+    UPDATE tasks SET created_at = datetime('now', 'localtime'), updated_at = datetime('now', 'localtime');
+    UPDATE tasks SET completed_at = datetime('now', 'localtime') WHERE completed;",
+    // 9: key:value extensions from imported todo.txt files, kept for export
+    "ALTER TABLE tasks ADD COLUMN extensions TEXT",
+    // 10: ids from other tools, so re-importing doesn't duplicate tasks, and
+    // notes attached to tasks
+    "ALTER TABLE tasks ADD COLUMN uid TEXT;
+    CREATE UNIQUE INDEX tasks_uid ON tasks (uid) WHERE uid IS NOT NULL;
+    CREATE TABLE annotations (
+        id INTEGER PRIMARY KEY,
+        task_id INTEGER NOT NULL REFERENCES tasks(id) ON DELETE CASCADE,
+        entry TEXT,
+        description TEXT NOT NULL
+    );",
+    // 11: full-text index over titles and descriptions, kept in step with
+    // the tasks table by triggers
+    "CREATE VIRTUAL TABLE tasks_fts USING fts5(
+        title, description, content = 'tasks', content_rowid = 'id',
+        tokenize = 'unicode61 remove_diacritics 2'
+    );
+    INSERT INTO tasks_fts (tasks_fts) VALUES ('rebuild');
+    CREATE TRIGGER tasks_fts_insert AFTER INSERT ON tasks BEGIN
+        INSERT INTO tasks_fts (rowid, title, description) VALUES (new.id, new.title, new.description);
+    END;
+    CREATE TRIGGER tasks_fts_delete AFTER DELETE ON tasks BEGIN
+        INSERT INTO tasks_fts (tasks_fts, rowid, title, description)
+        VALUES ('delete', old.id, old.title, old.description);
+    END;
+    CREATE TRIGGER tasks_fts_update AFTER UPDATE OF title, description ON tasks BEGIN
+        INSERT INTO tasks_fts (tasks_fts, rowid, title, description)
+        VALUES ('delete', old.id, old.title, old.description);
+        INSERT INTO tasks_fts (rowid, title, description) VALUES (new.id, new.title, new.description);
+    END;",
+    // 12: saved views (filter, status and sort) listed on the main menu,
+    // starting with two examples
+    "CREATE TABLE views (
+        id INTEGER PRIMARY KEY,
+        name TEXT NOT NULL UNIQUE COLLATE NOCASE,
+        filter TEXT NOT NULL DEFAULT '',
+        status TEXT NOT NULL DEFAULT 'open',
+        sort TEXT NOT NULL DEFAULT 'priority'
+    );
+    INSERT INTO views (name, filter, status, sort) VALUES
+        ('Today', 'due<=today', 'open', 'due date'),
+        ('Waiting on others', 'tag:waiting', 'open', 'created');",
+    // 13: log of task changes made in the UI, with the tasks' state before
+    // and after as JSON, for undo and redo
+    "CREATE TABLE operations (
+        id INTEGER PRIMARY KEY,
+        label TEXT NOT NULL,
+        before TEXT NOT NULL,
+        after TEXT NOT NULL,
+        undone BOOLEAN NOT NULL DEFAULT 0,
+        at TEXT NOT NULL DEFAULT (datetime('now', 'localtime'))
+    );",
+];
+
+/// Schema version this build knows how to handle.
+pub fn latest_version() -> u32 {
+    MIGRATIONS.len() as u32
+}
+
+pub fn current_version(conn: &Connection) -> rusqlite::Result<u32> {
+    conn.pragma_query_value(None, "user_version", |row| row.get(0))
+}
+
+/// Brings the database up to `latest_version()`, applying every pending
+/// migration inside a single transaction.
+pub fn migrate(conn: &Connection) -> Result<(), PersistenceError> {
+    let current = current_version(conn)?;
+    let latest = latest_version();
+
+    if current > latest {
+        return Err(PersistenceError::SchemaMismatch { found: current, supported: latest });
+    }
+    if current == latest {
+        return Ok(());
+    }
+
+    let tx = conn.unchecked_transaction()?;
+    for (i, sql) in MIGRATIONS.iter().enumerate().skip(current as usize) {
+        apply(&tx, sql, i as u32 + 1)?;
+    }
+    tx.commit()?;
+    Ok(())
+}
+
+fn apply(tx: &Transaction, sql: &str, version: u32) -> rusqlite::Result<()> {
+    tx.execute_batch(sql)?;
+    tx.pragma_update(None, "user_version", version)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::persistence::persistence::{Persistence, Task};
+
+    /// A database as the first release left it: the tasks table, created
+    /// without a `user_version`.
+    fn baseline() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS tasks (
+                id INTEGER PRIMARY KEY,
+                title TEXT NOT NULL,
+                description TEXT,
+                completed BOOLEAN NOT NULL
+            );
+            INSERT INTO tasks (title, description, completed) VALUES
+                ('Call the bank', 'about the card', 0),
+                ('Pay rent', NULL, 1);",
+        )
+        .unwrap();
+        conn
+    }
+
+    #[test]
+    fn baseline_database_migrates_with_its_tasks() {
+        let conn = baseline();
+        assert_eq!(current_version(&conn).unwrap(), 0);
+        migrate(&conn).unwrap();
+        assert_eq!(current_version(&conn).unwrap(), latest_version());
+
+        let p = Persistence { connection: Some(conn) };
+        let tasks = p.get_all::<Task>().unwrap();
+        assert_eq!(tasks.len(), 2);
+        let task = |title: &str| tasks.iter().find(|t| t.title == title).expect(title);
+        let (bank, rent) = (task("Call the bank"), task("Pay rent"));
+        assert_eq!((bank.description.as_deref(), bank.completed), (Some("about the card"), false));
+        assert_eq!((rent.description.as_deref(), rent.completed), (None, true));
+        assert_eq!(bank.project_id, Some(1));
+        assert!(bank.created_at.is_some() && rent.completed_at.is_some() && bank.completed_at.is_none());
+        // existing rows are in the full-text index
+        let conn = p.connection.as_ref().unwrap();
+        let found: i64 =
+            conn.query_row("SELECT rowid FROM tasks_fts WHERE tasks_fts MATCH 'bank'", [], |row| row.get(0)).unwrap();
+        assert_eq!(Some(found), bank.id);
+    }
+
+    #[test]
+    fn every_version_migrates_to_the_latest() {
+        for version in 0..=latest_version() {
+            let conn = baseline();
+            let tx = conn.unchecked_transaction().unwrap();
+            for (i, sql) in MIGRATIONS.iter().enumerate().take(version as usize) {
+                apply(&tx, sql, i as u32 + 1).unwrap();
+            }
+            tx.commit().unwrap();
+
+            migrate(&conn).unwrap();
+            assert_eq!(current_version(&conn).unwrap(), latest_version());
+            let count: i64 = conn.query_row("SELECT COUNT(*) FROM tasks", [], |row| row.get(0)).unwrap();
+            assert_eq!(count, 2, "from version {}", version);
+        }
+    }
+
+    #[test]
+    fn newer_databases_are_refused() {
+        let conn = baseline();
+        conn.pragma_update(None, "user_version", latest_version() + 1).unwrap();
+        assert!(matches!(migrate(&conn), Err(PersistenceError::SchemaMismatch { .. })));
+    }
+}
@@ -1 +1,14 @@
-pub mod persistence;
\ No newline at end of file
+pub mod annotation;
+pub mod error;
+pub mod extensions;
+pub mod filter;
+pub mod history;
+pub mod migrations;
+#[allow(clippy::module_inception)]
+pub mod persistence;
+pub mod project;
+pub mod query;
+pub mod recurrence;
+pub mod search;
+pub mod tag;
+pub mod view;
\ No newline at end of file
@@ -1,99 +1,227 @@
-use std::{fs::File, path::PathBuf, env};
+use std::{fs::File, path::{Path, PathBuf}, env};
+use chrono::{Duration, Local, NaiveDate, NaiveDateTime, NaiveTime, Timelike};
 use rusqlite::{ Connection };
+use serde::{Deserialize, Serialize};
+
+use super::error::PersistenceError;
+use super::annotation::Annotation;
+use super::extensions::Extensions;
+use super::migrations;
+use super::query::TaskQuery;
+use super::recurrence::Recurrence;
+
 pub struct Persistence {
     pub connection: Option<Connection>,
 }
 
 impl Persistence {
 
-    pub fn new() -> Self {
-        let db_path = Self::get_database_path();
-        Self::create_database();
+    pub fn new() -> Result<Self, PersistenceError> {
+        let db_path = Self::get_database_path()?;
+        Self::create_database(&db_path)?;
 
-        Persistence { connection: Some(Connection::open(&db_path).unwrap()) }
+        let connection = Connection::open(&db_path)?;
+        connection.pragma_update(None, "foreign_keys", true)?;
+
+        Ok(Persistence { connection: Some(connection) })
     }
 
+    /// A fresh, fully migrated database in memory.
+    #[cfg(test)]
+    pub fn in_memory() -> Self {
+        let connection = Connection::open_in_memory().expect("in-memory database");
+        connection.pragma_update(None, "foreign_keys", true).expect("foreign keys");
+        let persistence = Persistence { connection: Some(connection) };
+        persistence.sync_schema().expect("migrations");
+        persistence
+    }
 
-    pub fn sync_schema(&self) {
+    /// Applies any pending schema migrations. Safe to call on every start-up.
+    pub fn sync_schema(&self) -> Result<(), PersistenceError> {
         if let Some(conn) = &self.connection {
-            conn.execute(
-                "CREATE TABLE IF NOT EXISTS tasks (
-                    id INTEGER PRIMARY KEY,
-                    title TEXT NOT NULL,
-                    description TEXT,
-                    completed BOOLEAN NOT NULL
-                )",
-                [],
-            ).expect("Failed to create tasks table");
+            migrations::migrate(conn)?;
         }
+        Ok(())
     }
 
-    fn get_database_path() -> PathBuf {
+    fn get_database_path() -> Result<PathBuf, PersistenceError> {
         let data_dir = if cfg!(target_os = "windows") {
-            let appdata = env::var("APPDATA").expect("Failed to get APPDATA");
+            let appdata = env::var("APPDATA").map_err(|_| PersistenceError::MissingEnv("APPDATA"))?;
             PathBuf::from(appdata).join("todo")
         } else {
-            let home = env::var("HOME").expect("Failed to get HOME");
+            let home = env::var("HOME").map_err(|_| PersistenceError::MissingEnv("HOME"))?;
             let xdg_data = env::var("XDG_DATA_HOME")
                 .unwrap_or_else(|_| format!("{}/.local/share", home));
             PathBuf::from(xdg_data).join("todo")
         };
         
-        std::fs::create_dir_all(&data_dir).expect("Failed to create data directory");
+        std::fs::create_dir_all(&data_dir)?;
         
-        data_dir.join("tasks.db")
-    }
-
-    fn create_database() {
-        let db_path = Self::get_database_path();
-        let file: Result<File, std::io::Error> = File::open(&db_path);
-        match file {
-            Ok(_) => (),
-            Err(_) => {
-                match File::create(&db_path) {
-                    Ok(_) => (),
-                    Err(e) => eprintln!("Failed to create database: {}", e),
-                }
+        Ok(data_dir.join("tasks.db"))
+    }
+
+    fn create_database(db_path: &Path) -> Result<(), PersistenceError> {
+        if File::open(db_path).is_err() {
+            File::create(db_path)?;
+        }
+        Ok(())
+    }
+
+    /// Runs `f` inside one transaction, committing only if it succeeds.
+    /// `save` and `update` calls made by `f` join it instead of opening
+    /// their own.
+    pub fn transaction<R, E: From<PersistenceError>>(&self, f: impl FnOnce(&Self) -> Result<R, E>) -> Result<R, E> {
+        let Some(conn) = &self.connection else { return f(self) };
+        let tx = conn.unchecked_transaction().map_err(PersistenceError::from)?;
+        let result = f(self)?;
+        tx.commit().map_err(PersistenceError::from)?;
+        Ok(result)
+    }
+
+    /// Like `transaction`, but always rolls back; used to preview changes.
+    pub fn dry_run<R, E: From<PersistenceError>>(&self, f: impl FnOnce(&Self) -> Result<R, E>) -> Result<R, E> {
+        let Some(conn) = &self.connection else { return f(self) };
+        let _tx = conn.unchecked_transaction().map_err(PersistenceError::from)?;
+        f(self)
+    }
+
+    /// Runs `f` in its own transaction unless one is already open.
+    fn write<R>(conn: &Connection, f: impl FnOnce() -> rusqlite::Result<R>) -> Result<R, PersistenceError> {
+        if !conn.is_autocommit() {
+            return Ok(f()?);
+        }
+        let tx = conn.unchecked_transaction()?;
+        let result = f()?;
+        tx.commit()?;
+        Ok(result)
+    }
+
+    /// Inserts `item`, returning its row id (`None` when no database is open).
+    /// An item whose id is already set keeps it.
+    pub fn save<T: Persistable>(&self, item: &T) -> Result<Option<i64>, PersistenceError> {
+        if let Some(conn) = &self.connection {
+            let id = Self::write(conn, || {
+                conn.execute(item.insert_sql().as_str(), item.params().as_slice())?;
+                let id = conn.last_insert_rowid();
+                item.save_relations(conn, id)?;
+                Ok(id)
+            })?;
+            return Ok(Some(id));
+        }
+        Ok(None)
+    }
+
+    pub fn get_all<T: Persistable>(&self) -> Result<Vec<T>, PersistenceError> {
+        let mut items = Vec::new();
+        if let Some(conn) = &self.connection {
+            let mut stmt = conn.prepare(T::get_all_sql().as_str())?;
+            let rows = stmt.query_map([], |row| T::from_row(row))?;
+
+            for item in rows {
+                items.push(item?);
             }
         }
+        Ok(items)
     }
 
-    pub fn save<T: Persistable>(&self, item: &T) {
+    pub fn get<T: Persistable>(&self, id: i64) -> Result<Option<T>, PersistenceError> {
+        Ok(self.query::<T>(T::get_sql().as_str(), &[&id])?.into_iter().next())
+    }
+
+    /// Runs a statement that returns no rows, returning the number of rows changed.
+    pub fn execute(&self, sql: &str, params: &[&dyn rusqlite::ToSql]) -> Result<usize, PersistenceError> {
         if let Some(conn) = &self.connection {
-            conn.execute(
-                item.insert_sql().as_str(),
-                item.params().as_slice()
-            ).expect("Failed to insert item");
-        }   
+            return Ok(conn.execute(sql, params)?);
+        }
+        Ok(0)
     }
 
-    pub fn get_all<T: Persistable>(&self) -> Vec<T> {
+    /// Runs an arbitrary `SELECT` whose columns match `T::from_row`.
+    pub fn query<T: Persistable>(&self, sql: &str, params: &[&dyn rusqlite::ToSql]) -> Result<Vec<T>, PersistenceError> {
         let mut items = Vec::new();
         if let Some(conn) = &self.connection {
-            let mut stmt = conn.prepare(T::get_all_sql().as_str()).expect("Failed to prepare statement");
-            let rows = stmt.query_map([], |row| T::from_row(row)).expect("Failed to query items");
+            let mut stmt = conn.prepare(sql)?;
+            let rows = stmt.query_map(params, |row| T::from_row(row))?;
 
             for item in rows {
-                items.push(item.expect("Failed to map item"));
+                items.push(item?);
             }
         }
-        items
+        Ok(items)
+    }
+
+    /// Tasks picked and ordered by `query`.
+    pub fn tasks(&self, query: &TaskQuery) -> Result<Vec<Task>, PersistenceError> {
+        let (sql, params) = query.build();
+        let params: Vec<&dyn rusqlite::ToSql> = params.iter().map(|p| p as &dyn rusqlite::ToSql).collect();
+        self.query::<Task>(&sql, &params)
     }
 
-    pub fn update<T: Persistable>(&self, item: &T) {
+    /// Number of tasks `query` picks.
+    pub fn count(&self, query: &TaskQuery) -> Result<i64, PersistenceError> {
+        let Some(conn) = &self.connection else { return Ok(0) };
+        let (sql, params) = query.build();
+        let sql = format!("SELECT COUNT(*) FROM ({})", sql);
+        Ok(conn.query_row(&sql, rusqlite::params_from_iter(params), |row| row.get(0))?)
+    }
+
+    pub fn update<T: Persistable>(&self, item: &T) -> Result<(), PersistenceError> {
         if let Some(conn) = &self.connection {
-            conn.execute(
-                T::update_sql().as_str(),
-                item.update_params().as_slice()
-            ).expect("Failed to update item");
+            Self::write(conn, || {
+                conn.execute(T::update_sql().as_str(), item.update_params().as_slice())?;
+                if let Some(id) = item.id() {
+                    item.save_relations(conn, id)?;
+                }
+                Ok(())
+            })?;
         }
+        Ok(())
     }
 
-    pub fn delete<T: Persistable>(&self, id: i64) {
+    pub fn delete<T: Persistable>(&self, id: i64) -> Result<(), PersistenceError> {
         if let Some(conn) = &self.connection {
-            conn.execute(T::delete_sql().as_str(), [id])
-                .expect("Failed to delete item");
+            conn.execute(T::delete_sql().as_str(), [id])?;
         }
+        Ok(())
+    }
+
+    /// Checks that the task `task_id` can become a subtask of `parent_id`:
+    /// the parent must not be the task itself or one of its subtasks. A new
+    /// task (`None`) can have any parent.
+    pub fn check_parent(&self, task_id: Option<i64>, parent_id: i64) -> Result<(), PersistenceError> {
+        let (Some(task), Some(conn)) = (task_id, &self.connection) else { return Ok(()) };
+        let cycle: bool = conn.query_row(
+            "WITH RECURSIVE up(id) AS (
+                SELECT ?1
+                UNION
+                SELECT t.parent_id FROM tasks t JOIN up ON t.id = up.id WHERE t.parent_id IS NOT NULL
+            )
+            SELECT EXISTS (SELECT 1 FROM up WHERE id = ?2)",
+            [parent_id, task],
+            |row| row.get(0),
+        )?;
+        if cycle {
+            return Err(PersistenceError::InvalidParent { task, parent: parent_id });
+        }
+        Ok(())
+    }
+
+    /// Marks `task` complete or open. Completing a recurring task also
+    /// creates its next occurrence (once — re-completing an occurrence that
+    /// already has a successor doesn't create another), returning its id.
+    pub fn set_completed(&self, task: &Task, completed: bool) -> Result<Option<i64>, PersistenceError> {
+        // computed first so a rule that runs off the calendar changes nothing
+        let next = if completed { task.next_occurrence(Local::now().naive_local())? } else { None };
+        self.update(&Task { completed, ..task.clone() })?;
+        let Some(next) = next else { return Ok(None) };
+        let existing = self.query::<Task>(
+            &format!("SELECT {} FROM tasks WHERE occurrence_of = ?1", TASK_COLUMNS),
+            &[&task.id],
+        )?;
+        if !existing.is_empty() {
+            return Ok(None);
+        }
+        self.save(&next)
     }
 
 }
@@ -101,42 +229,279 @@ impl Persistence {
 
 
 pub trait Persistable: Sized {
+    fn id(&self) -> Option<i64>;
     fn insert_sql(&self) -> String;
     fn params(&self) -> Vec<&dyn rusqlite::ToSql>;
     fn update_sql() -> String;
     fn update_params(&self) -> Vec<&dyn rusqlite::ToSql>;
     fn get_all_sql() -> String;
+    /// Selects the single item whose id is bound to `?1`.
+    fn get_sql() -> String;
     fn delete_sql() -> String;
     fn from_row(row: &rusqlite::Row) -> rusqlite::Result<Self>;
+
+    /// Writes rows in other tables that belong to the item stored under `id`,
+    /// e.g. many-to-many links. Runs in the same transaction as the insert
+    /// or update.
+    fn save_relations(&self, _conn: &Connection, _id: i64) -> rusqlite::Result<()> {
+        Ok(())
+    }
 }
 
-#[derive(Debug)]
+/// A task. Its JSON form (`todo list --format json`) is documented in the
+/// README and must stay backwards compatible.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Task {
     pub id: Option<i64>,
     pub title: String,
     pub description: Option<String>,
     pub completed: bool,
+    pub due: Option<NaiveDateTime>,
+    pub priority: Priority,
+    pub project_id: Option<i64>,
+    pub tags: Vec<String>,
+    /// Task this one is a subtask of
+    pub parent_id: Option<i64>,
+    pub recurrence: Option<Recurrence>,
+    /// Previous occurrence of a recurring task, which this one was generated from
+    pub occurrence_of: Option<i64>,
+    /// Set by the database on insert unless provided
+    pub created_at: Option<NaiveDateTime>,
+    /// Set by the database on every insert and update
+    pub updated_at: Option<NaiveDateTime>,
+    /// Set by the database when the task is completed
+    pub completed_at: Option<NaiveDateTime>,
+    /// `key:value` pairs from imported todo.txt files, kept for export
+    pub extensions: Extensions,
+    /// Identifier in the tool the task was imported from, e.g. a Taskwarrior
+    /// UUID; unique
+    pub uid: Option<String>,
+    /// Notes added over time, oldest first
+    pub annotations: Vec<Annotation>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Priority {
+    #[default]
+    None,
+    Low,
+    Medium,
+    High,
+    Urgent,
+}
+
+impl Priority {
+    pub const ALL: [Priority; 5] = [
+        Priority::None,
+        Priority::Low,
+        Priority::Medium,
+        Priority::High,
+        Priority::Urgent,
+    ];
+
+    pub fn raised(self) -> Self {
+        Self::ALL.get(self as usize + 1).copied().unwrap_or(self)
+    }
+
+    pub fn lowered(self) -> Self {
+        (self as usize).checked_sub(1).map(|i| Self::ALL[i]).unwrap_or(self)
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Priority::None => "none",
+            Priority::Low => "low",
+            Priority::Medium => "medium",
+            Priority::High => "high",
+            Priority::Urgent => "urgent",
+        }
+    }
+}
+
+impl std::str::FromStr for Priority {
+    type Err = String;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let input = input.trim().to_lowercase();
+        Self::ALL
+            .into_iter()
+            .find(|p| p.label() == input)
+            .ok_or_else(|| format!("Invalid priority '{}' (use none, low, medium, high or urgent)", input))
+    }
+}
+
+impl rusqlite::ToSql for Priority {
+    fn to_sql(&self) -> rusqlite::Result<rusqlite::types::ToSqlOutput<'_>> {
+        Ok((*self as i64).into())
+    }
+}
+
+impl rusqlite::types::FromSql for Priority {
+    fn column_result(value: rusqlite::types::ValueRef<'_>) -> rusqlite::types::FromSqlResult<Self> {
+        let n = value.as_i64()?;
+        Self::ALL
+            .get(n as usize)
+            .copied()
+            .ok_or(rusqlite::types::FromSqlError::OutOfRange(n))
+    }
+}
+
+/// Where a task's due date falls relative to now.
+#[derive(Debug, PartialEq)]
+pub enum DueStatus {
+    Overdue,
+    Today,
+    Upcoming,
+}
+
+/// Columns read by `Task::from_row`, in order.
+pub(super) const TASK_COLUMNS: &str = "id, title, description, completed, due, priority, project_id, parent_id,
+    recurrence, occurrence_of, created_at, updated_at, completed_at, extensions, uid,
+    (SELECT json_group_array(json_object('entry', strftime('%Y-%m-%dT%H:%M:%S', entry),
+        'description', description) ORDER BY entry, id) FROM annotations WHERE task_id = tasks.id),
+    (SELECT group_concat(g.name, ',' ORDER BY g.name) FROM task_tags tt
+     JOIN tags g ON g.id = tt.tag_id WHERE tt.task_id = tasks.id)";
+
+/// Ids of every descendant of the task bound to `?1`.
+/// `UNION` rather than `UNION ALL`, so a cycle in `parent_id` can't make it
+/// run forever.
+const SUBTASK_IDS: &str = "WITH RECURSIVE sub(id) AS (
+        SELECT id FROM tasks WHERE parent_id = ?1
+        UNION
+        SELECT t.id FROM tasks t JOIN sub ON t.parent_id = sub.id
+    )";
+
+/// Most pressing first: priority, then soonest due (undated last), then newest.
+const TASK_ORDER: &str = "priority DESC, due IS NULL, due ASC, id DESC";
+
+/// Time assigned to due dates entered without one, so "due 2026-03-01"
+/// means by the end of that day.
+const END_OF_DAY: (u32, u32) = (23, 59);
+
+impl Task {
+    /// Marks every descendant of the task bound to `?1` complete.
+    pub fn complete_subtasks_sql() -> String {
+        format!(
+            "{} UPDATE tasks SET completed = 1, updated_at = datetime('now', 'localtime'),
+             completed_at = COALESCE(completed_at, datetime('now', 'localtime'))
+             WHERE id IN sub",
+            SUBTASK_IDS
+        )
+    }
+
+    /// Parses user input such as `2026-03-01`, `2026-03-01 14:30`, `today`
+    /// or `tomorrow` into a due date/time.
+    pub fn parse_due(input: &str) -> Result<NaiveDateTime, String> {
+        let input = input.trim();
+        let end_of_day = |date: NaiveDate| date.and_time(Self::end_of_day());
+        let today = Local::now().date_naive();
+
+        match input.to_lowercase().as_str() {
+            "today" => return Ok(end_of_day(today)),
+            "tomorrow" => return Ok(end_of_day(today + Duration::days(1))),
+            _ => {}
+        }
+        if let Ok(dt) = NaiveDateTime::parse_from_str(input, "%Y-%m-%d %H:%M") {
+            return Ok(dt);
+        }
+        NaiveDate::parse_from_str(input, "%Y-%m-%d")
+            .map(end_of_day)
+            .map_err(|_| format!("Invalid due date '{}' (use YYYY-MM-DD [HH:MM])", input))
+    }
+
+    /// Time given to due dates entered without one.
+    pub fn end_of_day() -> NaiveTime {
+        NaiveTime::from_hms_opt(END_OF_DAY.0, END_OF_DAY.1, 0).unwrap()
+    }
+
+    /// The next occurrence of a recurring task completed at `now`: a fresh
+    /// open copy with the due date advanced by its repeat rule. `None` for
+    /// tasks that don't repeat.
+    pub fn next_occurrence(&self, now: NaiveDateTime) -> Result<Option<Task>, PersistenceError> {
+        let Some(rule) = &self.recurrence else { return Ok(None) };
+        Ok(Some(Task {
+            id: None,
+            completed: false,
+            due: Some(rule.next_due(self.due, now, Self::end_of_day())?),
+            occurrence_of: self.id,
+            created_at: None,
+            updated_at: None,
+            completed_at: None,
+            ..self.clone()
+        }))
+    }
+
+    /// Formats a due date the way `parse_due` accepts it, dropping the time
+    /// when it is the implicit end of day.
+    pub fn format_due(due: &NaiveDateTime) -> String {
+        if (due.hour(), due.minute()) == END_OF_DAY {
+            due.format("%Y-%m-%d").to_string()
+        } else {
+            due.format("%Y-%m-%d %H:%M").to_string()
+        }
+    }
+
+    /// Due status of an open task; completed tasks and tasks without a due
+    /// date have none.
+    pub fn due_status(&self, now: NaiveDateTime) -> Option<DueStatus> {
+        let due = self.due.filter(|_| !self.completed)?;
+        Some(if due < now {
+            DueStatus::Overdue
+        } else if due.date() == now.date() {
+            DueStatus::Today
+        } else {
+            DueStatus::Upcoming
+        })
+    }
 }
 
 impl Persistable for Task {
+    fn id(&self) -> Option<i64> {
+        self.id
+    }
+
     fn insert_sql(&self) -> String {
-        "INSERT INTO tasks (title, description, completed) VALUES (?1, ?2, ?3)".to_string()
+        // Tasks saved without a project land in the Inbox (project 1)
+        // Timestamps are filled in unless given (e.g. when importing)
+        "INSERT INTO tasks (title, description, completed, due, priority, project_id, parent_id,
+            recurrence, occurrence_of, created_at, updated_at, completed_at, extensions, uid, id)
+         VALUES (?1, ?2, ?3, ?4, ?5, COALESCE(?6, 1), ?7, ?8, ?9,
+            COALESCE(?10, datetime('now', 'localtime')),
+            COALESCE(?11, datetime('now', 'localtime')),
+            CASE WHEN ?3 THEN COALESCE(?12, datetime('now', 'localtime')) END, ?13, ?14, ?15)".to_string()
     }
 
     fn params(&self) -> Vec<&dyn rusqlite::ToSql> {
-        vec![&self.title, &self.description, &self.completed]
+        vec![
+            &self.title, &self.description, &self.completed, &self.due, &self.priority,
+            &self.project_id, &self.parent_id, &self.recurrence, &self.occurrence_of,
+            &self.created_at, &self.updated_at, &self.completed_at, &self.extensions, &self.uid, &self.id,
+        ]
     }
 
     fn update_sql() -> String {
-        "UPDATE tasks SET title = ?1, description = ?2, completed = ?3 WHERE id = ?4".to_string()
+        "UPDATE tasks SET title = ?1, description = ?2, completed = ?3, due = ?4, priority = ?5,
+         project_id = COALESCE(?6, project_id), parent_id = ?7, recurrence = ?8, extensions = ?9, uid = ?10,
+         updated_at = datetime('now', 'localtime'),
+         completed_at = CASE WHEN ?3 THEN COALESCE(completed_at, datetime('now', 'localtime')) END
+         WHERE id = ?11".to_string()
     }
 
     fn update_params(&self) -> Vec<&dyn rusqlite::ToSql> {
-        vec![&self.title, &self.description, &self.completed, &self.id]
+        vec![
+            &self.title, &self.description, &self.completed, &self.due, &self.priority,
+            &self.project_id, &self.parent_id, &self.recurrence, &self.extensions, &self.uid, &self.id,
+        ]
     }
 
     fn get_all_sql() -> String {
-        "SELECT id, title, description, completed FROM tasks ORDER BY id DESC".to_string()
+        format!("SELECT {} FROM tasks ORDER BY {}", TASK_COLUMNS, TASK_ORDER)
+    }
+
+    fn get_sql() -> String {
+        format!("SELECT {} FROM tasks WHERE id = ?1", TASK_COLUMNS)
     }
 
     fn delete_sql() -> String {
@@ -149,6 +514,94 @@ impl Persistable for Task {
             title: row.get(1)?,
             description: row.get(2)?,
             completed: row.get(3)?,
+            due: row.get(4)?,
+            priority: row.get(5)?,
+            project_id: row.get(6)?,
+            parent_id: row.get(7)?,
+            recurrence: row.get(8)?,
+            occurrence_of: row.get(9)?,
+            created_at: row.get(10)?,
+            updated_at: row.get(11)?,
+            completed_at: row.get(12)?,
+            extensions: row.get(13)?,
+            uid: row.get(14)?,
+            annotations: serde_json::from_str(&row.get::<_, String>(15)?).map_err(|e| {
+                rusqlite::Error::FromSqlConversionFailure(15, rusqlite::types::Type::Text, Box::new(e))
+            })?,
+            tags: row
+                .get::<_, Option<String>>(16)?
+                .map(|tags| tags.split(',').map(str::to_string).collect())
+                .unwrap_or_default(),
         })
     }
-}
\ No newline at end of file
+
+    fn save_relations(&self, conn: &Connection, id: i64) -> rusqlite::Result<()> {
+        // Subtasks always live in their parent's project
+        conn.execute(
+            &format!(
+                "{} UPDATE tasks SET project_id = (SELECT project_id FROM tasks WHERE id = ?1) WHERE id IN sub",
+                SUBTASK_IDS
+            ),
+            [id],
+        )?;
+
+        conn.execute("DELETE FROM annotations WHERE task_id = ?1", [id])?;
+        for annotation in &self.annotations {
+            conn.execute(
+                "INSERT INTO annotations (task_id, entry, description) VALUES (?1, ?2, ?3)",
+                rusqlite::params![id, annotation.entry, annotation.description],
+            )?;
+        }
+
+        conn.execute("DELETE FROM task_tags WHERE task_id = ?1", [id])?;
+        for tag in &self.tags {
+            conn.execute("INSERT OR IGNORE INTO tags (name) VALUES (?1)", [tag])?;
+            conn.execute(
+                "INSERT OR IGNORE INTO task_tags (task_id, tag_id)
+                 SELECT ?1, id FROM tags WHERE name = ?2",
+                rusqlite::params![id, tag],
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn add(persistence: &Persistence, title: &str, parent_id: Option<i64>) -> i64 {
+        let task = Task { title: title.to_string(), parent_id, ..Default::default() };
+        persistence.save(&task).unwrap().unwrap()
+    }
+
+    #[test]
+    fn check_parent_rejects_self_and_descendants() {
+        let p = Persistence::in_memory();
+        let parent = add(&p, "Parent", None);
+        let child = add(&p, "Child", Some(parent));
+        let grandchild = add(&p, "Grandchild", Some(child));
+        let other = add(&p, "Other", None);
+
+        assert!(p.check_parent(Some(parent), parent).is_err());
+        assert!(p.check_parent(Some(parent), child).is_err());
+        assert!(p.check_parent(Some(parent), grandchild).is_err());
+        assert!(p.check_parent(Some(grandchild), parent).is_ok());
+        assert!(p.check_parent(Some(parent), other).is_ok());
+        assert!(p.check_parent(None, parent).is_ok());
+    }
+
+    #[test]
+    fn subtask_queries_end_on_a_cycle() {
+        let p = Persistence::in_memory();
+        let a = add(&p, "A", None);
+        let b = add(&p, "B", Some(a));
+        // written directly, as an older build could have
+        p.execute("UPDATE tasks SET parent_id = ?1 WHERE id = ?2", &[&b, &a]).unwrap();
+
+        assert_eq!(p.execute(&Task::complete_subtasks_sql(), &[&a]).unwrap(), 2);
+        let task = p.get::<Task>(a).unwrap().unwrap();
+        p.update(&task).unwrap();
+        assert!(p.check_parent(Some(a), b).is_err());
+    }
+}
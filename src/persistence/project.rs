@@ -0,0 +1,69 @@
+use serde::{Deserialize, Serialize};
+
+use super::persistence::Persistable;
+
+/// Id of the built-in project every task starts in. It is created by the
+/// migrations and cannot be deleted.
+pub const INBOX_ID: i64 = 1;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Project {
+    pub id: Option<i64>,
+    pub name: String,
+    /// Number of incomplete tasks in the project; read-only, computed on load
+    #[serde(skip_deserializing)]
+    pub open_tasks: i64,
+}
+
+impl Project {
+    pub fn is_inbox(&self) -> bool {
+        self.id == Some(INBOX_ID)
+    }
+}
+
+impl Persistable for Project {
+    fn id(&self) -> Option<i64> {
+        self.id
+    }
+
+    fn insert_sql(&self) -> String {
+        "INSERT INTO projects (name, id) VALUES (?1, ?2)".to_string()
+    }
+
+    fn params(&self) -> Vec<&dyn rusqlite::ToSql> {
+        vec![&self.name, &self.id]
+    }
+
+    fn update_sql() -> String {
+        "UPDATE projects SET name = ?1 WHERE id = ?2".to_string()
+    }
+
+    fn update_params(&self) -> Vec<&dyn rusqlite::ToSql> {
+        vec![&self.name, &self.id]
+    }
+
+    fn get_all_sql() -> String {
+        "SELECT p.id, p.name,
+            (SELECT COUNT(*) FROM tasks t WHERE t.project_id = p.id AND NOT t.completed)
+         FROM projects p ORDER BY p.id".to_string()
+    }
+
+    fn get_sql() -> String {
+        "SELECT p.id, p.name,
+            (SELECT COUNT(*) FROM tasks t WHERE t.project_id = p.id AND NOT t.completed)
+         FROM projects p WHERE p.id = ?1".to_string()
+    }
+
+    fn delete_sql() -> String {
+        "DELETE FROM projects WHERE id = ?1".to_string()
+    }
+
+    fn from_row(row: &rusqlite::Row) -> rusqlite::Result<Self> {
+        Ok(Project {
+            id: row.get(0)?,
+            name: row.get(1)?,
+            open_tasks: row.get(2)?,
+        })
+    }
+}
@@ -0,0 +1,178 @@
+use rusqlite::types::Value;
+
+use super::filter::ast::Expr;
+use super::persistence::TASK_COLUMNS;
+
+/// Which tasks to list by completion.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum Status {
+    #[default]
+    All,
+    Open,
+    Completed,
+}
+
+impl Status {
+    pub const ALL: [Status; 3] = [Status::All, Status::Open, Status::Completed];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Status::All => "all",
+            Status::Open => "open",
+            Status::Completed => "completed",
+        }
+    }
+
+    /// The next filter in `ALL`, wrapping around.
+    pub fn next(self) -> Self {
+        Self::ALL[(self as usize + 1) % Self::ALL.len()]
+    }
+}
+
+/// Stored by label, e.g. in saved views.
+impl rusqlite::ToSql for Status {
+    fn to_sql(&self) -> rusqlite::Result<rusqlite::types::ToSqlOutput<'_>> {
+        Ok(self.label().into())
+    }
+}
+
+impl rusqlite::types::FromSql for Status {
+    fn column_result(value: rusqlite::types::ValueRef<'_>) -> rusqlite::types::FromSqlResult<Self> {
+        let label = value.as_str()?;
+        Self::ALL.into_iter().find(|s| s.label() == label).ok_or(rusqlite::types::FromSqlError::InvalidType)
+    }
+}
+
+/// Order of the listed tasks.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum Sort {
+    /// Most pressing first: priority, then soonest due, then newest
+    #[default]
+    Priority,
+    /// Soonest due first, undated last
+    Due,
+    /// Newest first
+    Created,
+    /// Alphabetical, ignoring case
+    Title,
+    /// Open tasks first, then the most recently completed
+    Completion,
+    /// Best match for the words of the filter first; like `Priority` when
+    /// it has none
+    Relevance,
+}
+
+impl Sort {
+    /// Orders offered in the task list, in the order they are cycled through.
+    pub const CYCLE: [Sort; 5] = [Sort::Priority, Sort::Due, Sort::Created, Sort::Title, Sort::Completion];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Sort::Priority => "priority",
+            Sort::Due => "due date",
+            Sort::Created => "created",
+            Sort::Title => "title",
+            Sort::Completion => "completion",
+            Sort::Relevance => "relevance",
+        }
+    }
+
+    /// The next order in `CYCLE`, wrapping around.
+    pub fn next(self) -> Self {
+        let i = Self::CYCLE.iter().position(|s| *s == self).map(|i| i + 1).unwrap_or(0);
+        Self::CYCLE[i % Self::CYCLE.len()]
+    }
+
+    fn order_by(self, ranked: bool) -> &'static str {
+        match self {
+            Sort::Relevance if ranked => "score IS NULL, score, priority DESC, id DESC",
+            Sort::Priority | Sort::Relevance => "priority DESC, due IS NULL, due ASC, id DESC",
+            Sort::Due => "due IS NULL, due ASC, priority DESC, id DESC",
+            Sort::Created => "created_at DESC, id DESC",
+            Sort::Title => "title COLLATE NOCASE ASC, id DESC",
+            Sort::Completion => "completed ASC, completed_at DESC, priority DESC, id DESC",
+        }
+    }
+}
+
+/// Stored by label, like `Status`.
+impl rusqlite::ToSql for Sort {
+    fn to_sql(&self) -> rusqlite::Result<rusqlite::types::ToSqlOutput<'_>> {
+        Ok(self.label().into())
+    }
+}
+
+impl rusqlite::types::FromSql for Sort {
+    fn column_result(value: rusqlite::types::ValueRef<'_>) -> rusqlite::types::FromSqlResult<Self> {
+        let label = value.as_str()?;
+        Self::CYCLE
+            .into_iter()
+            .chain([Sort::Relevance])
+            .find(|s| s.label() == label)
+            .ok_or(rusqlite::types::FromSqlError::InvalidType)
+    }
+}
+
+/// Builds the `SELECT` for a filtered, sorted task list. Every condition
+/// is optional; the default lists all tasks, most pressing first.
+#[derive(Debug, Clone, Default)]
+pub struct TaskQuery {
+    /// Only tasks in this project
+    pub project_id: Option<i64>,
+    pub status: Status,
+    /// Only tasks carrying every one of these tags (ignoring case)
+    pub tags: Vec<String>,
+    /// Only tasks matching this filter expression
+    pub filter: Option<Expr>,
+    pub sort: Sort,
+}
+
+impl TaskQuery {
+    /// The statement and its parameters, ready for `Persistence::query`.
+    pub fn build(&self) -> (String, Vec<Value>) {
+        let mut sql = format!("SELECT {} FROM tasks", TASK_COLUMNS);
+        let mut conditions = Vec::new();
+        let mut params = Vec::new();
+
+        let search = self.filter.as_ref().map(Expr::search).unwrap_or_default();
+        let ranked = self.sort == Sort::Relevance && !search.is_empty();
+        if ranked {
+            // any of the words counts; the filter decides which tasks are
+            // listed. Title matches count for more than description matches.
+            params.push(Value::Text(search.any_expression()));
+            sql.push_str(&format!(
+                " LEFT JOIN (SELECT rowid AS match_id, bm25(tasks_fts, 5.0, 1.0) AS score
+                             FROM tasks_fts WHERE tasks_fts MATCH ?{}) ON match_id = tasks.id",
+                params.len()
+            ));
+        }
+        if let Some(project_id) = self.project_id {
+            params.push(Value::Integer(project_id));
+            conditions.push(format!("project_id = ?{}", params.len()));
+        }
+        match self.status {
+            Status::All => {}
+            Status::Open => conditions.push("NOT completed".to_string()),
+            Status::Completed => conditions.push("completed".to_string()),
+        }
+        if let Some(filter) = &self.filter {
+            conditions.push(filter.to_sql(&mut params));
+        }
+        for tag in &self.tags {
+            params.push(Value::Text(tag.clone()));
+            conditions.push(format!(
+                "EXISTS (SELECT 1 FROM task_tags tt JOIN tags g ON g.id = tt.tag_id
+                  WHERE tt.task_id = tasks.id AND g.name = ?{} COLLATE NOCASE)",
+                params.len()
+            ));
+        }
+
+        if !conditions.is_empty() {
+            sql.push_str(" WHERE ");
+            sql.push_str(&conditions.join(" AND "));
+        }
+        sql.push_str(" ORDER BY ");
+        sql.push_str(self.sort.order_by(ranked));
+        (sql, params)
+    }
+}
@@ -0,0 +1,238 @@
+use std::{fmt, str::FromStr};
+
+use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime, NaiveTime, Weekday};
+
+use super::error::PersistenceError;
+
+/// Longest `after:N` interval accepted, in days: ten years.
+pub const MAX_AFTER_DAYS: u32 = 3650;
+
+/// Repeat rule of a recurring task. Stored in the `recurrence` column using
+/// the same text form users type: `daily`, `weekly:mon,fri`, `monthly:15`
+/// or `after:3`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Recurrence {
+    Daily,
+    /// On the given weekdays
+    Weekly(Vec<Weekday>),
+    /// On day N of every month (clamped to the month's length)
+    Monthly(u32),
+    /// N days after the previous occurrence was completed
+    AfterCompletion(u32),
+}
+
+impl Recurrence {
+    /// Due date of the occurrence following one due at `due` and completed
+    /// at `completed`. Calendar rules skip ahead past `completed` so a late
+    /// completion doesn't leave a trail of already-overdue occurrences.
+    /// Fails when that date is past the last one chrono can represent.
+    pub fn next_due(
+        &self,
+        due: Option<NaiveDateTime>,
+        completed: NaiveDateTime,
+        end_of_day: NaiveTime,
+    ) -> Result<NaiveDateTime, PersistenceError> {
+        let time = due.map(|d| d.time()).unwrap_or(end_of_day);
+
+        if let Recurrence::AfterCompletion(days) = self {
+            let next = completed.date().checked_add_signed(Duration::days(*days as i64));
+            return next.map(|d| d.and_time(time)).ok_or(PersistenceError::DateOutOfRange);
+        }
+
+        let mut date = due.map(|d| d.date()).unwrap_or(completed.date());
+        loop {
+            date = self.step(date).ok_or(PersistenceError::DateOutOfRange)?;
+            if date > completed.date() {
+                return Ok(date.and_time(time));
+            }
+        }
+    }
+
+    /// The first matching date strictly after `date`; `None` past the last
+    /// representable date.
+    fn step(&self, date: NaiveDate) -> Option<NaiveDate> {
+        match self {
+            Recurrence::Daily | Recurrence::AfterCompletion(_) => date.succ_opt(),
+            Recurrence::Weekly(days) => {
+                let mut next = date.succ_opt()?;
+                for _ in 0..6 {
+                    if days.contains(&next.weekday()) {
+                        break;
+                    }
+                    next = next.succ_opt()?;
+                }
+                Some(next)
+            }
+            Recurrence::Monthly(day) => {
+                let this_month = day_in_month(date.year(), date.month(), *day);
+                if this_month > date.day() {
+                    return date.with_day(this_month);
+                }
+                let (year, month) = if date.month() == 12 {
+                    (date.year().checked_add(1)?, 1)
+                } else {
+                    (date.year(), date.month() + 1)
+                };
+                NaiveDate::from_ymd_opt(year, month, day_in_month(year, month, *day))
+            }
+        }
+    }
+}
+
+/// `day` clamped to the length of the given month.
+fn day_in_month(year: i32, month: u32, day: u32) -> u32 {
+    (1..=day.min(31))
+        .rev()
+        .find(|&d| NaiveDate::from_ymd_opt(year, month, d).is_some())
+        .unwrap_or(1)
+}
+
+fn weekday_name(day: Weekday) -> &'static str {
+    match day {
+        Weekday::Mon => "mon",
+        Weekday::Tue => "tue",
+        Weekday::Wed => "wed",
+        Weekday::Thu => "thu",
+        Weekday::Fri => "fri",
+        Weekday::Sat => "sat",
+        Weekday::Sun => "sun",
+    }
+}
+
+impl fmt::Display for Recurrence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Recurrence::Daily => write!(f, "daily"),
+            Recurrence::Weekly(days) => {
+                let days: Vec<&str> = days.iter().map(|d| weekday_name(*d)).collect();
+                write!(f, "weekly:{}", days.join(","))
+            }
+            Recurrence::Monthly(day) => write!(f, "monthly:{}", day),
+            Recurrence::AfterCompletion(days) => write!(f, "after:{}", days),
+        }
+    }
+}
+
+impl FromStr for Recurrence {
+    type Err = String;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let input = input.trim().to_lowercase();
+        let (kind, arg) = input.split_once(':').unwrap_or((input.as_str(), ""));
+        let invalid = || {
+            format!(
+                "Invalid repeat rule '{}' (use daily, weekly:mon,fri, monthly:15 or after:3, at most after:{})",
+                input, MAX_AFTER_DAYS
+            )
+        };
+
+        match kind.trim() {
+            "daily" if arg.is_empty() => Ok(Recurrence::Daily),
+            "weekly" => {
+                let mut days = Vec::new();
+                for name in arg.split(',').map(str::trim).filter(|n| !n.is_empty()) {
+                    let day = name.parse::<Weekday>().map_err(|_| invalid())?;
+                    if !days.contains(&day) {
+                        days.push(day);
+                    }
+                }
+                if days.is_empty() {
+                    return Err(invalid());
+                }
+                days.sort_by_key(|d| d.num_days_from_monday());
+                Ok(Recurrence::Weekly(days))
+            }
+            "monthly" => match arg.trim().parse::<u32>() {
+                Ok(day) if (1..=31).contains(&day) => Ok(Recurrence::Monthly(day)),
+                _ => Err(invalid()),
+            },
+            "after" => match arg.trim().trim_end_matches('d').parse::<u32>() {
+                Ok(days) if (1..=MAX_AFTER_DAYS).contains(&days) => Ok(Recurrence::AfterCompletion(days)),
+                _ => Err(invalid()),
+            },
+            _ => Err(invalid()),
+        }
+    }
+}
+
+impl rusqlite::ToSql for Recurrence {
+    fn to_sql(&self) -> rusqlite::Result<rusqlite::types::ToSqlOutput<'_>> {
+        Ok(self.to_string().into())
+    }
+}
+
+impl rusqlite::types::FromSql for Recurrence {
+    fn column_result(value: rusqlite::types::ValueRef<'_>) -> rusqlite::types::FromSqlResult<Self> {
+        value
+            .as_str()?
+            .parse()
+            .map_err(|e: String| rusqlite::types::FromSqlError::Other(e.into()))
+    }
+}
+
+/// Serialized in its text form, e.g. `"weekly:mon,fri"`.
+impl serde::Serialize for Recurrence {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for Recurrence {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap().and_hms_opt(23, 59, 0).unwrap()
+    }
+
+    #[test]
+    fn text_form_round_trips() {
+        for rule in ["daily", "weekly:mon,fri", "monthly:31", "after:3", &format!("after:{}", MAX_AFTER_DAYS)] {
+            assert_eq!(rule.parse::<Recurrence>().unwrap().to_string(), rule);
+        }
+        assert_eq!("Weekly: fri, mon, fri".parse::<Recurrence>().unwrap().to_string(), "weekly:mon,fri");
+        assert_eq!("after:10d".parse::<Recurrence>(), Ok(Recurrence::AfterCompletion(10)));
+    }
+
+    #[test]
+    fn out_of_range_rules_are_rejected() {
+        for rule in ["after:0", "after:4000000000", &format!("after:{}", MAX_AFTER_DAYS + 1), "monthly:32", "weekly:", "hourly"] {
+            assert!(rule.parse::<Recurrence>().is_err(), "{}", rule);
+        }
+    }
+
+    #[test]
+    fn next_due_follows_the_rule() {
+        let time = date(2026, 1, 1).time();
+        let due = date(2026, 1, 30);
+        let next = |rule: &str, completed| rule.parse::<Recurrence>().unwrap().next_due(Some(due), completed, time).unwrap();
+
+        assert_eq!(next("daily", due), date(2026, 1, 31));
+        // completed late: skips the occurrences already past
+        assert_eq!(next("daily", date(2026, 2, 3)), date(2026, 2, 4));
+        assert_eq!(next("weekly:mon", due), date(2026, 2, 2));
+        assert_eq!(next("monthly:31", due), date(2026, 1, 31));
+        assert_eq!(next("monthly:31", date(2026, 1, 31)), date(2026, 2, 28));
+        assert_eq!(next("after:3", date(2026, 2, 10)), date(2026, 2, 13));
+    }
+
+    #[test]
+    fn next_due_past_the_calendar_fails() {
+        let time = date(2026, 1, 1).time();
+        let last = NaiveDate::MAX.and_time(time);
+        for rule in [
+            Recurrence::Daily,
+            Recurrence::Weekly(vec![Weekday::Mon]),
+            Recurrence::Monthly(1),
+            Recurrence::AfterCompletion(u32::MAX),
+        ] {
+            assert!(rule.next_due(Some(last), last, time).is_err(), "{}", rule);
+        }
+    }
+}
@@ -0,0 +1,162 @@
+/// Words typed into a search, matched against task titles and descriptions
+/// through the `tasks_fts` index. Every word must appear, as a whole word
+/// or the start of one, so results narrow as the user types.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Search {
+    terms: Vec<String>,
+}
+
+impl Search {
+    /// Splits `input` into words the way the index does: on anything that
+    /// isn't a letter or digit. Punctuation and FTS5 operators are ignored.
+    pub fn new(input: &str) -> Self {
+        let terms = input
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|t| !t.is_empty())
+            .map(str::to_lowercase)
+            .collect();
+        Search { terms }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.terms.is_empty()
+    }
+
+    /// Adds the terms of `other`.
+    pub fn extend(&mut self, other: &Search) {
+        self.terms.extend(other.terms.iter().cloned());
+    }
+
+    /// FTS5 query for the terms, bound to `MATCH`: each one quoted (so
+    /// words like `NOT` are plain text) and matched as a prefix.
+    pub fn match_expression(&self) -> String {
+        self.terms.iter().map(|t| format!("\"{}\"*", t)).collect::<Vec<_>>().join(" ")
+    }
+
+    /// Like `match_expression`, but any one term is enough; used to rank
+    /// tasks picked by other means.
+    pub fn any_expression(&self) -> String {
+        self.terms.iter().map(|t| format!("\"{}\"*", t)).collect::<Vec<_>>().join(" OR ")
+    }
+
+    /// FTS5 query matching the terms next to each other, in order.
+    pub fn phrase_expression(&self) -> String {
+        format!("\"{}\"", self.terms.join(" "))
+    }
+
+    /// Splits `text` into pieces, flagging the start of each word that
+    /// matches a term, e.g. for highlighting.
+    pub fn highlight<'a>(&self, text: &'a str) -> Vec<(&'a str, bool)> {
+        let mut pieces = Vec::new();
+        let mut rest = 0;
+        for (start, word) in words(text) {
+            let Some(len) = self.terms.iter().filter_map(|t| prefix_len(word, t)).max() else { continue };
+            if start > rest {
+                pieces.push((&text[rest..start], false));
+            }
+            pieces.push((&text[start..start + len], true));
+            rest = start + len;
+        }
+        if rest < text.len() {
+            pieces.push((&text[rest..], false));
+        }
+        pieces
+    }
+}
+
+/// Runs of letters and digits in `text`, with their byte offsets.
+fn words(text: &str) -> Vec<(usize, &str)> {
+    let mut words = Vec::new();
+    let mut start = None;
+    for (i, c) in text.char_indices().chain([(text.len(), ' ')]) {
+        match (c.is_alphanumeric(), start) {
+            (true, None) => start = Some(i),
+            (false, Some(s)) => {
+                words.push((s, &text[s..i]));
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    words
+}
+
+/// Length in bytes of the part of `word` matched by `term`, if `word`
+/// starts with it (ignoring case).
+fn prefix_len(word: &str, term: &str) -> Option<usize> {
+    let mut chars = word.char_indices();
+    for expected in term.chars() {
+        let (_, c) = chars.next()?;
+        if !c.to_lowercase().eq(expected.to_lowercase()) {
+            return None;
+        }
+    }
+    Some(chars.next().map(|(i, _)| i).unwrap_or(word.len()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::persistence::filter::parser::parse;
+    use crate::persistence::persistence::{Persistence, Task};
+    use crate::persistence::query::{Sort, TaskQuery};
+
+    fn titles(p: &Persistence, filter: &str, sort: Sort) -> Vec<String> {
+        let query = TaskQuery { filter: parse(filter).unwrap(), sort, ..TaskQuery::default() };
+        p.tasks(&query).unwrap().into_iter().map(|t| t.title).collect()
+    }
+
+    #[test]
+    fn terms_are_quoted_prefixes() {
+        let search = Search::new("Call NOT-the bank!");
+        assert_eq!(search.match_expression(), r#""call"* "not"* "the"* "bank"*"#);
+        assert_eq!(search.phrase_expression(), r#""call not the bank""#);
+        assert!(Search::new("?!").is_empty());
+    }
+
+    #[test]
+    fn highlight_flags_the_matched_start_of_words() {
+        let search = Search::new("ba");
+        assert_eq!(search.highlight("Call the Bank"), [("Call the ", false), ("Ba", true), ("nk", false)]);
+        assert_eq!(Search::new("über").highlight("Übersee"), [("Über", true), ("see", false)]);
+    }
+
+    #[test]
+    fn index_follows_updates_and_deletes() {
+        let p = Persistence::in_memory();
+        let id = p.save(&Task { title: "Call the bank".to_string(), ..Task::default() }).unwrap().unwrap();
+        let milk = p.save(&Task { title: "Buy milk".to_string(), ..Task::default() }).unwrap().unwrap();
+        assert_eq!(titles(&p, "bank", Sort::Priority), ["Call the bank"]);
+
+        let task = p.get::<Task>(id).unwrap().unwrap();
+        p.update(&Task { title: "Call the plumber".to_string(), ..task }).unwrap();
+        assert!(titles(&p, "bank", Sort::Priority).is_empty());
+        assert_eq!(titles(&p, "plumb", Sort::Priority), ["Call the plumber"]);
+
+        p.delete::<Task>(milk).unwrap();
+        assert!(titles(&p, "milk", Sort::Priority).is_empty());
+        let count: i64 = p
+            .connection
+            .as_ref()
+            .unwrap()
+            .query_row("SELECT COUNT(*) FROM tasks_fts WHERE tasks_fts MATCH 'milk'", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(count, 0);
+    }
+
+    #[test]
+    fn relevance_puts_title_matches_first() {
+        let p = Persistence::in_memory();
+        let add = |title: &str, description: &str| {
+            let task = Task { title: title.to_string(), description: Some(description.to_string()), ..Task::default() };
+            p.save(&task).unwrap();
+        };
+        // added first, so only the ranking puts it ahead of the newer task
+        add("Invoice for March", "Send it to accounting");
+        add("Renew passport", "Bring the old invoice");
+        add("Water plants", "");
+
+        assert_eq!(titles(&p, "invoice", Sort::Relevance), ["Invoice for March", "Renew passport"]);
+        assert_eq!(titles(&p, "invoice", Sort::Priority), ["Renew passport", "Invoice for March"]);
+    }
+}
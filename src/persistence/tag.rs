@@ -0,0 +1,70 @@
+use serde::{Deserialize, Serialize};
+
+use super::persistence::Persistable;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Tag {
+    pub id: Option<i64>,
+    pub name: String,
+    /// Number of tasks carrying the tag; read-only, computed on load
+    #[serde(skip_deserializing)]
+    pub task_count: i64,
+}
+
+impl Tag {
+    /// Splits a comma-separated tag field into clean, de-duplicated names.
+    pub fn parse_list(input: &str) -> Vec<String> {
+        let mut tags: Vec<String> = Vec::new();
+        for name in input.split(',').map(str::trim).filter(|n| !n.is_empty()) {
+            if !tags.iter().any(|t| t.eq_ignore_ascii_case(name)) {
+                tags.push(name.to_string());
+            }
+        }
+        tags
+    }
+}
+
+impl Persistable for Tag {
+    fn id(&self) -> Option<i64> {
+        self.id
+    }
+
+    fn insert_sql(&self) -> String {
+        "INSERT INTO tags (name, id) VALUES (?1, ?2)".to_string()
+    }
+
+    fn params(&self) -> Vec<&dyn rusqlite::ToSql> {
+        vec![&self.name, &self.id]
+    }
+
+    fn update_sql() -> String {
+        "UPDATE tags SET name = ?1 WHERE id = ?2".to_string()
+    }
+
+    fn update_params(&self) -> Vec<&dyn rusqlite::ToSql> {
+        vec![&self.name, &self.id]
+    }
+
+    fn get_all_sql() -> String {
+        "SELECT g.id, g.name, (SELECT COUNT(*) FROM task_tags tt WHERE tt.tag_id = g.id)
+         FROM tags g ORDER BY g.name".to_string()
+    }
+
+    fn get_sql() -> String {
+        "SELECT g.id, g.name, (SELECT COUNT(*) FROM task_tags tt WHERE tt.tag_id = g.id)
+         FROM tags g WHERE g.id = ?1".to_string()
+    }
+
+    fn delete_sql() -> String {
+        "DELETE FROM tags WHERE id = ?1".to_string()
+    }
+
+    fn from_row(row: &rusqlite::Row) -> rusqlite::Result<Self> {
+        Ok(Tag {
+            id: row.get(0)?,
+            name: row.get(1)?,
+            task_count: row.get(2)?,
+        })
+    }
+}
@@ -0,0 +1,105 @@
+use super::filter::error::FilterError;
+use super::filter::parser::parse;
+use super::persistence::Persistable;
+use super::query::{Sort, Status, TaskQuery};
+
+/// A saved task list: a filter expression with the status and order to
+/// show it in, across every project. Listed on the main menu.
+#[derive(Debug, Clone, Default)]
+pub struct View {
+    pub id: Option<i64>,
+    /// Unique, ignoring case
+    pub name: String,
+    /// Filter expression as typed; empty for every task
+    pub filter: String,
+    pub status: Status,
+    pub sort: Sort,
+}
+
+impl View {
+    /// The tasks the view lists. Fails if the saved filter no longer parses.
+    pub fn query(&self) -> Result<TaskQuery, FilterError> {
+        Ok(TaskQuery { status: self.status, filter: parse(&self.filter)?, sort: self.sort, ..TaskQuery::default() })
+    }
+}
+
+impl Persistable for View {
+    fn id(&self) -> Option<i64> {
+        self.id
+    }
+
+    fn insert_sql(&self) -> String {
+        "INSERT INTO views (name, filter, status, sort, id) VALUES (?1, ?2, ?3, ?4, ?5)".to_string()
+    }
+
+    fn params(&self) -> Vec<&dyn rusqlite::ToSql> {
+        vec![&self.name, &self.filter, &self.status, &self.sort, &self.id]
+    }
+
+    fn update_sql() -> String {
+        "UPDATE views SET name = ?1, filter = ?2, status = ?3, sort = ?4 WHERE id = ?5".to_string()
+    }
+
+    fn update_params(&self) -> Vec<&dyn rusqlite::ToSql> {
+        vec![&self.name, &self.filter, &self.status, &self.sort, &self.id]
+    }
+
+    fn get_all_sql() -> String {
+        "SELECT id, name, filter, status, sort FROM views ORDER BY id".to_string()
+    }
+
+    fn get_sql() -> String {
+        "SELECT id, name, filter, status, sort FROM views WHERE id = ?1".to_string()
+    }
+
+    fn delete_sql() -> String {
+        "DELETE FROM views WHERE id = ?1".to_string()
+    }
+
+    fn from_row(row: &rusqlite::Row) -> rusqlite::Result<Self> {
+        Ok(View {
+            id: row.get(0)?,
+            name: row.get(1)?,
+            filter: row.get(2)?,
+            status: row.get(3)?,
+            sort: row.get(4)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::persistence::persistence::{Persistence, Task};
+
+    #[test]
+    fn views_round_trip_and_count_their_tasks() {
+        let p = Persistence::in_memory();
+        let view = View {
+            name: "Work".to_string(),
+            filter: "tag:work or title~report".to_string(),
+            status: Status::Open,
+            sort: Sort::Relevance,
+            ..View::default()
+        };
+        let id = p.save(&view).unwrap().unwrap();
+        let saved = p.get::<View>(id).unwrap().unwrap();
+        assert_eq!((saved.name.as_str(), saved.filter.as_str()), ("Work", view.filter.as_str()));
+        assert_eq!((saved.status, saved.sort), (Status::Open, Sort::Relevance));
+
+        p.save(&Task { title: "Plan sprint".to_string(), tags: vec!["work".to_string()], ..Task::default() }).unwrap();
+        p.save(&Task { title: "Write report".to_string(), ..Task::default() }).unwrap();
+        p.save(&Task { title: "Old report".to_string(), completed: true, ..Task::default() }).unwrap();
+        p.save(&Task { title: "Buy milk".to_string(), ..Task::default() }).unwrap();
+        assert_eq!(p.count(&saved.query().unwrap()).unwrap(), 2);
+    }
+
+    #[test]
+    fn names_are_unique_ignoring_case_and_bad_filters_fail_to_query() {
+        let p = Persistence::in_memory();
+        assert!(p.save(&View { name: "today".to_string(), ..View::default() }).is_err());
+
+        let view = View { name: "Broken".to_string(), filter: "due<".to_string(), ..View::default() };
+        assert!(view.query().is_err());
+    }
+}
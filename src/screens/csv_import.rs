@@ -0,0 +1,266 @@
+use crate::exchange::csv::{ColumnMap, Field, Table};
+use crate::exchange::dataset::{Dataset, ImportMode};
+use crate::exchange::error::{ExchangeError, RowError};
+use crate::persistence::persistence::{Persistence, Priority, Task};
+use crate::screens::settings::SettingsScreen;
+use crate::screens::tasks::ListOptions;
+use crate::ui::screen::{Screen, ScreenAction};
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::layout::{Constraint, Direction, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, List, ListItem, ListState, Paragraph, Row, Table as TableWidget};
+use ratatui::Frame;
+use std::path::PathBuf;
+
+/// Most rejected rows listed at once; the rest are only counted.
+const SHOWN_ERRORS: usize = 5;
+
+/// Previews a CSV import: which column feeds each field, the tasks that
+/// would be added and the rows that would be skipped. The mapping can be
+/// changed before anything is written.
+pub struct CsvImportScreen {
+    path: PathBuf,
+    table: Table,
+    map: ColumnMap,
+    /// Selected field in the mapping list
+    state: ListState,
+    data: Dataset,
+    errors: Vec<RowError>,
+    error: Option<String>,
+    /// Handed back to the settings screen
+    list_options: ListOptions,
+}
+
+impl CsvImportScreen {
+    pub fn new(path: PathBuf, table: Table, list_options: ListOptions) -> Self {
+        let mut state = ListState::default();
+        state.select(Some(0));
+        let mut screen = CsvImportScreen {
+            path,
+            map: ColumnMap::detect(&table.headers),
+            table,
+            state,
+            data: Dataset::default(),
+            errors: Vec::new(),
+            error: None,
+            list_options,
+        };
+        screen.refresh();
+        screen
+    }
+
+    fn selected_field(&self) -> Field {
+        Field::ALL[self.state.selected().unwrap_or(0)]
+    }
+
+    /// Re-reads the rows with the current mapping.
+    fn refresh(&mut self) {
+        match self.table.tasks(&self.map) {
+            Ok((data, errors)) => {
+                self.data = data;
+                self.errors = errors;
+                self.error = None;
+            }
+            Err(e) => {
+                self.data = Dataset::default();
+                self.errors.clear();
+                self.error = Some(e.to_string());
+            }
+        }
+    }
+
+    /// Moves the selected field to the next or previous column, passing
+    /// through "not imported".
+    fn cycle_column(&mut self, forward: bool) {
+        let field = self.selected_field();
+        let count = self.table.headers.len() + 1;
+        // 0 is "not imported", n is column n - 1
+        let current = self.map.get(field).map(|c| c + 1).unwrap_or(0);
+        let next = if forward { (current + 1) % count } else { (current + count - 1) % count };
+        self.map.set(field, next.checked_sub(1));
+        self.refresh();
+    }
+
+    fn import(&mut self) -> Option<ScreenAction> {
+        if self.data.tasks.is_empty() {
+            self.error = Some("Nothing to import".to_string());
+            return None;
+        }
+        let result = Persistence::new()
+            .and_then(|p| p.sync_schema().map(|_| p))
+            .map_err(ExchangeError::from)
+            .and_then(|persistence| self.data.import(&persistence, ImportMode::Merge, false));
+        let message = match result {
+            Ok(report) if self.errors.is_empty() => {
+                format!(" ✓ Imported {} tasks from {}", report.tasks.added, self.path.display())
+            }
+            Ok(report) => format!(
+                " ✓ Imported {} tasks from {} ({} rows skipped)",
+                report.tasks.added,
+                self.path.display(),
+                self.errors.len()
+            ),
+            Err(e) => format!(" ✗ Import failed: {}", e),
+        };
+        self.back(message)
+    }
+
+    fn back(&self, message: String) -> Option<ScreenAction> {
+        let mut settings = SettingsScreen::new(self.list_options);
+        settings.status_message = Some(message);
+        Some(ScreenAction::Switch(Box::new(settings)))
+    }
+}
+
+impl Screen for CsvImportScreen {
+    fn handle_input(&mut self, key: KeyEvent) -> Option<ScreenAction> {
+        match key.code {
+            KeyCode::Down => {
+                let i = self.state.selected().map(|i| (i + 1) % Field::ALL.len()).unwrap_or(0);
+                self.state.select(Some(i));
+                None
+            }
+            KeyCode::Up => {
+                let i = self.state.selected().map(|i| (i + Field::ALL.len() - 1) % Field::ALL.len()).unwrap_or(0);
+                self.state.select(Some(i));
+                None
+            }
+            KeyCode::Right => {
+                self.cycle_column(true);
+                None
+            }
+            KeyCode::Left => {
+                self.cycle_column(false);
+                None
+            }
+            KeyCode::Enter => self.import(),
+            KeyCode::Esc | KeyCode::Char('q') => self.back(" Import cancelled.".to_string()),
+            _ => None,
+        }
+    }
+
+    fn render(&mut self, frame: &mut Frame, area: Rect) {
+        let error_lines = self.errors.len().min(SHOWN_ERRORS + 1) as u16;
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(Field::ALL.len() as u16 + 2),
+                Constraint::Min(4),
+                Constraint::Length(if error_lines > 0 { error_lines + 2 } else { 0 }),
+                Constraint::Length(3),
+            ])
+            .split(area);
+
+        // --- Column mapping ---
+        let items: Vec<ListItem> = Field::ALL
+            .iter()
+            .map(|field| {
+                let column = match self.map.get(*field) {
+                    Some(i) => Span::styled(
+                        format!("column {} \"{}\"", i + 1, self.table.headers[i]),
+                        Style::default().fg(Color::Cyan),
+                    ),
+                    None => Span::styled("not imported", Style::default().fg(Color::DarkGray)),
+                };
+                ListItem::new(Line::from(vec![Span::raw(format!("  {:<12} ← ", field.name())), column]))
+            })
+            .collect();
+        let list = List::new(items)
+            .block(
+                Block::default()
+                    .title(format!(" Import {} ", self.path.display()))
+                    .borders(Borders::ALL)
+                    .border_style(Style::default().fg(Color::Cyan))
+                    .style(Style::default().bg(Color::Black)),
+            )
+            .highlight_style(
+                Style::default()
+                    .bg(Color::Blue)
+                    .fg(Color::White)
+                    .add_modifier(Modifier::BOLD),
+            )
+            .highlight_symbol(">>");
+        frame.render_stateful_widget(list, chunks[0], &mut self.state);
+
+        // --- Tasks that would be added ---
+        let rows: Vec<Row> = self
+            .data
+            .tasks
+            .iter()
+            .map(|task| {
+                let project = self.data.projects.iter().find(|p| p.id == task.project_id);
+                Row::new(vec![
+                    if task.completed { "[x]" } else { "[ ]" }.to_string(),
+                    task.title.clone(),
+                    task.due.as_ref().map(Task::format_due).unwrap_or_default(),
+                    Some(task.priority).filter(|p| *p != Priority::None).map(|p| p.label().to_string()).unwrap_or_default(),
+                    project.map(|p| p.name.clone()).unwrap_or_default(),
+                    task.tags.join(", "),
+                ])
+            })
+            .collect();
+        let preview = TableWidget::new(
+            rows,
+            [
+                Constraint::Length(3),
+                Constraint::Percentage(40),
+                Constraint::Length(16),
+                Constraint::Length(8),
+                Constraint::Percentage(20),
+                Constraint::Percentage(20),
+            ],
+        )
+        .header(
+            Row::new(vec!["", "Title", "Due", "Priority", "Project", "Tags"])
+                .style(Style::default().fg(Color::DarkGray).add_modifier(Modifier::BOLD)),
+        )
+        .block(
+            Block::default()
+                .title(format!(" {} tasks to add ", self.data.tasks.len()))
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::Cyan))
+                .style(Style::default().bg(Color::Black)),
+        );
+        frame.render_widget(preview, chunks[1]);
+
+        // --- Rows that would be skipped ---
+        if !self.errors.is_empty() {
+            let mut lines: Vec<Line> = self
+                .errors
+                .iter()
+                .take(SHOWN_ERRORS)
+                .map(|e| Line::from(Span::styled(format!("  {}", e), Style::default().fg(Color::Red))))
+                .collect();
+            if self.errors.len() > SHOWN_ERRORS {
+                lines.push(Line::from(Span::styled(
+                    format!("  … and {} more", self.errors.len() - SHOWN_ERRORS),
+                    Style::default().fg(Color::DarkGray),
+                )));
+            }
+            let errors = Paragraph::new(lines).block(
+                Block::default()
+                    .title(format!(" {} rows will be skipped ", self.errors.len()))
+                    .borders(Borders::ALL)
+                    .border_style(Style::default().fg(Color::Red))
+                    .style(Style::default().bg(Color::Black)),
+            );
+            frame.render_widget(errors, chunks[2]);
+        }
+
+        // --- Status bar ---
+        let (status_text, status_color) = match &self.error {
+            Some(error) => (format!("  ✗ {}", error), Color::Red),
+            None => (
+                "  ↑↓ field   ←→ column   Enter → import   Esc → cancel".to_string(),
+                Color::Green,
+            ),
+        };
+        let status = Paragraph::new(Line::from(Span::styled(status_text, Style::default().fg(status_color))))
+            .block(Block::default()
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::DarkGray))
+                .style(Style::default().bg(Color::Black)));
+        frame.render_widget(status, chunks[3]);
+    }
+}
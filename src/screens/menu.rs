@@ -1,39 +1,114 @@
+use crate::persistence::error::PersistenceError;
+use crate::persistence::persistence::Persistence;
+use crate::persistence::view::View;
+use crate::screens::projects::ProjectsScreen;
 use crate::screens::settings::SettingsScreen;
-use crate::screens::tasks::TasksScreen;
+use crate::screens::tasks::{ListOptions, TasksScreen};
 use crate::ui::navigation::NavigatableList;
 use crate::ui::screen::{Screen, ScreenAction};
 use crossterm::event::{KeyCode, KeyEvent};
-use ratatui::layout::Rect;
+use ratatui::layout::{Constraint, Direction, Layout, Rect};
 use ratatui::style::{Color, Modifier, Style};
-use ratatui::widgets::{Block, Borders, List, ListItem};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, List, ListItem, Paragraph};
 use ratatui::Frame;
 
 pub struct MenuScreen {
     pub title: String,
     pub list: NavigatableList,
+    /// Saved views, listed right after "View Tasks"
+    views: Vec<View>,
+    /// Number of tasks in each view; `None` where its filter no longer parses
+    counts: Vec<Option<i64>>,
+    persistence: Persistence,
+    /// Waiting for confirmation before deleting the selected view
+    confirm_delete: bool,
+    error: Option<String>,
+    /// Passed on to the screens opened from here
+    list_options: ListOptions,
 }
 
 impl MenuScreen {
-    pub fn new() -> Self {
-        let mut list = NavigatableList {
-            state: ratatui::widgets::ListState::default(),
-            options: vec![
-                "View Tasks".to_string(),
-                "Settings".to_string(),
-                "Exit".to_string(),
-            ],
+    pub fn new(list_options: ListOptions) -> Self {
+        let mut menu = MenuScreen {
+            title: "Main Menu".to_string(),
+            list: NavigatableList { state: ratatui::widgets::ListState::default(), options: Vec::new() },
+            views: Vec::new(),
+            counts: Vec::new(),
+            persistence: Persistence { connection: None },
+            confirm_delete: false,
+            error: None,
+            list_options,
         };
-        list.state.select(Some(0));
 
-        MenuScreen {
-            title: "Main Menu".to_string(),
-            list,
+        let opened = Persistence::new().and_then(|p| p.sync_schema().map(|_| p));
+        if let Some(persistence) = menu.check(opened) {
+            menu.persistence = persistence;
+        }
+        menu.reload();
+        menu.list.state.select(Some(0));
+        menu
+    }
+
+    fn check<T>(&mut self, result: Result<T, PersistenceError>) -> Option<T> {
+        match result {
+            Ok(value) => Some(value),
+            Err(e) => {
+                self.error = Some(e.to_string());
+                None
+            }
+        }
+    }
+
+    /// Loads the views and their counts, and lays out the entries.
+    fn reload(&mut self) {
+        let loaded = self.persistence.get_all::<View>();
+        let views: Vec<View> = self.check(loaded).unwrap_or_default();
+        self.counts = views
+            .iter()
+            .map(|view| {
+                let counted = self.persistence.count(&view.query().ok()?);
+                self.check(counted)
+            })
+            .collect();
+        self.views = views;
+
+        let mut options = vec!["View Tasks".to_string()];
+        options.extend(self.views.iter().map(|v| v.name.clone()));
+        options.extend(["Projects", "Settings", "Exit"].map(String::from));
+        self.list.options = options;
+
+        let last = self.list.options.len() - 1;
+        if let Some(i) = self.list.state.selected() {
+            self.list.state.select(Some(i.min(last)));
         }
     }
+
+    /// Index in `views` of the entry at `selected`, if it is a view.
+    fn view_at(&self, selected: usize) -> Option<usize> {
+        (1..=self.views.len()).contains(&selected).then(|| selected - 1)
+    }
+
+    fn selected_view(&self) -> Option<usize> {
+        self.list.state.selected().and_then(|i| self.view_at(i))
+    }
 }
 
 impl Screen for MenuScreen {
     fn handle_input(&mut self, key: KeyEvent) -> Option<ScreenAction> {
+        self.error = None;
+        if self.confirm_delete {
+            self.confirm_delete = false;
+            if key.code == KeyCode::Enter
+                && let Some(id) = self.selected_view().and_then(|i| self.views[i].id)
+            {
+                let result = self.persistence.delete::<View>(id);
+                self.check(result);
+                self.reload();
+            }
+            return None;
+        }
+
         match key.code {
             KeyCode::Down => {
                 self.list.next();
@@ -45,24 +120,50 @@ impl Screen for MenuScreen {
             }
             KeyCode::Enter => {
                 let selected = self.list.state.selected().unwrap_or(0);
+                if let Some(i) = self.view_at(selected) {
+                    return Some(ScreenAction::Switch(Box::new(TasksScreen::for_view(self.views[i].clone(), self.list_options))));
+                }
                 match self.list.options[selected].as_str() {
-                    "View Tasks" => Some(ScreenAction::Switch(Box::new(TasksScreen::new()))),
-                    "Settings" => Some(ScreenAction::Switch(Box::new(SettingsScreen::new()))),
+                    "View Tasks" => Some(ScreenAction::Switch(Box::new(TasksScreen::new(self.list_options)))),
+                    "Projects" => Some(ScreenAction::Switch(Box::new(ProjectsScreen::new(self.list_options)))),
+                    "Settings" => Some(ScreenAction::Switch(Box::new(SettingsScreen::new(self.list_options)))),
                     "Exit" => Some(ScreenAction::Exit),
                     _ => None,
                 }
             }
+            KeyCode::Char('d') | KeyCode::Delete => {
+                self.confirm_delete = self.selected_view().is_some();
+                None
+            }
             KeyCode::Char('q') => Some(ScreenAction::Exit),
             _ => None,
         }
     }
 
     fn render(&mut self, frame: &mut Frame, area: Rect) {
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(3), Constraint::Length(3)])
+            .split(area);
+
         let items: Vec<ListItem> = self
             .list
             .options
             .iter()
-            .map(|label| ListItem::new(format!("  {}", label)))
+            .enumerate()
+            .map(|(i, label)| match self.view_at(i) {
+                Some(v) => {
+                    let count = match self.counts.get(v).copied().flatten() {
+                        Some(count) => format!("  ({})", count),
+                        None => "  (invalid filter)".to_string(),
+                    };
+                    ListItem::new(Line::from(vec![
+                        Span::raw(format!("    {}", label)),
+                        Span::styled(count, Style::default().fg(Color::DarkGray)),
+                    ]))
+                }
+                None => ListItem::new(format!("  {}", label)),
+            })
             .collect();
 
         let list = List::new(items)
@@ -81,6 +182,24 @@ impl Screen for MenuScreen {
             )
             .highlight_symbol(">>");
 
-        frame.render_stateful_widget(list, area, &mut self.list.state);
+        frame.render_stateful_widget(list, chunks[0], &mut self.list.state);
+
+        let (status_text, status_color) = match self.selected_view() {
+            _ if self.error.is_some() => (format!("  ✗ {}", self.error.as_deref().unwrap_or_default()), Color::Red),
+            Some(i) if self.confirm_delete => (
+                format!("  ⚠  Delete the view '{}'? Its tasks are kept.   Enter → confirm   Esc → cancel", self.views[i].name),
+                Color::Red,
+            ),
+            Some(_) => ("  ↑↓ navigate   Enter → open   d → delete view   q → quit".to_string(), Color::Green),
+            None => ("  ↑↓ navigate   Enter → open   q → quit".to_string(), Color::Green),
+        };
+
+        let status = Paragraph::new(Line::from(Span::styled(status_text, Style::default().fg(status_color))))
+            .block(Block::default()
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::DarkGray))
+                .style(Style::default().bg(Color::Black)));
+
+        frame.render_widget(status, chunks[1]);
     }
-}
\ No newline at end of file
+}
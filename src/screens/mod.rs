@@ -1,3 +1,5 @@
+pub mod csv_import;
 pub mod menu;
+pub mod projects;
 pub mod settings;
 pub mod tasks;
\ No newline at end of file
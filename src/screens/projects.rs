@@ -0,0 +1,238 @@
+use crate::persistence::error::PersistenceError;
+use crate::persistence::persistence::Persistence;
+use crate::persistence::project::Project;
+use crate::screens::tasks::{ListOptions, TasksScreen};
+use crate::ui::screen::{Screen, ScreenAction};
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::layout::{Constraint, Direction, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, List, ListItem, ListState, Paragraph};
+use ratatui::Frame;
+
+enum ProjectsMode {
+    /// Navigate the project list
+    View,
+    /// Typing the name of a new project
+    Adding(String),
+    /// Waiting for confirmation before deleting a project and its tasks
+    ConfirmDelete,
+}
+
+pub struct ProjectsScreen {
+    pub title: String,
+    projects: Vec<Project>,
+    state: ListState,
+    mode: ProjectsMode,
+    persistence: Persistence,
+    error: Option<String>,
+    /// Passed on to the task list and back to the menu
+    list_options: ListOptions,
+}
+
+impl ProjectsScreen {
+    pub fn new(list_options: ListOptions) -> Self {
+        let mut screen = ProjectsScreen {
+            title: "Projects".to_string(),
+            projects: Vec::new(),
+            state: ListState::default(),
+            mode: ProjectsMode::View,
+            persistence: Persistence { connection: None },
+            error: None,
+            list_options,
+        };
+
+        let opened = Persistence::new().and_then(|p| p.sync_schema().map(|_| p));
+        if let Some(persistence) = screen.check(opened) {
+            screen.persistence = persistence;
+            screen.reload();
+        }
+        screen
+    }
+
+    fn check<T>(&mut self, result: Result<T, PersistenceError>) -> Option<T> {
+        match result {
+            Ok(value) => Some(value),
+            Err(e) => {
+                self.error = Some(e.to_string());
+                None
+            }
+        }
+    }
+
+    fn reload(&mut self) {
+        let loaded = self.persistence.get_all::<Project>();
+        let Some(projects) = self.check(loaded) else { return };
+        self.projects = projects;
+        if self.projects.is_empty() {
+            self.state.select(None);
+        } else {
+            let i = self.state.selected().unwrap_or(0).min(self.projects.len() - 1);
+            self.state.select(Some(i));
+        }
+    }
+
+    fn selected_project(&self) -> Option<&Project> {
+        self.state.selected().and_then(|i| self.projects.get(i))
+    }
+
+    fn list_next(&mut self) {
+        if self.projects.is_empty() { return; }
+        let i = match self.state.selected() {
+            Some(i) => if i >= self.projects.len() - 1 { 0 } else { i + 1 },
+            None => 0,
+        };
+        self.state.select(Some(i));
+    }
+
+    fn list_prev(&mut self) {
+        if self.projects.is_empty() { return; }
+        let i = match self.state.selected() {
+            Some(i) => if i == 0 { self.projects.len() - 1 } else { i - 1 },
+            None => 0,
+        };
+        self.state.select(Some(i));
+    }
+}
+
+impl Screen for ProjectsScreen {
+    fn handle_input(&mut self, key: KeyEvent) -> Option<ScreenAction> {
+        self.error = None;
+        match &mut self.mode {
+            ProjectsMode::View => match key.code {
+                KeyCode::Up => { self.list_prev(); None }
+                KeyCode::Down => { self.list_next(); None }
+                KeyCode::Enter => {
+                    let id = self.selected_project().and_then(|p| p.id)?;
+                    Some(ScreenAction::Switch(Box::new(TasksScreen::for_project(id, self.list_options))))
+                }
+                KeyCode::Char('a') => {
+                    self.mode = ProjectsMode::Adding(String::new());
+                    None
+                }
+                KeyCode::Char('d') | KeyCode::Delete => {
+                    match self.selected_project() {
+                        Some(project) if project.is_inbox() => {
+                            self.error = Some("The Inbox cannot be deleted".to_string());
+                        }
+                        Some(_) => self.mode = ProjectsMode::ConfirmDelete,
+                        None => {}
+                    }
+                    None
+                }
+                KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('b') => {
+                    let menu = crate::screens::menu::MenuScreen::new(self.list_options);
+                    Some(ScreenAction::Switch(Box::new(menu)))
+                }
+                _ => None,
+            },
+            ProjectsMode::Adding(name) => match key.code {
+                KeyCode::Esc => {
+                    self.mode = ProjectsMode::View;
+                    None
+                }
+                KeyCode::Enter => {
+                    let name = name.trim().to_string();
+                    if !name.is_empty() {
+                        let result = self.persistence.save(&Project { name, ..Default::default() });
+                        let id = self.check(result).flatten();
+                        self.reload();
+                        if let Some(i) = self.projects.iter().position(|p| id.is_some() && p.id == id) {
+                            self.state.select(Some(i));
+                        }
+                    }
+                    self.mode = ProjectsMode::View;
+                    None
+                }
+                KeyCode::Backspace => {
+                    name.pop();
+                    None
+                }
+                KeyCode::Char(c) => {
+                    name.push(c);
+                    None
+                }
+                _ => None,
+            },
+            ProjectsMode::ConfirmDelete => match key.code {
+                KeyCode::Enter => {
+                    if let Some(project) = self.selected_project().cloned() {
+                        let result = self.persistence.delete_project_logged(&project);
+                        self.check(result);
+                    }
+                    self.reload();
+                    self.mode = ProjectsMode::View;
+                    None
+                }
+                KeyCode::Esc | KeyCode::Char('n') => {
+                    self.mode = ProjectsMode::View;
+                    None
+                }
+                _ => None,
+            },
+        }
+    }
+
+    fn render(&mut self, frame: &mut Frame, area: Rect) {
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(3), Constraint::Length(3)])
+            .split(area);
+
+        let items: Vec<ListItem> = self
+            .projects
+            .iter()
+            .map(|p| {
+                ListItem::new(Line::from(vec![
+                    Span::raw(format!("  {}", p.name)),
+                    Span::styled(format!("  ({} open)", p.open_tasks), Style::default().fg(Color::DarkGray)),
+                ]))
+            })
+            .collect();
+
+        let list = List::new(items)
+            .block(
+                Block::default()
+                    .title(format!(" {} ", self.title))
+                    .borders(Borders::ALL)
+                    .border_style(Style::default().fg(Color::Cyan))
+                    .style(Style::default().bg(Color::Black)),
+            )
+            .highlight_style(
+                Style::default()
+                    .bg(Color::Blue)
+                    .fg(Color::White)
+                    .add_modifier(Modifier::BOLD),
+            )
+            .highlight_symbol(">>");
+
+        frame.render_stateful_widget(list, chunks[0], &mut self.state);
+
+        let (status_text, status_color) = match &self.mode {
+            _ if self.error.is_some() => (
+                format!("  ✗ {}", self.error.as_deref().unwrap_or_default()),
+                Color::Red,
+            ),
+            ProjectsMode::View => (
+                "  ↑↓ navigate   Enter → open   a → add   d → delete   q/Esc → back".to_string(),
+                Color::Green,
+            ),
+            ProjectsMode::Adding(name) => (
+                format!("  New project name: {}_   Enter → save   Esc → cancel", name),
+                Color::Yellow,
+            ),
+            ProjectsMode::ConfirmDelete => (
+                "  ⚠  Delete this project and all its tasks?   Enter → confirm   Esc/n → cancel".to_string(),
+                Color::Red,
+            ),
+        };
+
+        let status = Paragraph::new(Line::from(Span::styled(status_text, Style::default().fg(status_color))))
+            .block(Block::default()
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::DarkGray))
+                .style(Style::default().bg(Color::Black)));
+
+        frame.render_widget(status, chunks[1]);
+    }
+}
@@ -1,3 +1,10 @@
+use crate::exchange::csv::Table;
+use crate::exchange::dataset::{ImportMode, ImportReport};
+use crate::exchange::error::ExchangeError;
+use crate::exchange::format::Format;
+use crate::persistence::persistence::Persistence;
+use crate::screens::csv_import::CsvImportScreen;
+use crate::screens::tasks::ListOptions;
 use crate::ui::navigation::NavigatableList;
 use crate::ui::screen::{Screen, ScreenAction};
 use crossterm::event::{KeyCode, KeyEvent};
@@ -6,6 +13,7 @@ use ratatui::style::{Color, Modifier, Style};
 use ratatui::text::{Line, Span};
 use ratatui::widgets::{Block, Borders, Clear, List, ListItem, Paragraph, Wrap};
 use ratatui::Frame;
+use std::path::PathBuf;
 use std::sync::mpsc::{self, Receiver};
 use std::thread;
 
@@ -17,20 +25,45 @@ pub enum UpdateState {
     UpdateAvailable(String, String, String),
 }
 
+/// Export or import in progress.
+pub enum Transfer {
+    /// Typing the file to export to
+    ExportPath(String),
+    /// Typing the file to import from
+    ImportPath(String),
+    /// Showing what an import would change; waiting for merge, replace or
+    /// cancel
+    ConfirmImport(Box<ImportPreview>),
+}
+
+/// A file read for import, with dry-run reports for both modes.
+pub struct ImportPreview {
+    path: PathBuf,
+    format: Format,
+    input: String,
+    merge: ImportReport,
+    replace: ImportReport,
+}
+
 pub struct SettingsScreen {
     pub title: String,
     pub list: NavigatableList,
     pub status_message: Option<String>,
     pub update_state: UpdateState,
+    pub transfer: Option<Transfer>,
     update_rx: Option<Receiver<Option<(String, String, String)>>>,
+    /// Handed back to the menu
+    list_options: ListOptions,
 }
 
 impl SettingsScreen {
-    pub fn new() -> Self {
+    pub fn new(list_options: ListOptions) -> Self {
         let mut list = NavigatableList {
             state: ratatui::widgets::ListState::default(),
             options: vec![
                 "Check for Updates".to_string(),
+                "Export Tasks".to_string(),
+                "Import Tasks".to_string(),
                 "Back".to_string(),
             ],
         };
@@ -41,8 +74,112 @@ impl SettingsScreen {
             list,
             status_message: None,
             update_state: UpdateState::Idle,
+            transfer: None,
             update_rx: None,
+            list_options,
+        }
+    }
+
+    fn open() -> Result<Persistence, ExchangeError> {
+        let persistence = Persistence::new()?;
+        persistence.sync_schema()?;
+        Ok(persistence)
+    }
+
+    /// The typed path and the format its extension selects (JSON otherwise).
+    fn target(path: &str) -> (PathBuf, Format) {
+        let path = PathBuf::from(path.trim());
+        let format = Format::from_path(&path).unwrap_or(Format::Json);
+        (path, format)
+    }
+
+    fn export(&mut self, path: &str) {
+        let (path, format) = Self::target(path);
+        let result = Self::open()
+            .and_then(|persistence| format.export(&persistence))
+            .and_then(|text| std::fs::write(&path, text).map_err(ExchangeError::from));
+        self.status_message = Some(match result {
+            Ok(()) => format!(" ✓ Exported to {}", path.display()),
+            Err(e) => format!(" ✗ Export failed: {}", e),
+        });
+        self.transfer = None;
+    }
+
+    /// Dry-runs both import modes so the user can pick one.
+    fn preview_import(&mut self, path: &str) {
+        let (path, format) = Self::target(path);
+        let preview = || -> Result<Transfer, ExchangeError> {
+            let input = std::fs::read_to_string(&path)?;
+            let persistence = Self::open()?;
+            let merge = format.import(&persistence, &input, ImportMode::Merge, true)?;
+            let replace = format.import(&persistence, &input, ImportMode::Replace, true)?;
+            Ok(Transfer::ConfirmImport(Box::new(ImportPreview { path: path.clone(), format, input, merge, replace })))
+        };
+        match preview() {
+            Ok(transfer) => self.transfer = Some(transfer),
+            Err(e) => {
+                self.status_message = Some(format!(" ✗ Cannot import {}: {}", path.display(), e));
+                self.transfer = None;
+            }
+        }
+    }
+
+    fn import(&mut self, mode: ImportMode) {
+        let Some(Transfer::ConfirmImport(preview)) = self.transfer.take() else { return };
+        let ImportPreview { path, format, input, .. } = *preview;
+        let result = Self::open().and_then(|persistence| format.import(&persistence, &input, mode, false));
+        self.status_message = Some(match result {
+            Ok(report) => format!(" ✓ Imported {} ({} tasks added, {} updated)", path.display(), report.tasks.added, report.tasks.updated),
+            Err(e) => format!(" ✗ Import failed: {}", e),
+        });
+    }
+
+    /// CSV files get their own screen, where the columns can be mapped.
+    fn preview_csv(&mut self, path: &str) -> Option<ScreenAction> {
+        let (path, _) = Self::target(path);
+        let table = std::fs::read_to_string(&path).map_err(ExchangeError::from).and_then(|input| Table::read(&input));
+        match table {
+            Ok(table) => Some(ScreenAction::Switch(Box::new(CsvImportScreen::new(path, table, self.list_options)))),
+            Err(e) => {
+                self.status_message = Some(format!(" ✗ Cannot import {}: {}", path.display(), e));
+                self.transfer = None;
+                None
+            }
+        }
+    }
+
+    fn handle_transfer_input(&mut self, key: KeyEvent) -> Option<ScreenAction> {
+        match self.transfer.as_mut() {
+            Some(Transfer::ExportPath(path)) | Some(Transfer::ImportPath(path)) => match key.code {
+                KeyCode::Esc => self.transfer = None,
+                KeyCode::Backspace => {
+                    path.pop();
+                }
+                KeyCode::Char(c) => path.push(c),
+                KeyCode::Enter => {
+                    let path = path.clone();
+                    if let Some(Transfer::ExportPath(_)) = self.transfer {
+                        self.export(&path);
+                    } else if Self::target(&path).1 == Format::Csv {
+                        return self.preview_csv(&path);
+                    } else {
+                        self.preview_import(&path);
+                    }
+                }
+                _ => {}
+            },
+            Some(Transfer::ConfirmImport(_)) => match key.code {
+                KeyCode::Char('m') | KeyCode::Char('M') => self.import(ImportMode::Merge),
+                KeyCode::Char('r') | KeyCode::Char('R') => self.import(ImportMode::Replace),
+                KeyCode::Esc | KeyCode::Char('n') => {
+                    self.transfer = None;
+                    self.status_message = Some(" Import cancelled.".to_string());
+                }
+                _ => {}
+            },
+            None => {}
         }
+        None
     }
 
     fn check_for_updates(&mut self) {
@@ -62,15 +199,15 @@ impl SettingsScreen {
     }
 
     fn poll_updates(&mut self) {
-        if let Some(rx) = &self.update_rx {
-            if let Ok(result) = rx.try_recv() {
-                self.update_rx = None; // clear the receiver
-                if let Some((latest, date, current)) = result {
-                    self.update_state = UpdateState::UpdateAvailable(latest, date, current);
-                } else {
-                    self.update_state = UpdateState::Idle;
-                    self.status_message = Some(format!(" ✓ Version {} is up to date.", VERSION));
-                }
+        if let Some(rx) = &self.update_rx
+            && let Ok(result) = rx.try_recv()
+        {
+            self.update_rx = None; // clear the receiver
+            if let Some((latest, date, current)) = result {
+                self.update_state = UpdateState::UpdateAvailable(latest, date, current);
+            } else {
+                self.update_state = UpdateState::Idle;
+                self.status_message = Some(format!(" ✓ Version {} is up to date.", VERSION));
             }
         }
     }
@@ -93,6 +230,10 @@ impl Screen for SettingsScreen {
             }
         }
 
+        if self.transfer.is_some() {
+            return self.handle_transfer_input(key);
+        }
+
         match key.code {
             KeyCode::Down => {
                 self.list.next();
@@ -109,15 +250,25 @@ impl Screen for SettingsScreen {
                         self.check_for_updates();
                         None
                     }
+                    "Export Tasks" => {
+                        let path = Format::Json.default_path().display().to_string();
+                        self.transfer = Some(Transfer::ExportPath(path));
+                        None
+                    }
+                    "Import Tasks" => {
+                        let path = Format::Json.default_path().display().to_string();
+                        self.transfer = Some(Transfer::ImportPath(path));
+                        None
+                    }
                     "Back" => {
-                        let menu = crate::screens::menu::MenuScreen::new();
+                        let menu = crate::screens::menu::MenuScreen::new(self.list_options);
                         Some(ScreenAction::Switch(Box::new(menu)))
                     }
                     _ => None,
                 }
             }
             KeyCode::Esc | KeyCode::Char('q') => {
-                let menu = crate::screens::menu::MenuScreen::new();
+                let menu = crate::screens::menu::MenuScreen::new(self.list_options);
                 Some(ScreenAction::Switch(Box::new(menu)))
             }
             _ => None,
@@ -161,6 +312,7 @@ impl Screen for SettingsScreen {
 
         // --- Status bar ---
         let (status_text, status_color) = match &self.update_state {
+            _ if self.transfer.is_some() => (" Waiting for input...".to_string(), Color::Yellow),
             UpdateState::Checking => (" Checking for updates...".to_string(), Color::Yellow),
             UpdateState::UpdateAvailable(_, _, _) => (" Waiting for input...".to_string(), Color::Yellow),
             UpdateState::Idle => {
@@ -225,9 +377,71 @@ impl Screen for SettingsScreen {
             frame.render_widget(Clear, popup_area); // This clears out the background
             frame.render_widget(popup, popup_area);
         }
+
+        if let Some(transfer) = &self.transfer {
+            render_transfer(frame, area, transfer);
+        }
     }
 }
 
+fn render_transfer(frame: &mut Frame, area: Rect, transfer: &Transfer) {
+    let hint = |text: &str| Line::from(Span::styled(text.to_string(), Style::default().fg(Color::DarkGray)));
+    let (title, lines) = match transfer {
+        Transfer::ExportPath(path) | Transfer::ImportPath(path) => {
+            let title = if let Transfer::ExportPath(_) = transfer { " Export Tasks " } else { " Import Tasks " };
+            let mut formats: Vec<String> = Vec::new();
+            for extension in Format::ALL.map(|f| format!(".{}", f.extension())) {
+                if !formats.contains(&extension) {
+                    formats.push(extension);
+                }
+            }
+            let formats = formats.join(", ");
+            (title, vec![
+                Line::from(""),
+                Line::from(Span::styled("File:", Style::default().fg(Color::DarkGray))),
+                Line::from(Span::styled(format!("{}_", path), Style::default().fg(Color::Yellow))),
+                Line::from(""),
+                hint(&format!("The format follows the extension ({}).", formats)),
+                Line::from(""),
+                hint("Enter → continue   Esc → cancel"),
+            ])
+        }
+        Transfer::ConfirmImport(preview) => {
+            let ImportPreview { path, merge, replace, .. } = preview.as_ref();
+            let mut lines = vec![
+                Line::from(""),
+                Line::from(vec![
+                    Span::raw("Importing "),
+                    Span::styled(path.display().to_string(), Style::default().fg(Color::Cyan)),
+                ]),
+                Line::from(""),
+                Line::from(Span::styled("[m] Merge: add new items and update matching ones", Style::default().fg(Color::Green).add_modifier(Modifier::BOLD))),
+            ];
+            lines.extend(merge.to_string().lines().map(|l| Line::from(format!("    {}", l))));
+            lines.push(Line::from(""));
+            lines.push(Line::from(Span::styled("[r] Replace: make the database an exact copy", Style::default().fg(Color::Red).add_modifier(Modifier::BOLD))));
+            lines.extend(replace.to_string().lines().map(|l| Line::from(format!("    {}", l))));
+            lines.push(Line::from(""));
+            lines.push(hint("Esc → cancel"));
+            (" Import Tasks ", lines)
+        }
+    };
+
+    let popup = Paragraph::new(lines)
+        .alignment(Alignment::Center)
+        .wrap(Wrap { trim: false })
+        .block(
+            Block::default()
+                .title(title)
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::Yellow))
+                .style(Style::default().bg(Color::Black)),
+        );
+    let popup_area = centered_rect(70, 60, area);
+    frame.render_widget(Clear, popup_area);
+    frame.render_widget(popup, popup_area);
+}
+
 /// Helper function to create a centered rect using up certain percentage of the available rect `r`
 fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
     let popup_layout = Layout::default()
@@ -1,6 +1,20 @@
-use crate::persistence::persistence::{Persistence, Task};
+use crate::persistence::error::PersistenceError;
+use crate::persistence::filter::ast::Expr;
+use crate::persistence::filter::error::FilterError;
+use crate::persistence::filter::lexer::quote;
+use crate::persistence::filter::parser::parse;
+use crate::persistence::history::Operation;
+use crate::persistence::persistence::{DueStatus, Persistence, Priority, Task};
+use crate::persistence::project::{Project, INBOX_ID};
+use crate::persistence::query::{Sort, Status, TaskQuery};
+use crate::persistence::recurrence::Recurrence;
+use crate::persistence::search::Search;
+use crate::persistence::tag::Tag;
+use crate::persistence::view::View;
 use crate::ui::screen::{Screen, ScreenAction};
-use crossterm::event::{KeyCode, KeyEvent};
+use chrono::{Local, NaiveDateTime};
+use std::collections::{HashMap, HashSet};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 use ratatui::layout::{Constraint, Direction, Layout, Rect};
 use ratatui::style::{Color, Modifier, Style};
 use ratatui::text::{Line, Span};
@@ -10,129 +24,818 @@ use ratatui::Frame;
 enum TasksMode {
     /// Normal view: navigate list, see details
     View,
-    /// Inline add form: typing title then description
-    Adding {
-        active_field: AddField,
-        title: String,
-        description: String,
-    },
+    /// Inline add form
+    Adding(TaskForm),
     /// Waiting for confirmation before deleting
     ConfirmDelete,
-    Updating {
-        active_field: AddField,
-        title: String,
-        description: String
-    }
+    /// Completing a task that still has open subtasks: complete them too?
+    ConfirmCompleteSubtasks(usize),
+    /// Inline edit form for the selected task
+    Updating(TaskForm),
+    /// Choosing which tags to filter the list by
+    PickingTags(TagPicker),
+    /// Typing a filter expression; the list is narrowed after every key
+    Searching,
+    /// Typing the name to save the list's filter, status and sort under
+    SavingView(String),
 }
 
-#[derive(PartialEq)]
-enum AddField {
+#[derive(PartialEq, Clone, Copy)]
+enum FormField {
     Title,
     Description,
+    Due,
+    Repeat,
+    Tags,
+    Project,
+}
+
+/// Field buffers shared by the add and update forms.
+struct TaskForm {
+    active_field: FormField,
+    title: String,
+    description: String,
+    due: String,
+    repeat: String,
+    /// Comma-separated tag names
+    tags: String,
+    project_id: i64,
+    parent_id: Option<i64>,
+}
+
+enum FormOutcome {
+    Editing,
+    Cancel,
+    Submit,
+}
+
+impl TaskForm {
+    fn new(project_id: i64, parent_id: Option<i64>) -> Self {
+        TaskForm {
+            active_field: FormField::Title,
+            title: String::new(),
+            description: String::new(),
+            due: String::new(),
+            repeat: String::new(),
+            tags: String::new(),
+            project_id,
+            parent_id,
+        }
+    }
+
+    fn from_task(task: &Task) -> Self {
+        TaskForm {
+            active_field: FormField::Title,
+            title: task.title.clone(),
+            description: task.description.clone().unwrap_or_default(),
+            due: task.due.as_ref().map(Task::format_due).unwrap_or_default(),
+            repeat: task.recurrence.as_ref().map(Recurrence::to_string).unwrap_or_default(),
+            tags: task.tags.join(", "),
+            project_id: task.project_id.unwrap_or(INBOX_ID),
+            parent_id: task.parent_id,
+        }
+    }
+
+    fn handle_key(&mut self, key: KeyEvent, projects: &[Project]) -> FormOutcome {
+        if self.active_field == FormField::Project {
+            let step: isize = match key.code {
+                KeyCode::Left => -1,
+                KeyCode::Right | KeyCode::Char(' ') => 1,
+                _ => 0,
+            };
+            if step != 0 {
+                self.cycle_project(projects, step);
+                return FormOutcome::Editing;
+            }
+        }
+
+        match key.code {
+            KeyCode::Esc => FormOutcome::Cancel,
+            KeyCode::Enter => FormOutcome::Submit,
+            KeyCode::Tab => {
+                self.active_field = match self.active_field {
+                    FormField::Title => FormField::Description,
+                    FormField::Description => FormField::Due,
+                    FormField::Due => FormField::Repeat,
+                    FormField::Repeat => FormField::Tags,
+                    FormField::Tags => FormField::Project,
+                    FormField::Project => FormField::Title,
+                };
+                FormOutcome::Editing
+            }
+            KeyCode::Backspace => {
+                if let Some(buffer) = self.active_buffer() {
+                    buffer.pop();
+                }
+                FormOutcome::Editing
+            }
+            KeyCode::Char(c) => {
+                if let Some(buffer) = self.active_buffer() {
+                    buffer.push(c);
+                }
+                FormOutcome::Editing
+            }
+            _ => FormOutcome::Editing,
+        }
+    }
+
+    /// Text buffer of the focused field; the project is picked with ←/→ instead.
+    fn active_buffer(&mut self) -> Option<&mut String> {
+        match self.active_field {
+            FormField::Title => Some(&mut self.title),
+            FormField::Description => Some(&mut self.description),
+            FormField::Due => Some(&mut self.due),
+            FormField::Repeat => Some(&mut self.repeat),
+            FormField::Tags => Some(&mut self.tags),
+            FormField::Project => None,
+        }
+    }
+
+    fn cycle_project(&mut self, projects: &[Project], step: isize) {
+        if projects.is_empty() { return; }
+        let current = projects
+            .iter()
+            .position(|p| p.id == Some(self.project_id))
+            .unwrap_or(0) as isize;
+        let next = (current + step).rem_euclid(projects.len() as isize) as usize;
+        self.project_id = projects[next].id.unwrap_or(INBOX_ID);
+    }
+
+    /// Applies the form onto `base`, validating the entered fields.
+    fn apply(&self, base: Task) -> Result<Task, String> {
+        let title = self.title.trim().to_string();
+        if title.is_empty() {
+            return Err("Title cannot be empty".to_string());
+        }
+        let description = self.description.trim().to_string();
+        let due = match self.due.trim() {
+            "" => None,
+            input => Some(Task::parse_due(input)?),
+        };
+        let recurrence = match self.repeat.trim() {
+            "" => None,
+            input => Some(input.parse::<Recurrence>()?),
+        };
+        Ok(Task {
+            title,
+            description: if description.is_empty() { None } else { Some(description) },
+            due,
+            recurrence,
+            tags: Tag::parse_list(&self.tags),
+            project_id: Some(self.project_id),
+            parent_id: self.parent_id,
+            ..base
+        })
+    }
+}
+
+/// Tag list shown while picking the tag filter.
+struct TagPicker {
+    tags: Vec<Tag>,
+    chosen: Vec<String>,
+    state: ListState,
+}
+
+impl TagPicker {
+    fn toggle_selected(&mut self) {
+        let Some(tag) = self.state.selected().and_then(|i| self.tags.get(i)) else { return };
+        if let Some(pos) = self.chosen.iter().position(|t| t.eq_ignore_ascii_case(&tag.name)) {
+            self.chosen.remove(pos);
+        } else {
+            self.chosen.push(tag.name.clone());
+        }
+    }
+
+    fn step(&mut self, forward: bool) {
+        if self.tags.is_empty() { return; }
+        let last = self.tags.len() - 1;
+        let i = match (self.state.selected(), forward) {
+            (Some(i), true) => if i >= last { 0 } else { i + 1 },
+            (Some(i), false) => if i == 0 { last } else { i - 1 },
+            (None, _) => 0,
+        };
+        self.state.select(Some(i));
+    }
+}
+
+const TAG_COLORS: [Color; 6] = [
+    Color::Cyan,
+    Color::Magenta,
+    Color::Green,
+    Color::Yellow,
+    Color::LightBlue,
+    Color::LightRed,
+];
+
+/// A coloured chip for `tag`; the colour is stable for a given name.
+fn tag_chip(tag: &str) -> Span<'static> {
+    let hash = tag.to_lowercase().bytes().fold(0usize, |acc, b| acc.wrapping_mul(31).wrapping_add(b as usize));
+    Span::styled(
+        format!(" {} ", tag),
+        Style::default().fg(Color::Black).bg(TAG_COLORS[hash % TAG_COLORS.len()]),
+    )
+}
+
+fn priority_marker(priority: Priority) -> Span<'static> {
+    match priority {
+        Priority::None => Span::raw("  "),
+        Priority::Low => Span::styled("↓ ", Style::default().fg(Color::Blue)),
+        Priority::Medium => Span::styled("= ", Style::default().fg(Color::Green)),
+        Priority::High => Span::styled("↑ ", Style::default().fg(Color::Yellow)),
+        Priority::Urgent => Span::styled("‼ ", Style::default().fg(Color::Red).add_modifier(Modifier::BOLD)),
+    }
+}
+
+fn due_color(status: &DueStatus) -> Color {
+    match status {
+        DueStatus::Overdue => Color::Red,
+        DueStatus::Today => Color::Yellow,
+        DueStatus::Upcoming => Color::Cyan,
+    }
+}
+
+/// One visible line of the task tree.
+struct TreeRow {
+    /// Index into `TasksScreen::tasks`
+    task: usize,
+    depth: usize,
+    /// Direct subtasks loaded for this task, and how many of them are done
+    children: usize,
+    done_children: usize,
+}
+
+/// Completion filter and order of the task list. Every screen passes them
+/// on to the next, so they last for the rest of the session: leaving a list
+/// and coming back (or opening another project) keeps them.
+#[derive(Debug, Clone, Copy)]
+pub struct ListOptions {
+    status: Status,
+    sort: Sort,
+}
+
+impl Default for ListOptions {
+    fn default() -> Self {
+        ListOptions { status: Status::All, sort: Sort::Priority }
+    }
 }
 
 // ── Screen ────────────────────────────────────────────────────────────────────
 
 pub struct TasksScreen {
     pub title: String,
+    /// Project whose tasks are listed, and where new tasks go
+    project_id: i64,
+    /// Saved view the list was opened from or last saved as; its tasks come
+    /// from every project
+    view: Option<View>,
+    /// All projects, for the form's project picker
+    projects: Vec<Project>,
+    /// Only tasks carrying every one of these tags are listed
+    tag_filter: Vec<String>,
+    /// Filter expression as typed
+    filter_text: String,
+    /// Only tasks matching this are listed: `filter_text` as of the last
+    /// time it parsed
+    filter: Option<Expr>,
+    /// Why `filter_text` doesn't parse, while it doesn't
+    filter_error: Option<FilterError>,
+    options: ListOptions,
+    /// The session's options, handed back to the menu; follows `options`
+    /// unless showing a view, which has its own
+    session: ListOptions,
     tasks: Vec<Task>,
+    /// Visible rows of the task tree; the list selection indexes into this
+    rows: Vec<TreeRow>,
+    /// Tasks whose subtasks are hidden
+    collapsed: HashSet<i64>,
     state: ListState,
     mode: TasksMode,
     persistence: Persistence,
+    /// Last error (persistence failure or invalid input), shown in the
+    /// footer until the next key press
+    error: Option<String>,
+    /// What was just undone or redone, shown like `error`
+    notice: Option<String>,
 }
 
 impl TasksScreen {
-    pub fn new() -> Self {
-        let persistence = Persistence::new();
-        persistence.sync_schema();
-        let tasks = persistence.get_all::<Task>();
+    pub fn new(session: ListOptions) -> Self {
+        Self::for_project(INBOX_ID, session)
+    }
 
-        let mut state = ListState::default();
-        if !tasks.is_empty() {
-            state.select(Some(0));
-        }
+    pub fn for_project(project_id: i64, session: ListOptions) -> Self {
+        Self::open(project_id, None, session)
+    }
+
+    /// The tasks `view` picks, with its filter, status and sort.
+    pub fn for_view(view: View, session: ListOptions) -> Self {
+        Self::open(INBOX_ID, Some(view), session)
+    }
 
-        TasksScreen {
+    fn open(project_id: i64, view: Option<View>, session: ListOptions) -> Self {
+        let options = match &view {
+            Some(view) => ListOptions { status: view.status, sort: view.sort },
+            None => session,
+        };
+        let filter_text = view.as_ref().map(|v| v.filter.clone()).unwrap_or_default();
+        let mut screen = TasksScreen {
             title: "Tasks".to_string(),
-            tasks,
-            state,
+            project_id,
+            view,
+            projects: Vec::new(),
+            tag_filter: Vec::new(),
+            filter_text,
+            filter: None,
+            filter_error: None,
+            options,
+            session,
+            tasks: Vec::new(),
+            rows: Vec::new(),
+            collapsed: HashSet::new(),
+            state: ListState::default(),
             mode: TasksMode::View,
-            persistence,
+            persistence: Persistence { connection: None },
+            error: None,
+            notice: None,
+        };
+
+        match parse(&screen.filter_text) {
+            Ok(filter) => screen.filter = filter,
+            Err(e) => {
+                screen.error = Some(format!("The view's filter is invalid: {}", e));
+                screen.filter_error = Some(e);
+            }
+        }
+
+        // If the database can't be opened the screen still comes up, empty,
+        // with the reason in the footer instead of taking the terminal down.
+        let opened = Persistence::new().and_then(|p| p.sync_schema().map(|_| p));
+        if let Some(persistence) = screen.check(opened) {
+            screen.persistence = persistence;
+            screen.reload();
+        }
+        screen
+    }
+
+    /// Records a failed persistence call for the footer, passing successes through.
+    fn check<T>(&mut self, result: Result<T, PersistenceError>) -> Option<T> {
+        match result {
+            Ok(value) => Some(value),
+            Err(e) => {
+                self.error = Some(e.to_string());
+                None
+            }
         }
     }
 
     fn reload(&mut self) {
-        self.tasks = self.persistence.get_all::<Task>();
+        let projects = self.persistence.get_all::<Project>();
+        if let Some(projects) = self.check(projects) {
+            self.projects = projects;
+        }
+        if let Some(view) = &self.view {
+            self.title = view.name.clone();
+        } else if let Some(project) = self.projects.iter().find(|p| p.id == Some(self.project_id)) {
+            self.title = project.name.clone();
+        }
+
+        let query = TaskQuery {
+            project_id: if self.view.is_some() { None } else { Some(self.project_id) },
+            status: self.options.status,
+            tags: self.tag_filter.clone(),
+            filter: self.filter.clone(),
+            sort: self.options.sort,
+        };
+        let loaded = self.persistence.tasks(&query);
+        let Some(tasks) = self.check(loaded) else { return };
+        self.tasks = tasks;
+        self.rebuild_rows();
+    }
+
+    /// Applies changed list options, keeping them for the session (views
+    /// have their own) and the selected task selected.
+    fn set_options(&mut self, options: ListOptions) {
+        self.options = options;
+        if self.view.is_none() {
+            self.session = options;
+        }
+        let id = self.selected_task().and_then(|t| t.id);
+        self.reload();
+        self.select_id(id);
+    }
+
+    fn is_filtered(&self) -> bool {
+        self.options.status != Status::All || !self.tag_filter.is_empty() || self.filter.is_some()
+    }
+
+    /// Parses the filter being typed and lists what it matches. While it
+    /// doesn't parse the list stays as it was.
+    fn apply_filter(&mut self) {
+        match parse(&self.filter_text) {
+            Ok(filter) => {
+                self.filter = filter;
+                self.filter_error = None;
+                self.reload();
+                self.state.select(if self.rows.is_empty() { None } else { Some(0) });
+            }
+            Err(e) => self.filter_error = Some(e),
+        }
+    }
+
+    /// Saves what the list shows as the view `name`, replacing the view of
+    /// that name if there is one, and shows the list as that view from now on.
+    fn save_view(&mut self, name: &str) {
+        if self.filter_error.is_some() {
+            self.error = Some("Fix the filter before saving the view".to_string());
+            return;
+        }
+        let loaded = self.persistence.get_all::<View>();
+        let Some(views) = self.check(loaded) else { return };
+        let existing = views.into_iter().find(|v| v.name.eq_ignore_ascii_case(name));
+
+        // views span every project, so the project and tags become part of
+        // the filter
+        let mut terms = Vec::new();
+        if self.view.is_none()
+            && let Some(project) = self.projects.iter().find(|p| p.id == Some(self.project_id))
+        {
+            terms.push(format!("project:{}", quote(&project.name)));
+        }
+        terms.extend(self.tag_filter.iter().map(|tag| format!("tag:{}", quote(tag))));
+        let filter = match (self.filter_text.trim(), terms.is_empty()) {
+            (text, true) => text.to_string(),
+            ("", false) => terms.join(" "),
+            (text, false) => format!("({}) {}", text, terms.join(" ")),
+        };
+
+        let mut view = View {
+            id: existing.and_then(|v| v.id),
+            name: name.to_string(),
+            filter,
+            status: self.options.status,
+            sort: self.options.sort,
+        };
+        let result = match view.id {
+            Some(_) => self.persistence.update(&view).map(|_| view.id),
+            None => self.persistence.save(&view),
+        };
+        let Some(id) = self.check(result) else { return };
+        view.id = id;
+
+        self.filter_text = view.filter.clone();
+        self.filter = parse(&self.filter_text).ok().flatten();
+        self.tag_filter.clear();
+        self.view = Some(view);
+        let selected = self.selected_task().and_then(|t| t.id);
+        self.reload();
+        self.select_id(selected);
+    }
+
+    fn clear_filter(&mut self) {
+        self.filter_text.clear();
+        self.filter = None;
+        self.filter_error = None;
+        self.reload();
+    }
+
+    /// Lays the loaded tasks out as a tree: subtasks directly below their
+    /// parent, in list order. Tasks whose parent isn't loaded (filtered out
+    /// or in another project) are shown at the top level.
+    fn rebuild_rows(&mut self) {
+        let loaded: HashSet<i64> = self.tasks.iter().filter_map(|t| t.id).collect();
+        let mut children: HashMap<Option<i64>, Vec<usize>> = HashMap::new();
+        for (i, task) in self.tasks.iter().enumerate() {
+            let parent = task.parent_id.filter(|p| loaded.contains(p));
+            children.entry(parent).or_default().push(i);
+        }
+
+        let mut rows = Vec::new();
+        let mut stack: Vec<(usize, usize)> = children
+            .get(&None)
+            .map(|roots| roots.iter().rev().map(|&i| (i, 0)).collect())
+            .unwrap_or_default();
+        while let Some((i, depth)) = stack.pop() {
+            let id = self.tasks[i].id;
+            let kids = children.get(&id).map(Vec::as_slice).unwrap_or_default();
+            rows.push(TreeRow {
+                task: i,
+                depth,
+                children: kids.len(),
+                done_children: kids.iter().filter(|&&k| self.tasks[k].completed).count(),
+            });
+            if !id.is_some_and(|id| self.collapsed.contains(&id)) {
+                stack.extend(kids.iter().rev().map(|&k| (k, depth + 1)));
+            }
+        }
+        self.rows = rows;
+
         // keep selection in bounds
-        if self.tasks.is_empty() {
+        if self.rows.is_empty() {
             self.state.select(None);
         } else {
-            let i = self.state.selected().unwrap_or(0).min(self.tasks.len() - 1);
+            let i = self.state.selected().unwrap_or(0).min(self.rows.len() - 1);
             self.state.select(Some(i));
         }
     }
 
+    /// Moves the selection to the task with `id`, e.g. after a re-sort.
+    fn select_id(&mut self, id: Option<i64>) {
+        if let Some(i) = self.rows.iter().position(|r| id.is_some() && self.tasks[r.task].id == id) {
+            self.state.select(Some(i));
+        }
+    }
+
+    fn selected_row(&self) -> Option<&TreeRow> {
+        self.state.selected().and_then(|i| self.rows.get(i))
+    }
+
     fn selected_task(&self) -> Option<&Task> {
-        self.state.selected().and_then(|i| self.tasks.get(i))
+        self.selected_row().map(|r| &self.tasks[r.task])
+    }
+
+    /// Open subtasks (at any depth) of the task at `index` among those loaded.
+    fn open_descendants(&self, index: usize) -> usize {
+        let mut open = 0;
+        let mut pending = vec![self.tasks[index].id];
+        while let Some(parent) = pending.pop() {
+            for task in self.tasks.iter().filter(|t| parent.is_some() && t.parent_id == parent) {
+                if !task.completed {
+                    open += 1;
+                }
+                pending.push(task.id);
+            }
+        }
+        open
+    }
+
+    fn set_collapsed(&mut self, collapsed: bool) {
+        let Some(row) = self.selected_row() else { return };
+        let (id, has_children) = (self.tasks[row.task].id, row.children > 0);
+        let Some(id) = id.filter(|_| has_children) else { return };
+        if collapsed {
+            self.collapsed.insert(id);
+        } else {
+            self.collapsed.remove(&id);
+        }
+        self.rebuild_rows();
+        self.select_id(Some(id));
     }
 
     fn list_next(&mut self) {
-        if self.tasks.is_empty() { return; }
+        if self.rows.is_empty() { return; }
         let i = match self.state.selected() {
-            Some(i) => if i >= self.tasks.len() - 1 { 0 } else { i + 1 },
+            Some(i) => if i >= self.rows.len() - 1 { 0 } else { i + 1 },
             None => 0,
         };
         self.state.select(Some(i));
     }
 
     fn list_prev(&mut self) {
-        if self.tasks.is_empty() { return; }
+        if self.rows.is_empty() { return; }
         let i = match self.state.selected() {
-            Some(i) => if i == 0 { self.tasks.len() - 1 } else { i - 1 },
+            Some(i) => if i == 0 { self.rows.len() - 1 } else { i - 1 },
             None => 0,
         };
         self.state.select(Some(i));
     }
+
+    /// Toggles completion of the selected task, asking first whether open
+    /// subtasks should be completed along with it.
+    fn toggle_selected(&mut self) {
+        let Some(row) = self.selected_row() else { return };
+        let task = &self.tasks[row.task];
+        let open = if task.completed { 0 } else { self.open_descendants(row.task) };
+        if open > 0 {
+            self.mode = TasksMode::ConfirmCompleteSubtasks(open);
+            return;
+        }
+        let (task, completed) = (task.clone(), !task.completed);
+        self.set_completed(task, completed, false);
+    }
+
+    /// Saves the completion state (of the open subtasks too if `subtasks`),
+    /// selecting the next occurrence if one was generated for a recurring
+    /// task.
+    fn set_completed(&mut self, task: Task, completed: bool, subtasks: bool) {
+        let result = self.persistence.set_completed_logged(&task, completed, subtasks);
+        let next = self.check(result).flatten();
+        self.reload();
+        self.select_id(next.or(task.id));
+    }
+
+    /// Undoes the last change, or redoes the last one undone, selecting the
+    /// task it was about.
+    fn undo(&mut self, redo: bool) {
+        let result = if redo { self.persistence.redo() } else { self.persistence.undo() };
+        let Some(operation) = self.check(result) else { return };
+        let Some(Operation { label, before, after, .. }) = operation else {
+            self.notice = Some(format!("Nothing to {}", if redo { "redo" } else { "undo" }));
+            return;
+        };
+        let shown = if redo { &after } else { &before };
+        self.notice = Some(format!("{} {}", if redo { "Redone:" } else { "Undone:" }, label));
+        self.reload();
+        self.select_id(shown.0.first().and_then(|t| t.id));
+    }
+
+    /// Earlier, completed occurrences of a recurring task among those loaded,
+    /// most recent first.
+    fn history(&self, task: &Task) -> Vec<&Task> {
+        let mut history = Vec::new();
+        let mut previous = task.occurrence_of;
+        while let Some(t) = previous.and_then(|id| self.tasks.iter().find(|t| t.id == Some(id))) {
+            history.push(t);
+            previous = t.occurrence_of;
+        }
+        history
+    }
+
+    fn submit_add(&mut self) {
+        let TasksMode::Adding(form) = &self.mode else { return };
+        match form.apply(Task::default()) {
+            Ok(task) => {
+                let result = self.persistence.add_logged(&task);
+                let id = self.check(result).flatten();
+                self.reload();
+                self.select_id(id);
+                self.mode = TasksMode::View;
+            }
+            Err(msg) => self.error = Some(msg),
+        }
+    }
+
+    fn submit_update(&mut self) {
+        let TasksMode::Updating(form) = &self.mode else { return };
+        let Some(base) = self.selected_task().cloned() else {
+            self.mode = TasksMode::View;
+            return;
+        };
+        match form.apply(base) {
+            Ok(task) => {
+                self.update_selected(task, "edit");
+                self.mode = TasksMode::View;
+            }
+            Err(msg) => self.error = Some(msg),
+        }
+    }
+
+    fn complete_selected(&mut self, subtasks: bool) {
+        self.mode = TasksMode::View;
+        if let Some(task) = self.selected_task().cloned() {
+            self.set_completed(task, true, subtasks);
+        }
+    }
+
+    /// Saves `task`, logged as `action` (e.g. "edit") for undo, and keeps it
+    /// selected wherever the new ordering puts it.
+    fn update_selected(&mut self, task: Task, action: &str) {
+        let result = self.persistence.update_logged(&task, action);
+        self.check(result);
+        self.reload();
+        self.select_id(task.id);
+    }
+
+    /// The filter being typed, with the part that doesn't parse underlined
+    /// and the reason after it, or `hint` while it parses.
+    fn filter_prompt(&self, hint: String) -> Line<'static> {
+        let mut spans = vec![Span::styled("  Filter: ", Style::default().fg(Color::DarkGray))];
+        let chars: Vec<char> = self.filter_text.chars().collect();
+        let text = |range: std::ops::Range<usize>| chars[range.start.min(chars.len())..range.end.min(chars.len())].iter().collect::<String>();
+        match &self.filter_error {
+            Some(e) => {
+                let (start, end) = (e.span.start, e.span.end.max(e.span.start + 1));
+                spans.push(Span::raw(text(0..start)));
+                // a missing token is pointed at past the end of the input
+                let marked = if start >= chars.len() { " ".to_string() } else { text(start..end) };
+                spans.push(Span::styled(
+                    marked,
+                    Style::default().fg(Color::Red).add_modifier(Modifier::UNDERLINED | Modifier::BOLD),
+                ));
+                spans.push(Span::raw(text(end..chars.len())));
+                spans.push(Span::raw("▏"));
+                spans.push(Span::styled(format!("   ✗ {}", e.message), Style::default().fg(Color::Red)));
+            }
+            None => {
+                spans.push(Span::raw(format!("{}▏", self.filter_text)));
+                spans.push(Span::styled(hint, Style::default().fg(Color::Yellow)));
+            }
+        }
+        Line::from(spans)
+    }
+
+    /// One line summing up the filters and order in effect, with the keys
+    /// that change them.
+    fn filter_bar(&self) -> Line<'static> {
+        let key = |k: &str| Span::styled(format!(" {} ", k), Style::default().fg(Color::Black).bg(Color::DarkGray));
+        let label = |text: &str| Span::styled(format!("  {} ", text), Style::default().fg(Color::DarkGray));
+        let active = Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD);
+
+        let mut spans = vec![key("f"), label("Show:")];
+        for status in Status::ALL {
+            let style = if status == self.options.status { active } else { Style::default().fg(Color::DarkGray) };
+            spans.push(Span::styled(format!("{} ", status.label()), style));
+        }
+        spans.extend([Span::raw("   "), key("o"), label("Sort:"), Span::styled(self.options.sort.label(), active)]);
+
+        spans.extend([Span::raw("   "), key("/"), label("Filter:")]);
+        spans.push(match self.filter_text.trim() {
+            "" => Span::styled("—", Style::default().fg(Color::DarkGray)),
+            text => Span::styled(text.to_string(), active),
+        });
+        spans.extend([Span::raw("   "), key("t"), label("Tags:")]);
+        spans.push(if self.tag_filter.is_empty() {
+            Span::styled("—", Style::default().fg(Color::DarkGray))
+        } else {
+            Span::styled(self.tag_filter.join(", "), active)
+        });
+        Line::from(spans)
+    }
 }
 
 // ── Input handling ────────────────────────────────────────────────────────────
 
 impl Screen for TasksScreen {
     fn handle_input(&mut self, key: KeyEvent) -> Option<ScreenAction> {
+        self.error = None;
+        self.notice = None;
         match &mut self.mode {
             // ── View mode ─────────────────────────────────────────────
             TasksMode::View => match key.code {
                 KeyCode::Up => { self.list_prev(); None }
                 KeyCode::Down => { self.list_next(); None }
                 KeyCode::Char(' ') | KeyCode::Enter => {
+                    self.toggle_selected();
+                    None
+                }
+                KeyCode::Left => { self.set_collapsed(true); None }
+                KeyCode::Right => { self.set_collapsed(false); None }
+                KeyCode::Char('+') | KeyCode::Char('=') => {
                     if let Some(task) = self.selected_task() {
                         let updated_task = Task {
-                            id: task.id,
-                            title: task.title.clone(),
-                            description: task.description.clone(),
-                            completed: !task.completed,
+                            priority: task.priority.raised(),
+                            ..task.clone()
                         };
-                        self.persistence.update(&updated_task);
-                        self.reload();
+                        self.update_selected(updated_task, "raise the priority of");
                     }
                     None
                 }
-                KeyCode::Char('u') => {
+                KeyCode::Char('-') => {
                     if let Some(task) = self.selected_task() {
-                        self.mode = TasksMode::Updating {
-                            active_field: AddField::Title,
-                            title: task.title.clone(),
-                            description: task.description.clone().unwrap_or_else(String::new),
+                        let updated_task = Task {
+                            priority: task.priority.lowered(),
+                            ..task.clone()
                         };
+                        self.update_selected(updated_task, "lower the priority of");
                     }
                     None
                 }
+                KeyCode::Char('e') => {
+                    if let Some(task) = self.selected_task() {
+                        self.mode = TasksMode::Updating(TaskForm::from_task(task));
+                    }
+                    None
+                }
+                KeyCode::Char('u') => {
+                    self.undo(false);
+                    None
+                }
+                KeyCode::Char('r') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    self.undo(true);
+                    None
+                }
+                KeyCode::Char('U') => {
+                    self.undo(true);
+                    None
+                }
                 KeyCode::Char('a') => {
-                    self.mode = TasksMode::Adding {
-                        active_field: AddField::Title,
-                        title: String::new(),
-                        description: String::new(),
-                    };
+                    self.mode = TasksMode::Adding(TaskForm::new(self.project_id, None));
+                    None
+                }
+                KeyCode::Char('s') => {
+                    if let Some(task) = self.selected_task() {
+                        let parent_id = task.id;
+                        // make sure the new subtask will be visible
+                        if let Some(id) = parent_id {
+                            self.collapsed.remove(&id);
+                        }
+                        self.mode = TasksMode::Adding(TaskForm::new(self.project_id, parent_id));
+                    }
+                    None
+                }
+                KeyCode::Char('t') => {
+                    let tags = self.persistence.get_all::<Tag>();
+                    if let Some(mut tags) = self.check(tags) {
+                        tags.retain(|t| t.task_count > 0);
+                        let mut state = ListState::default();
+                        if !tags.is_empty() {
+                            state.select(Some(0));
+                        }
+                        self.mode = TasksMode::PickingTags(TagPicker {
+                            tags,
+                            chosen: self.tag_filter.clone(),
+                            state,
+                        });
+                    }
                     None
                 }
                 KeyCode::Char('d') | KeyCode::Delete => {
@@ -141,128 +844,154 @@ impl Screen for TasksScreen {
                     }
                     None
                 }
+                KeyCode::Char('/') => {
+                    self.mode = TasksMode::Searching;
+                    None
+                }
+                KeyCode::Char('f') => {
+                    self.set_options(ListOptions { status: self.options.status.next(), ..self.options });
+                    None
+                }
+                KeyCode::Char('o') => {
+                    self.set_options(ListOptions { sort: self.options.sort.next(), ..self.options });
+                    None
+                }
+                KeyCode::Char('v') => {
+                    let name = self.view.as_ref().map(|v| v.name.clone()).unwrap_or_default();
+                    self.mode = TasksMode::SavingView(name);
+                    None
+                }
+                // the first Esc drops the filter, the next one leaves
+                KeyCode::Esc if !self.filter_text.is_empty() => {
+                    self.clear_filter();
+                    None
+                }
                 KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('b') => {
-                    let menu = crate::screens::menu::MenuScreen::new();
+                    let menu = crate::screens::menu::MenuScreen::new(self.session);
                     Some(ScreenAction::Switch(Box::new(menu)))
                 }
                 _ => None,
             },
 
             // ── Add mode ──────────────────────────────────────────────
-            TasksMode::Adding { active_field, title, description } => match key.code {
-                KeyCode::Esc => {
+            TasksMode::Adding(form) => {
+                match form.handle_key(key, &self.projects) {
+                    FormOutcome::Editing => {}
+                    FormOutcome::Cancel => self.mode = TasksMode::View,
+                    FormOutcome::Submit => self.submit_add(),
+                }
+                None
+            }
+            // ── Confirm delete mode ───────────────────────────────────
+            TasksMode::ConfirmDelete => match key.code {
+                KeyCode::Enter => {
+                    if let Some(task) = self.selected_task().cloned() {
+                        let result = self.persistence.delete_logged(&task);
+                        self.check(result);
+                    }
+                    self.reload();
                     self.mode = TasksMode::View;
                     None
                 }
-                KeyCode::Tab => {
-                    *active_field = if *active_field == AddField::Title {
-                        AddField::Description
-                    } else {
-                        AddField::Title
-                    };
+                KeyCode::Esc | KeyCode::Char('n') => {
+                    self.mode = TasksMode::View;
                     None
                 }
-                KeyCode::Enter => {
-                    if !title.trim().is_empty() {
-                        let t = title.trim().to_string();
-                        let d = description.trim().to_string();
-                        let task = Task {
-                            id: None,
-                            title: t,
-                            description: if d.is_empty() { None } else { Some(d) },
-                            completed: false,
-                        };
-                        self.persistence.save(&task);
-                        self.reload();
-                        if !self.tasks.is_empty() {
-                            self.state.select(Some(self.tasks.len() - 1));
-                        }
-                    }
-                    self.mode = TasksMode::View;
+                _ => None,
+            },
+            // ── Complete subtasks prompt ──────────────────────────────
+            TasksMode::ConfirmCompleteSubtasks(_) => match key.code {
+                KeyCode::Char('y') | KeyCode::Char('Y') | KeyCode::Enter => {
+                    self.complete_selected(true);
                     None
                 }
-                KeyCode::Backspace => {
-                    match active_field {
-                        AddField::Title => { title.pop(); }
-                        AddField::Description => { description.pop(); }
-                    }
+                KeyCode::Char('n') | KeyCode::Char('N') => {
+                    self.complete_selected(false);
                     None
                 }
-                KeyCode::Char(c) => {
-                    match active_field {
-                        AddField::Title => title.push(c),
-                        AddField::Description => description.push(c),
-                    }
+                KeyCode::Esc => {
+                    self.mode = TasksMode::View;
                     None
                 }
                 _ => None,
             },
-            // ── Confirm delete mode ───────────────────────────────────
-            TasksMode::ConfirmDelete => match key.code {
+            // ── Tag filter picker ─────────────────────────────────────
+            TasksMode::PickingTags(picker) => match key.code {
+                KeyCode::Up => { picker.step(false); None }
+                KeyCode::Down => { picker.step(true); None }
+                KeyCode::Char(' ') => { picker.toggle_selected(); None }
+                KeyCode::Char('c') => { picker.chosen.clear(); None }
                 KeyCode::Enter => {
-                    if let Some(task) = self.selected_task() {
-                        if let Some(id) = task.id {
-                            self.persistence.delete::<Task>(id);
-                        }
-                    }
-                    self.reload();
+                    self.tag_filter = std::mem::take(&mut picker.chosen);
                     self.mode = TasksMode::View;
+                    self.reload();
                     None
                 }
-                KeyCode::Esc | KeyCode::Char('n') => {
+                KeyCode::Esc => {
                     self.mode = TasksMode::View;
                     None
                 }
                 _ => None,
             },
-            TasksMode::Updating { active_field, title, description } => match key.code {
-                KeyCode::Esc => {
+            // ── Search mode ───────────────────────────────────────────
+            TasksMode::Searching => match key.code {
+                KeyCode::Up => { self.list_prev(); None }
+                KeyCode::Down => { self.list_next(); None }
+                KeyCode::Char(c) => {
+                    self.filter_text.push(c);
+                    self.apply_filter();
+                    None
+                }
+                KeyCode::Backspace => {
+                    self.filter_text.pop();
+                    self.apply_filter();
+                    None
+                }
+                // a filter that doesn't parse has to be fixed or cleared
+                KeyCode::Enter if self.filter_error.is_none() => {
                     self.mode = TasksMode::View;
                     None
                 }
-                KeyCode::Tab => {
-                    *active_field = if *active_field == AddField::Title {
-                        AddField::Description
-                    } else {
-                        AddField::Title
-                    };
+                KeyCode::Esc => {
+                    self.clear_filter();
+                    self.mode = TasksMode::View;
                     None
                 }
+                _ => None,
+            },
+            // ── Save view prompt ──────────────────────────────────────
+            TasksMode::SavingView(name) => match key.code {
                 KeyCode::Enter => {
-                    if !title.trim().is_empty() {
-                        let t = title.trim().to_string();
-                        let d = description.trim().to_string();
-                        let task = Task {
-                            id: self.selected_task().and_then(|t| t.id),
-                            title: t,
-                            description: if d.is_empty() { None } else { Some(d) },
-                            completed: self.selected_task().map(|t| t.completed).unwrap_or(false),
-                        };
-                        self.persistence.update(&task);
-                        self.reload();
-                        if !self.tasks.is_empty() {
-                            self.state.select(Some(self.tasks.len() - 1));
-                        }
+                    let name = name.trim().to_string();
+                    if !name.is_empty() {
+                        self.save_view(&name);
                     }
                     self.mode = TasksMode::View;
                     None
                 }
+                KeyCode::Esc => {
+                    self.mode = TasksMode::View;
+                    None
+                }
                 KeyCode::Backspace => {
-                    match active_field {
-                        AddField::Title => { title.pop(); }
-                        AddField::Description => { description.pop(); }
-                    }
+                    name.pop();
                     None
                 }
                 KeyCode::Char(c) => {
-                    match active_field {
-                        AddField::Title => title.push(c),
-                        AddField::Description => description.push(c),
-                    }
+                    name.push(c);
                     None
                 }
                 _ => None,
             },
+            // ── Update mode ───────────────────────────────────────────
+            TasksMode::Updating(form) => {
+                match form.handle_key(key, &self.projects) {
+                    FormOutcome::Editing => {}
+                    FormOutcome::Cancel => self.mode = TasksMode::View,
+                    FormOutcome::Submit => self.submit_update(),
+                }
+                None
+            }
         }
     }
 
@@ -271,33 +1000,71 @@ impl Screen for TasksScreen {
     fn render(&mut self, frame: &mut Frame, area: Rect) {
         let vertical = Layout::default()
             .direction(Direction::Vertical)
-            .constraints([Constraint::Min(3), Constraint::Length(3)])
+            .constraints([Constraint::Length(1), Constraint::Min(3), Constraint::Length(3)])
             .split(area);
 
         let horizontal = Layout::default()
             .direction(Direction::Horizontal)
             .constraints([Constraint::Percentage(42), Constraint::Percentage(58)])
-            .split(vertical[0]);
+            .split(vertical[1]);
+
+        let now = Local::now().naive_local();
+        let search = self.filter.as_ref().map(Expr::search).unwrap_or_default();
+
+        // ── Top: filter bar ───────────────────────────────────────────
+        frame.render_widget(Paragraph::new(self.filter_bar()), vertical[0]);
 
         // ── Left: task list ───────────────────────────────────────────
         let items: Vec<ListItem> = if self.tasks.is_empty() {
-            vec![ListItem::new(Span::styled(
-                "  (no tasks — press 'a' to add one)",
-                Style::default().fg(Color::DarkGray),
-            ))]
+            let hint = if self.is_filtered() {
+                "  (no tasks match the filters)"
+            } else {
+                "  (no tasks — press 'a' to add one)"
+            };
+            vec![ListItem::new(Span::styled(hint, Style::default().fg(Color::DarkGray)))]
         } else {
-            self.tasks
+            self.rows
                 .iter()
-                .map(|t| {
+                .map(|row| {
+                    let t = &self.tasks[row.task];
+                    let due_status = t.due_status(now);
                     let (icon, style) = if t.completed {
                         ("✓", Style::default().fg(Color::DarkGray))
+                    } else if let Some(status) = &due_status {
+                        ("○", Style::default().fg(due_color(status)))
                     } else {
                         ("○", Style::default().fg(Color::White))
                     };
-                    ListItem::new(Line::from(vec![
-                        Span::styled(format!("  {} ", icon), style),
-                        Span::styled(t.title.clone(), style),
-                    ]))
+                    let fold = match (row.children, t.id.is_some_and(|id| self.collapsed.contains(&id))) {
+                        (0, _) => " ",
+                        (_, true) => "▸",
+                        (_, false) => "▾",
+                    };
+                    let mut spans = vec![
+                        Span::styled(format!("{}{} {} ", "  ".repeat(row.depth + 1), fold, icon), style),
+                        priority_marker(t.priority),
+                    ];
+                    spans.extend(highlighted(&t.title, &search, style));
+                    if t.recurrence.is_some() && !t.completed {
+                        spans.push(Span::styled(" ↻", Style::default().fg(Color::Magenta)));
+                    }
+                    if row.children > 0 {
+                        spans.push(Span::styled(
+                            format!("  {}/{} done", row.done_children, row.children),
+                            Style::default().fg(Color::DarkGray),
+                        ));
+                    }
+                    for tag in &t.tags {
+                        spans.push(Span::raw(" "));
+                        spans.push(tag_chip(tag));
+                    }
+                    if let (Some(due), Some(_)) = (&t.due, &due_status) {
+                        spans.push(Span::styled(
+                            format!("  {}", Task::format_due(due)),
+                            style.add_modifier(Modifier::DIM),
+                        ));
+                    }
+                    ListItem::new(Line::from(spans))
                 })
                 .collect()
         };
@@ -322,30 +1089,13 @@ impl Screen for TasksScreen {
 
         // ── Right: context panel (details / add form) ─────────────────
         match &self.mode {
-            TasksMode::View | TasksMode::ConfirmDelete => {
+            TasksMode::View
+            | TasksMode::ConfirmDelete
+            | TasksMode::ConfirmCompleteSubtasks(_)
+            | TasksMode::Searching
+            | TasksMode::SavingView(_) => {
                 let detail_lines = if let Some(task) = self.selected_task() {
-                    let status_str = if task.completed { "✓  Completed" } else { "○  Pending" };
-                    let status_color = if task.completed { Color::Green } else { Color::Magenta };
-                    let desc = task.description.as_deref().unwrap_or("No description.");
-                    vec![
-                        Line::from(vec![
-                            Span::styled("  ID:     ", Style::default().fg(Color::DarkGray)),
-                            Span::raw(task.id.unwrap_or(0).to_string()),
-                        ]),
-                        Line::from(""),
-                        Line::from(vec![
-                            Span::styled("  Title:  ", Style::default().fg(Color::DarkGray)),
-                            Span::styled(task.title.clone(), Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)),
-                        ]),
-                        Line::from(""),
-                        Line::from(vec![
-                            Span::styled("  Status: ", Style::default().fg(Color::DarkGray)),
-                            Span::styled(status_str, Style::default().fg(status_color)),
-                        ]),
-                        Line::from(""),
-                        Line::from(Span::styled("  Description:", Style::default().fg(Color::DarkGray))),
-                        Line::from(Span::raw(format!("  {}", desc))),
-                    ]
+                    task_details(task, &self.history(task), &search, now)
                 } else {
                     vec![Line::from(Span::styled(
                         "  Select a task to see details.",
@@ -365,134 +1115,287 @@ impl Screen for TasksScreen {
                 frame.render_widget(detail, horizontal[1]);
             }
 
-            TasksMode::Adding { active_field, title, description } => {
-                let title_style = if *active_field == AddField::Title {
-                    Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)
-                } else {
-                    Style::default().fg(Color::White)
-                };
-                let desc_style = if *active_field == AddField::Description {
-                    Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)
-                } else {
-                    Style::default().fg(Color::White)
-                };
-
-                let form_lines = vec![
-                    Line::from(""),
-                    Line::from(Span::styled(
-                        "  Title",
-                        Style::default().fg(Color::DarkGray),
-                    )),
-                    Line::from(vec![
-                        Span::styled("  > ", Style::default().fg(Color::Cyan)),
-                        Span::styled(format!("{}_", title), title_style),
-                    ]),
-                    Line::from(""),
-                    Line::from(Span::styled(
-                        "  Description  (optional)",
-                        Style::default().fg(Color::DarkGray),
-                    )),
-                    Line::from(vec![
-                        Span::styled("  > ", Style::default().fg(Color::Cyan)),
-                        Span::styled(format!("{}_", description), desc_style),
-                    ]),
-                    Line::from(""),
-                    Line::from(""),
-                    Line::from(Span::styled(
-                        "  Tab → next field   Enter → save   Esc → cancel",
-                        Style::default().fg(Color::DarkGray),
-                    )),
-                ];
-
-                let form = Paragraph::new(form_lines)
-                    .wrap(Wrap { trim: false })
-                    .block(
-                        Block::default()
-                            .title(" Add Task ")
-                            .borders(Borders::ALL)
-                            .border_style(Style::default().fg(Color::Yellow))
-                            .style(Style::default().bg(Color::Black)),
-                    );
-                frame.render_widget(form, horizontal[1]);
-            }
-
-            TasksMode::Updating { active_field, title, description } => {
-                let title_style = if *active_field == AddField::Title {
-                    Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)
-                } else {
-                    Style::default().fg(Color::White)
-                };
-                let desc_style = if *active_field == AddField::Description {
-                    Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)
-                } else {
-                    Style::default().fg(Color::White)
-                };
-
-                let form_lines = vec![
-                    Line::from(""),
-                    Line::from(Span::styled(
-                        "  Title",
-                        Style::default().fg(Color::DarkGray),
-                    )),
-                    Line::from(vec![
-                        Span::styled("  > ", Style::default().fg(Color::Cyan)),
-                        Span::styled(format!("{}_", title), title_style),
-                    ]),
-                    Line::from(""),
-                    Line::from(Span::styled(
-                        "  Description  (optional)",
-                        Style::default().fg(Color::DarkGray),
-                    )),
-                    Line::from(vec![
-                        Span::styled("  > ", Style::default().fg(Color::Cyan)),
-                        Span::styled(format!("{}_", description), desc_style),
-                    ]),
-                    Line::from(""),
-                    Line::from(""),
-                    Line::from(Span::styled(
-                        "  Tab → next field   Enter → save   Esc → cancel",
-                        Style::default().fg(Color::DarkGray),
-                    )),
-                ];
-
-                let form = Paragraph::new(form_lines)
-                    .block(
-                        Block::default()
-                            .title(" Add Task ")
-                            .borders(Borders::ALL)
-                            .border_style(Style::default().fg(Color::Yellow))
-                            .style(Style::default().bg(Color::Black)),
-                    );
-                frame.render_widget(form, horizontal[1]);
+            TasksMode::Adding(form) if form.parent_id.is_some() => {
+                render_form(frame, horizontal[1], form, &self.projects, " Add Subtask ")
             }
+            TasksMode::Adding(form) => render_form(frame, horizontal[1], form, &self.projects, " Add Task "),
+            TasksMode::Updating(form) => render_form(frame, horizontal[1], form, &self.projects, " Update Task "),
+            TasksMode::PickingTags(picker) => render_tag_picker(frame, horizontal[1], picker),
         }
 
         // ── Bottom: status / hint bar ────────────────────────────────
         let (status_text, status_color) = match &self.mode {
+            _ if self.error.is_some() => (
+                format!("  ✗ {}", self.error.as_deref().unwrap_or_default()),
+                Color::Red,
+            ),
+            TasksMode::View if self.notice.is_some() => (
+                format!("  ↶ {}   u → undo   U/Ctrl-r → redo", self.notice.as_deref().unwrap_or_default()),
+                Color::Cyan,
+            ),
+            TasksMode::View if self.filter.is_some() => (
+                "  ↑↓ navigate   Space/Enter → toggle   / → edit filter   Esc → clear filter   v → save as view   a → add   e → edit   d → delete   u → undo".to_string(),
+                Color::Green,
+            ),
             TasksMode::View => (
-                "  ↑↓ navigate   Space/Enter → toggle   a → add   u → update   d → delete   q/Esc → back".to_string(),
+                "  ↑↓ navigate   ←→ collapse/expand   Space/Enter → toggle   +/- → priority   a → add   s → subtask   e → edit   d → delete   u/U → undo/redo   q/Esc → back".to_string(),
                 Color::Green,
             ),
-            TasksMode::Adding { .. } => (
+            TasksMode::Searching => (
+                "  ↑↓ navigate   Enter: keep filter   Esc: clear   e.g. status:open tag:work due<7d title~deploy".to_string(),
+                Color::Yellow,
+            ),
+            TasksMode::Adding(_) => (
                 "  Adding task — Tab: switch field   Enter: save   Esc: cancel".to_string(),
                 Color::Yellow,
             ),
             TasksMode::ConfirmDelete => (
-                "  ⚠  Delete this task?   Enter → confirm   Esc/n → cancel".to_string(),
+                if self.selected_row().is_some_and(|r| r.children > 0) {
+                    "  ⚠  Delete this task and its subtasks?   Enter → confirm   Esc/n → cancel".to_string()
+                } else {
+                    "  ⚠  Delete this task?   Enter → confirm   Esc/n → cancel".to_string()
+                },
                 Color::Red,
             ),
-            TasksMode::Updating { .. } => (
+            TasksMode::ConfirmCompleteSubtasks(open) => (
+                format!("  Also complete {} open subtask(s)?   y/Enter → yes   n → only this task   Esc → cancel", open),
+                Color::Yellow,
+            ),
+            TasksMode::Updating(_) => (
                 "  Updating task   Enter → confirm   Esc/n → cancel".to_string(),
                 Color::Red,
             ),
+            TasksMode::PickingTags(_) => (
+                "  Filter by tags — Space: toggle   c: clear   Enter: apply   Esc: cancel".to_string(),
+                Color::Yellow,
+            ),
+            TasksMode::SavingView(name) => (
+                format!("  Save view as: {}_   Enter → save (replacing a view of that name)   Esc → cancel", name),
+                Color::Yellow,
+            ),
         };
 
-        let status = Paragraph::new(Line::from(Span::styled(status_text, Style::default().fg(status_color))))
+        let status_line = match &self.mode {
+            TasksMode::Searching if self.error.is_none() => self.filter_prompt(status_text),
+            _ => Line::from(Span::styled(status_text, Style::default().fg(status_color))),
+        };
+        let status = Paragraph::new(status_line)
             .block(Block::default()
                 .borders(Borders::ALL)
                 .border_style(Style::default().fg(Color::DarkGray))
                 .style(Style::default().bg(Color::Black)));
 
-        frame.render_widget(status, vertical[1]);
+        frame.render_widget(status, vertical[2]);
+    }
+}
+
+/// `text` in `style`, with the parts matching `search` picked out.
+fn highlighted(text: &str, search: &Search, style: Style) -> Vec<Span<'static>> {
+    search
+        .highlight(text)
+        .into_iter()
+        .map(|(piece, matched)| {
+            if matched {
+                Span::styled(
+                    piece.to_string(),
+                    style.fg(Color::Black).bg(Color::Yellow).add_modifier(Modifier::BOLD | Modifier::UNDERLINED),
+                )
+            } else {
+                Span::styled(piece.to_string(), style)
+            }
+        })
+        .collect()
+}
+
+fn task_details(task: &Task, history: &[&Task], search: &Search, now: NaiveDateTime) -> Vec<Line<'static>> {
+    let status_str = if task.completed { "✓  Completed" } else { "○  Pending" };
+    let status_color = if task.completed { Color::Green } else { Color::Magenta };
+    let desc = task.description.clone().unwrap_or_else(|| "No description.".to_string());
+
+    let due_span = match (&task.due, task.due_status(now)) {
+        (Some(due), Some(status)) => {
+            let label = match status {
+                DueStatus::Overdue => "overdue",
+                DueStatus::Today => "due today",
+                DueStatus::Upcoming => "upcoming",
+            };
+            Span::styled(
+                format!("{}  ({})", Task::format_due(due), label),
+                Style::default().fg(due_color(&status)),
+            )
+        }
+        (Some(due), None) => Span::raw(Task::format_due(due)),
+        (None, _) => Span::styled("—", Style::default().fg(Color::DarkGray)),
+    };
+
+    let mut lines = vec![
+        Line::from(vec![
+            Span::styled("  ID:     ", Style::default().fg(Color::DarkGray)),
+            Span::raw(task.id.unwrap_or(0).to_string()),
+        ]),
+        Line::from(""),
+        Line::from(
+            std::iter::once(Span::styled("  Title:  ", Style::default().fg(Color::DarkGray)))
+                .chain(highlighted(&task.title, search, Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)))
+                .collect::<Vec<_>>(),
+        ),
+        Line::from(""),
+        Line::from(vec![
+            Span::styled("  Status: ", Style::default().fg(Color::DarkGray)),
+            Span::styled(status_str, Style::default().fg(status_color)),
+        ]),
+        Line::from(""),
+        Line::from(vec![
+            Span::styled("  Due:    ", Style::default().fg(Color::DarkGray)),
+            due_span,
+        ]),
+        Line::from(""),
+        Line::from(vec![
+            Span::styled("  Priority: ", Style::default().fg(Color::DarkGray)),
+            priority_marker(task.priority),
+            Span::raw(task.priority.label()),
+        ]),
+        Line::from(""),
+        Line::from(
+            std::iter::once(Span::styled("  Tags:   ", Style::default().fg(Color::DarkGray)))
+                .chain(task.tags.iter().flat_map(|t| [tag_chip(t), Span::raw(" ")]))
+                .collect::<Vec<_>>(),
+        ),
+        Line::from(""),
+        Line::from(Span::styled("  Description:", Style::default().fg(Color::DarkGray))),
+        Line::from(
+            std::iter::once(Span::raw("  "))
+                .chain(highlighted(&desc, search, Style::default()))
+                .collect::<Vec<_>>(),
+        ),
+    ];
+
+    if !task.annotations.is_empty() {
+        lines.push(Line::from(""));
+        lines.push(Line::from(Span::styled("  Annotations:", Style::default().fg(Color::DarkGray))));
+        for annotation in &task.annotations {
+            let entry = annotation.entry.map(|e| e.format("%Y-%m-%d %H:%M").to_string()).unwrap_or_default();
+            lines.push(Line::from(vec![
+                Span::styled(format!("    {} ", entry), Style::default().fg(Color::DarkGray)),
+                Span::raw(annotation.description.clone()),
+            ]));
+        }
+    }
+    if let Some(rule) = &task.recurrence {
+        lines.push(Line::from(""));
+        lines.push(Line::from(vec![
+            Span::styled("  Repeats: ", Style::default().fg(Color::DarkGray)),
+            Span::styled(format!("↻ {}", rule), Style::default().fg(Color::Magenta)),
+        ]));
     }
+    if !history.is_empty() {
+        lines.push(Line::from(""));
+        lines.push(Line::from(Span::styled(
+            format!("  History ({} earlier occurrence(s)):", history.len()),
+            Style::default().fg(Color::DarkGray),
+        )));
+        for previous in history {
+            let mark = if previous.completed { "✓" } else { "○" };
+            let due = previous.due.as_ref().map(Task::format_due).unwrap_or_else(|| "no due date".to_string());
+            lines.push(Line::from(Span::styled(
+                format!("    {} {}", mark, due),
+                Style::default().fg(Color::DarkGray),
+            )));
+        }
+    }
+    lines
+}
+
+fn render_form(frame: &mut Frame, area: Rect, form: &TaskForm, projects: &[Project], heading: &str) {
+    let field_style = |field: FormField| {
+        if form.active_field == field {
+            Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)
+        } else {
+            Style::default().fg(Color::White)
+        }
+    };
+    let field = |label: &'static str, value: &str, field: FormField| {
+        vec![
+            Line::from(""),
+            Line::from(Span::styled(label, Style::default().fg(Color::DarkGray))),
+            Line::from(vec![
+                Span::styled("  > ", Style::default().fg(Color::Cyan)),
+                Span::styled(format!("{}_", value), field_style(field)),
+            ]),
+        ]
+    };
+
+    let mut form_lines = Vec::new();
+    form_lines.extend(field("  Title", &form.title, FormField::Title));
+    form_lines.extend(field("  Description  (optional)", &form.description, FormField::Description));
+    form_lines.extend(field("  Due  (optional: YYYY-MM-DD [HH:MM], today, tomorrow)", &form.due, FormField::Due));
+    form_lines.extend(field("  Repeat  (optional: daily, weekly:mon,fri, monthly:15, after:3)", &form.repeat, FormField::Repeat));
+    form_lines.extend(field("  Tags  (optional, comma-separated)", &form.tags, FormField::Tags));
+    let project_name = projects
+        .iter()
+        .find(|p| p.id == Some(form.project_id))
+        .map(|p| p.name.as_str())
+        .unwrap_or("Inbox");
+    form_lines.extend(vec![
+        Line::from(""),
+        Line::from(Span::styled("  Project  (←/→ to change)", Style::default().fg(Color::DarkGray))),
+        Line::from(vec![
+            Span::styled("  > ", Style::default().fg(Color::Cyan)),
+            Span::styled(format!("◀ {} ▶", project_name), field_style(FormField::Project)),
+        ]),
+    ]);
+    form_lines.push(Line::from(""));
+    form_lines.push(Line::from(""));
+    form_lines.push(Line::from(Span::styled(
+        "  Tab → next field   Enter → save   Esc → cancel",
+        Style::default().fg(Color::DarkGray),
+    )));
+
+    let form = Paragraph::new(form_lines)
+        .wrap(Wrap { trim: false })
+        .block(
+            Block::default()
+                .title(heading)
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::Yellow))
+                .style(Style::default().bg(Color::Black)),
+        );
+    frame.render_widget(form, area);
+}
+
+fn render_tag_picker(frame: &mut Frame, area: Rect, picker: &TagPicker) {
+    let mut lines = vec![
+        Line::from(""),
+        Line::from(Span::styled(
+            "  Show tasks carrying all of the checked tags:",
+            Style::default().fg(Color::DarkGray),
+        )),
+        Line::from(""),
+    ];
+    if picker.tags.is_empty() {
+        lines.push(Line::from(Span::styled(
+            "  (no tags yet — add some from the task form)",
+            Style::default().fg(Color::DarkGray),
+        )));
+    }
+    for (i, tag) in picker.tags.iter().enumerate() {
+        let cursor = if picker.state.selected() == Some(i) { ">> " } else { "   " };
+        let checked = picker.chosen.iter().any(|t| t.eq_ignore_ascii_case(&tag.name));
+        lines.push(Line::from(vec![
+            Span::styled(cursor, Style::default().fg(Color::Cyan)),
+            Span::raw(if checked { "[x] " } else { "[ ] " }),
+            tag_chip(&tag.name),
+            Span::styled(format!("  ({})", tag.task_count), Style::default().fg(Color::DarkGray)),
+        ]));
+    }
+
+    let picker = Paragraph::new(lines)
+        .block(
+            Block::default()
+                .title(" Filter by Tags ")
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::Yellow))
+                .style(Style::default().bg(Color::Black)),
+        );
+    frame.render_widget(picker, area);
 }